@@ -0,0 +1,290 @@
+//! Allowances actions module
+//!
+//! Allows holders to let other accounts, such as escrows, OTC desks
+//! or vaults, move up to an amount of their shares without taking
+//! custody of them. Holders set the allowance of each spender with
+//! ft_approve, paying for its storage, which is refunded when the
+//! allowance is revoked or used up.
+//!
+//! ft_transfer_from moves shares through the same path as ft_transfer,
+//! settling the rewards of both the holder and the receiver.
+//!
+//! Allowances are amounts in the share unit they were granted in, so
+//! a split voids every allowance granted before it.
+
+use crate::compliance::TransferParty;
+use crate::events::ShareAllowanceUpdated;
+use crate::*;
+
+#[near_bindgen]
+impl Contract {
+    /// Allows spender_id to transfer up to amount shares of the caller,
+    /// replacing its previous allowance. 0 revokes the allowance.
+    /// Caller pays for the storage of the allowance, the excess is refunded.
+    #[payable]
+    pub fn ft_approve(&mut self, spender_id: AccountId, amount: U128) {
+        assert!(
+            env::attached_deposit() >= 1,
+            "Requires attached deposit of at least 1 yoctoNEAR"
+        );
+        let owner_id = env::predecessor_account_id();
+        assert!(
+            self.accounts_rps.contains_key(&owner_id),
+            "The account {} is not registered",
+            owner_id
+        );
+        assert!(owner_id != spender_id, "Cannot approve own account");
+        let initial_storage_usage = env::storage_usage();
+        self.internal_set_allowance(&owner_id, &spender_id, amount.0);
+        if env::storage_usage() >= initial_storage_usage {
+            self.charge_storage_deposit(initial_storage_usage, &owner_id);
+        } else {
+            let storage_released = initial_storage_usage - env::storage_usage();
+            Promise::new(owner_id).transfer(
+                env::attached_deposit()
+                    + Balance::from(storage_released) * env::storage_byte_cost(),
+            );
+        }
+    }
+
+    /// Shares of owner_id that spender_id can still transfer
+    pub fn ft_allowance(&self, owner_id: AccountId, spender_id: AccountId) -> U128 {
+        U128(self.share_allowances.get(&owner_id).map_or(0, |allowances| {
+            allowances.allowance(&spender_id, self.splits_count)
+        }))
+    }
+
+    /// Transfers amount shares of owner_id to receiver_id out of the
+    /// allowance of the caller
+    #[payable]
+    pub fn ft_transfer_from(
+        &mut self,
+        owner_id: AccountId,
+        receiver_id: AccountId,
+        amount: U128,
+        memo: Option<String>,
+    ) {
+        assert_one_yocto();
+        self.assert_not_paused(&PauseFeature::Transfers);
+        self.assert_not_escrow(&receiver_id);
+        let spender_id = env::predecessor_account_id();
+        let allowance = self.ft_allowance(owner_id.clone(), spender_id.clone()).0;
+        assert!(
+            amount.0 <= allowance,
+            "Allowance of {} over shares of {} is {}",
+            spender_id,
+            owner_id,
+            allowance
+        );
+        assert!(
+            self.accounts_rps.contains_key(&receiver_id),
+            "{} must be registered to receive shares, see storage_deposit",
+            receiver_id
+        );
+        self.assert_party_allowed(&spender_id, TransferParty::Spender);
+        self.assert_transfer_allowed(&owner_id, &receiver_id);
+        let initial_storage_usage = env::storage_usage();
+        self.internal_set_allowance(&owner_id, &spender_id, allowance - amount.0);
+        self.refund_storage_release(initial_storage_usage, &owner_id);
+        self.internal_transfer_shares(&owner_id, &receiver_id, amount.0, memo);
+    }
+}
+
+impl Contract {
+    /// Stores the allowance of spender_id over shares of owner_id,
+    /// removing it when 0 along with allowances voided by a split
+    pub fn internal_set_allowance(&mut self, owner_id: &AccountId, spender_id: &AccountId, amount: u128) {
+        let mut allowances = self
+            .share_allowances
+            .get(owner_id)
+            .filter(|allowances| allowances.splits_count == self.splits_count)
+            .unwrap_or_else(|| ShareAllowances::new(self.splits_count));
+        if amount > 0 {
+            allowances.spenders.insert(spender_id.clone(), U128(amount));
+        } else {
+            allowances.spenders.remove(spender_id);
+        }
+        if allowances.spenders.is_empty() {
+            self.share_allowances.remove(owner_id);
+        } else {
+            self.share_allowances.insert(owner_id, &allowances);
+        }
+        ShareAllowanceUpdated {
+            owner_id,
+            spender_id,
+            amount: &U128(amount),
+        }
+        .emit();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::tests::*;
+
+    #[rstest]
+    /// Test ft_approve and ft_transfer_from methods
+    /// ASSERT:
+    /// (1) Spender cannot exceed its allowance
+    #[should_panic = "Allowance of admin.testnet over shares of user.testnet is 50"]
+    #[case(50, 51)]
+    /// (2) Shares move out of the allowance, settling rewards of both
+    ///     sides, and used up allowances are removed
+    #[case(50, 30)]
+    #[case(50, 50)]
+    fn test_ft_transfer_from(#[case] allowance: u128, #[case] amount: u128) {
+        // setup
+        let context = get_context(
+            vec![],
+            10u128.pow(24),
+            10u128.pow(24),
+            USER_ACCOUNT.parse().unwrap(),
+            0,
+            Gas(300u64 * 10u64.pow(12)),
+        );
+        testing_env!(context.clone());
+        let mut contract = init_contract(1);
+        let user: AccountId = USER_ACCOUNT.parse().unwrap();
+        let spender: AccountId = ADMIN_ACCOUNT.parse().unwrap();
+        let new_user: AccountId = NEW_USER_ACCOUNT.parse().unwrap();
+        register_user(&mut contract, &user, 100, 0, 0);
+        register_user(&mut contract, &new_user, 0, 0, 0);
+        contract.ft_approve(spender.clone(), U128(allowance));
+        assert_eq!(contract.ft_allowance(user.clone(), spender.clone()), U128(allowance));
+        assert!(get_logs()[0].contains(r#""event":"share_allowance_updated""#));
+        let total_supply = contract.ft_total_supply().0;
+        contract.distribute_rewards_token(total_supply);
+
+        // call tested method
+        testing_env!(VMContext {
+            predecessor_account_id: spender.clone(),
+            attached_deposit: 1,
+            storage_usage: env::storage_usage(),
+            ..context
+        });
+        contract.ft_transfer_from(user.clone(), new_user.clone(), U128(amount), None);
+
+        // perform assertions
+        assert_eq!(contract.ft_balance_of(user.clone()), U128(100 - amount));
+        assert_eq!(contract.ft_balance_of(new_user.clone()), U128(amount));
+        assert_eq!(
+            contract.ft_allowance(user.clone(), spender.clone()),
+            U128(allowance - amount)
+        );
+        assert_eq!(
+            contract.share_allowances.get(&user).is_none(),
+            allowance == amount
+        );
+        let rewards = |contract: &Contract, account: AccountId| {
+            contract.view_claimable_rewards(account)[REWARDS_TOKEN_ACCOUNT].0
+        };
+        assert_eq!(rewards(&contract, user), 100);
+        assert_eq!(rewards(&contract, new_user), 0);
+        let receipts = get_created_receipts();
+        assert_eq!(receipts.len(), if allowance == amount { 1 } else { 0 });
+    }
+
+    #[rstest]
+    /// Test ft_approve method
+    /// ASSERT:
+    /// (1) Storage of the allowance is charged and the excess refunded
+    /// (2) Revoking the allowance refunds its storage
+    fn test_ft_approve() {
+        // setup
+        let context = get_context(
+            vec![],
+            10u128.pow(24),
+            10u128.pow(24),
+            USER_ACCOUNT.parse().unwrap(),
+            0,
+            Gas(300u64 * 10u64.pow(12)),
+        );
+        testing_env!(context.clone());
+        let mut contract = init_contract(1);
+        let user: AccountId = USER_ACCOUNT.parse().unwrap();
+        let spender: AccountId = ADMIN_ACCOUNT.parse().unwrap();
+        register_user(&mut contract, &user, 100, 0, 0);
+
+        // call tested method
+        let initial_storage_usage = env::storage_usage();
+        contract.ft_approve(spender.clone(), U128(10));
+
+        // perform assertions
+        let storage_cost =
+            Balance::from(env::storage_usage() - initial_storage_usage) * env::storage_byte_cost();
+        assert!(storage_cost > 0);
+        let refund = |receipts: Vec<near_sdk::mock::Receipt>| match receipts[0].actions[0].clone() {
+            VmAction::Transfer { deposit } => deposit,
+            _ => panic!(),
+        };
+        assert_eq!(refund(get_created_receipts()), 10u128.pow(24) - storage_cost);
+
+        testing_env!(VMContext {
+            attached_deposit: 1,
+            storage_usage: env::storage_usage(),
+            ..context
+        });
+        contract.ft_approve(spender.clone(), U128(0));
+        assert_eq!(contract.ft_allowance(user, spender), U128(0));
+        assert_eq!(refund(get_created_receipts()), 1 + storage_cost);
+    }
+
+    #[rstest]
+    /// Test ft_transfer_from method after a split
+    /// ASSERT:
+    /// (1) Allowances granted before a split are void
+    #[should_panic = "Allowance of admin.testnet over shares of user.testnet is 0"]
+    #[case(false)]
+    /// (2) Allowances granted after the split can be used
+    #[case(true)]
+    fn test_ft_transfer_from_after_split(#[case] approve_again: bool) {
+        // setup
+        let context = get_context(
+            vec![],
+            10u128.pow(24),
+            10u128.pow(24),
+            USER_ACCOUNT.parse().unwrap(),
+            0,
+            Gas(300u64 * 10u64.pow(12)),
+        );
+        testing_env!(context.clone());
+        let mut contract = init_contract(1);
+        let user: AccountId = USER_ACCOUNT.parse().unwrap();
+        let new_user: AccountId = NEW_USER_ACCOUNT.parse().unwrap();
+        let spender: AccountId = ADMIN_ACCOUNT.parse().unwrap();
+        register_user(&mut contract, &user, 100, 0, 0);
+        register_user(&mut contract, &new_user, 0, 0, 0);
+        contract.ft_approve(spender.clone(), U128(100));
+        testing_env!(VMContext {
+            predecessor_account_id: OWNER_ACCOUNT.parse().unwrap(),
+            attached_deposit: 1,
+            storage_usage: env::storage_usage(),
+            ..context.clone()
+        });
+        contract.split(1, 10, Some(U128(1)));
+        assert_eq!(contract.ft_allowance(user.clone(), spender.clone()), U128(0));
+        if approve_again {
+            testing_env!(VMContext {
+                storage_usage: env::storage_usage(),
+                ..context.clone()
+            });
+            contract.ft_approve(spender.clone(), U128(10));
+        }
+
+        // call tested method
+        testing_env!(VMContext {
+            predecessor_account_id: spender.clone(),
+            attached_deposit: 1,
+            storage_usage: env::storage_usage(),
+            ..context
+        });
+        contract.ft_transfer_from(user.clone(), new_user.clone(), U128(10), None);
+
+        // perform assertions
+        assert_eq!(contract.ft_balance_of(user.clone()), U128(0));
+        assert_eq!(contract.ft_balance_of(new_user), U128(10));
+        assert!(contract.share_allowances.get(&user).is_none());
+    }
+}
@@ -0,0 +1,479 @@
+//! Auction actions module
+//!
+//! Allows admins to run a descending price (dutch) auction of shares
+//! for price discovery. The price decreases linearly from start_price
+//! to floor_price during the auction window.
+//!
+//! The issuer escrows the auctioned shares by calling ft_transfer_call
+//! on this contract with receiver_id being the contract itself and
+//! msg "fund_auction". Registered bidders commit funds either by
+//! calling ft_transfer_call on the reward token with msg "bid_auction"
+//! or by attaching NEAR to bid_auction. Bids are accepted as long as
+//! all bids are worth less than the supply at the current price.
+//!
+//! Once bids reach that value or the window ends, every bidder buys
+//! at the same clearing price. settle_auction computes each bidder's
+//! shares and refund, returns unsold shares to the issuer and sends
+//! the proceeds to the treasury. Each bidder then collects shares and
+//! excess funds through claim_auction.
+
+use crate::auction::AuctionConfig;
+use crate::compliance::TransferParty;
+use crate::events::{AuctionBidPlaced, AuctionCreated, AuctionSettled};
+use crate::ext_interface::{ext_ft, ext_self, FT_TRANSFER_GAS, REWARD_WITHDRAW_CALLBACK_GAS};
+use crate::sale::SaleCurrency;
+use crate::*;
+
+/// Bidders settled per settle_auction call by default
+const DEFAULT_SETTLE_LIMIT: u64 = 100;
+
+#[near_bindgen]
+impl Contract {
+    /// Creates an auction, only one auction can run at a time.
+    /// Returns the id of the auction.
+    #[payable]
+    pub fn create_auction(&mut self, config: AuctionConfig) -> u64 {
+        assert_one_yocto();
+        self.assert_role(&Role::Admin);
+        self.assert_no_split();
+        self.assert_no_buyout();
+        assert!(
+            self.auction.as_ref().is_none_or(Auction::is_settled),
+            "An auction is already running"
+        );
+        config.assert_valid();
+        let id = self.auctions_count;
+        self.auctions_count += 1;
+        AuctionCreated {
+            auction_id: id,
+            config: &config,
+        }
+        .emit();
+        self.auction = Some(Auction {
+            id,
+            config,
+            funded: false,
+            committed: U128(0),
+            bidders: 0,
+            clearing_price: None,
+            settled_bidders: 0,
+            sold: U128(0),
+            proceeds: U128(0),
+        });
+        id
+    }
+
+    /// Bids the attached NEAR in an auction paid in NEAR, the part
+    /// exceeding the value of the supply at the current price is refunded
+    #[payable]
+    pub fn bid_auction(&mut self) {
+        let bidder = env::predecessor_account_id();
+        let refund =
+            self.internal_bid_auction(bidder.clone(), env::attached_deposit(), SaleCurrency::Near);
+        if refund > 0 {
+            Promise::new(bidder).transfer(refund);
+        }
+    }
+
+    /// Computes the shares and refund of up to limit bidders. Once every
+    /// bidder is settled, returns unsold shares to the issuer and sends
+    /// proceeds to the treasury. Callable by anyone once the auction
+    /// has cleared or ended.
+    pub fn settle_auction(&mut self, limit: Option<u64>) {
+        self.assert_not_paused(&PauseFeature::Transfers);
+        let mut auction = self.auction.clone().expect("No auction is running");
+        assert!(!auction.is_settled(), "Auction is already settled");
+        let clearing_price = match auction.clearing_price {
+            Some(price) => price.0,
+            None => {
+                let price = auction
+                    .compute_clearing_price(env::block_timestamp())
+                    .expect("Auction has neither cleared nor ended");
+                auction.clearing_price = Some(U128(price));
+                price
+            }
+        };
+        let last = std::cmp::min(
+            auction.bidders,
+            auction.settled_bidders + limit.unwrap_or(DEFAULT_SETTLE_LIMIT),
+        );
+        for index in auction.settled_bidders..last {
+            let bidder = self.auction_bidders.remove(&(auction.id, index)).unwrap();
+            let key = (auction.id, bidder);
+            let mut bid = self.auction_bids.get(&key).unwrap();
+            let shares = bid.committed.0 / clearing_price;
+            bid.shares = U128(shares);
+            bid.refund = U128(bid.committed.0 - shares * clearing_price);
+            self.auction_bids.insert(&key, &bid);
+            auction.sold = U128(auction.sold.0 + shares);
+            auction.proceeds = U128(auction.proceeds.0 + shares * clearing_price);
+        }
+        auction.settled_bidders = last;
+
+        if auction.is_settled() {
+            let unsold = if auction.funded {
+                auction.config.supply.0 - auction.sold.0
+            } else {
+                0
+            };
+            if unsold > 0 {
+                self.internal_release_from_escrow(
+                    &auction.config.issuer,
+                    unsold,
+                    "unsold auction shares",
+                );
+            }
+            self.internal_send_proceeds(
+                auction.config.currency,
+                &auction.config.treasury,
+                auction.proceeds.0,
+            );
+            AuctionSettled {
+                auction_id: auction.id,
+                clearing_price: &U128(clearing_price),
+                sold: &auction.sold,
+                proceeds: &auction.proceeds,
+            }
+            .emit();
+        }
+        self.auction = Some(auction);
+    }
+
+    /// Transfers the caller's shares and excess funds of a settled auction
+    pub fn claim_auction(&mut self, auction_id: u64) {
+        self.assert_not_paused(&PauseFeature::Transfers);
+        if let Some(auction) = &self.auction {
+            assert!(
+                auction.id != auction_id || auction.is_settled(),
+                "Auction is not settled yet"
+            );
+        }
+        let account_id = env::predecessor_account_id();
+        let bid = self
+            .auction_bids
+            .remove(&(auction_id, account_id.clone()))
+            .expect("No bid to claim");
+        // refunds are paid whatever the restrictions on the bidder
+        if bid.shares.0 > 0 {
+            self.assert_party_allowed(&account_id, TransferParty::Receiver);
+            self.internal_release_from_escrow(&account_id, bid.shares.0, "auction");
+        }
+        if bid.refund.0 > 0 {
+            match bid.currency {
+                SaleCurrency::Near => {
+                    Promise::new(account_id).transfer(bid.refund.0);
+                }
+                // refunds failing to transfer become claimable rewards
+                SaleCurrency::RewardToken => {
+                    ext_ft::ext(self.reward_token.clone())
+                        .with_static_gas(FT_TRANSFER_GAS)
+                        .with_attached_deposit(1)
+                        .ft_transfer(account_id.clone(), bid.refund, None)
+                        .then(
+                            ext_self::ext(env::current_account_id())
+                                .with_static_gas(REWARD_WITHDRAW_CALLBACK_GAS)
+                                .resolve_reward_transfer(account_id, bid.refund),
+                        );
+                }
+            }
+        }
+    }
+
+    pub fn view_auction(&self) -> Option<Auction> {
+        self.auction.clone()
+    }
+
+    /// Current price of one share in the running auction
+    pub fn view_auction_price(&self) -> U128 {
+        let auction = self.auction.as_ref().expect("No auction is running");
+        U128(auction.config.price_at(env::block_timestamp()))
+    }
+
+    pub fn view_auction_bid(&self, auction_id: u64, account_id: AccountId) -> Option<AuctionBid> {
+        self.auction_bids.get(&(auction_id, account_id))
+    }
+}
+
+impl Contract {
+    /// Marks the auction as funded with the shares received from sender_id
+    pub fn internal_fund_auction(&mut self, sender_id: AccountId, amount: U128) -> U128 {
+        let timestamp = env::block_timestamp();
+        let auction = self.auction.as_mut().expect("No auction is running");
+        assert!(
+            sender_id == auction.config.issuer,
+            "Only the issuer can fund the auction"
+        );
+        assert!(!auction.funded, "Auction is already funded");
+        assert!(
+            amount == auction.config.supply,
+            "Must fund exactly {} shares",
+            auction.config.supply.0
+        );
+        assert!(timestamp < auction.config.end.0, "Auction has ended");
+        auction.funded = true;
+        U128(0)
+    }
+
+    /// Records the part of payment that the auction can still accept.
+    /// Returns the part of payment to be refunded.
+    pub fn internal_bid_auction(
+        &mut self,
+        bidder: AccountId,
+        payment: u128,
+        currency: SaleCurrency,
+    ) -> u128 {
+        self.assert_not_paused(&PauseFeature::Transfers);
+        let timestamp = env::block_timestamp();
+        let mut auction = self.auction.clone().expect("No auction is running");
+        assert!(
+            auction.config.currency == currency,
+            "Auction is paid in {:?}",
+            auction.config.currency
+        );
+        assert!(auction.funded, "Auction is not funded");
+        assert!(
+            timestamp >= auction.config.start.0 && timestamp < auction.config.end.0,
+            "Auction is not open"
+        );
+        assert!(!auction.has_cleared(timestamp), "Auction has cleared");
+        assert!(
+            self.accounts_rps.contains_key(&bidder),
+            "{} must be registered to bid, see storage_deposit",
+            bidder
+        );
+        self.assert_party_allowed(&bidder, TransferParty::Receiver);
+        let price = auction.config.price_at(timestamp);
+        let capacity = auction.config.supply.0 * price - auction.committed.0;
+        let accepted = std::cmp::min(payment, capacity);
+        auction.committed = U128(auction.committed.0 + accepted);
+
+        let key = (auction.id, bidder.clone());
+        let mut bid = self.auction_bids.get(&key).unwrap_or_else(|| {
+            self.auction_bidders
+                .insert(&(auction.id, auction.bidders), &bidder);
+            auction.bidders += 1;
+            AuctionBid {
+                currency,
+                committed: U128(0),
+                shares: U128(0),
+                refund: U128(0),
+            }
+        });
+        bid.committed = U128(bid.committed.0 + accepted);
+        self.auction_bids.insert(&key, &bid);
+        AuctionBidPlaced {
+            auction_id: auction.id,
+            account_id: &bidder,
+            amount: &U128(accepted),
+            price: &U128(price),
+        }
+        .emit();
+        self.auction = Some(auction);
+        payment - accepted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::tests::*;
+    use near_sdk::json_types::U64;
+
+    /// Auction of 100 shares from price 20 at timestamp 100
+    /// down to price 10 at timestamp 200
+    fn auction_config(currency: SaleCurrency) -> AuctionConfig {
+        AuctionConfig {
+            issuer: OWNER_ACCOUNT.parse().unwrap(),
+            treasury: ADMIN_ACCOUNT.parse().unwrap(),
+            currency,
+            supply: U128(100),
+            start_price: U128(20),
+            floor_price: U128(10),
+            start: U64(100),
+            end: U64(200),
+        }
+    }
+
+    /// Creates an auction funded by the issuer
+    fn setup_auction(contract: &mut Contract, currency: SaleCurrency) {
+        let owner: AccountId = OWNER_ACCOUNT.parse().unwrap();
+        let escrow: AccountId = CONTRACT_ACCOUNT.parse().unwrap();
+        contract.auction = Some(Auction {
+            id: 0,
+            config: auction_config(currency),
+            funded: false,
+            committed: U128(0),
+            bidders: 0,
+            clearing_price: None,
+            settled_bidders: 0,
+            sold: U128(0),
+            proceeds: U128(0),
+        });
+        contract.auctions_count = 1;
+        // simulate ft_transfer_call of the auctioned shares to the contract
+        contract
+            .ft_functionality
+            .internal_transfer(&owner, &escrow, 100, None);
+        contract.internal_update_holder(&escrow);
+        contract.internal_fund_auction(owner, U128(100));
+    }
+
+    fn set_time(context: &VMContext, predecessor: &str, timestamp: u64) {
+        testing_env!(VMContext {
+            predecessor_account_id: predecessor.parse().unwrap(),
+            block_timestamp: timestamp,
+            storage_usage: env::storage_usage(),
+            ..context.clone()
+        });
+    }
+
+    #[rstest]
+    /// Test create_auction method
+    /// ASSERT:
+    /// (1) Only admins can create auctions
+    #[should_panic = "user.testnet does not have the admin role"]
+    #[case(USER_ACCOUNT, auction_config(SaleCurrency::Near))]
+    /// (2) Terms are validated
+    #[should_panic = "start_price cannot be lower than floor_price"]
+    #[case(OWNER_ACCOUNT, AuctionConfig { start_price: U128(5), ..auction_config(SaleCurrency::Near) })]
+    /// (3) Auction is created and its price decreases linearly
+    #[case(OWNER_ACCOUNT, auction_config(SaleCurrency::Near))]
+    fn test_create_auction(#[case] caller: &str, #[case] config: AuctionConfig) {
+        // setup
+        let context = get_context(
+            vec![],
+            1,
+            0,
+            caller.parse().unwrap(),
+            0,
+            Gas(300u64 * 10u64.pow(12)),
+        );
+        testing_env!(context.clone());
+        let mut contract = init_contract(1);
+
+        // call tested method
+        let id = contract.create_auction(config);
+
+        // perform assertions
+        assert_eq!(id, 0);
+        assert!(get_logs()[0].contains(r#""event":"auction_created""#));
+        assert_eq!(contract.view_auction_price(), U128(20));
+        set_time(&context, caller, 150);
+        assert_eq!(contract.view_auction_price(), U128(15));
+        set_time(&context, caller, 250);
+        assert_eq!(contract.view_auction_price(), U128(10));
+    }
+
+    #[rstest]
+    /// Test bids, settle_auction and claim_auction methods
+    /// ASSERT:
+    /// (1) Bids are only accepted while the auction is open
+    #[should_panic = "Auction is not open"]
+    #[case(SaleCurrency::Near, 50, vec![100], 300)]
+    /// (2) Auction cannot be settled before clearing or ending
+    #[should_panic = "Auction has neither cleared nor ended"]
+    #[case(SaleCurrency::Near, 150, vec![100], 150)]
+    /// (3) Bids reaching the supply value clear the auction, every
+    ///     bidder buys at the uniform price and the excess of the
+    ///     last bid is refunded immediately
+    #[case(SaleCurrency::Near, 150, vec![700, 1000], 150)]
+    #[case(SaleCurrency::RewardToken, 150, vec![700, 1000], 150)]
+    /// (4) When the auction ends without clearing, shares sell at the
+    ///     floor price and unsold shares return to the issuer
+    #[case(SaleCurrency::RewardToken, 150, vec![305, 400], 200)]
+    fn test_auction(
+        #[case] currency: SaleCurrency,
+        #[case] bid_timestamp: u64,
+        #[case] bids: Vec<u128>,
+        #[case] settle_timestamp: u64,
+    ) {
+        // setup
+        let context = get_context(
+            vec![],
+            0,
+            10u128.pow(24),
+            OWNER_ACCOUNT.parse().unwrap(),
+            0,
+            Gas(300u64 * 10u64.pow(12)),
+        );
+        testing_env!(context.clone());
+        let mut contract = init_contract(1);
+        let bidders: Vec<AccountId> = vec![
+            USER_ACCOUNT.parse().unwrap(),
+            NEW_USER_ACCOUNT.parse().unwrap(),
+        ];
+        for bidder in bidders.iter() {
+            register_user(&mut contract, bidder, 0, 0, 0);
+        }
+        setup_auction(&mut contract, currency);
+
+        // call tested methods
+        let mut immediate_refunds = vec![];
+        for (bidder, payment) in bidders.iter().zip(bids.iter()) {
+            immediate_refunds.push(match currency {
+                SaleCurrency::Near => {
+                    testing_env!(VMContext {
+                        predecessor_account_id: bidder.clone(),
+                        attached_deposit: *payment,
+                        block_timestamp: bid_timestamp,
+                        storage_usage: env::storage_usage(),
+                        ..context.clone()
+                    });
+                    contract.bid_auction();
+                    get_created_receipts().len()
+                }
+                SaleCurrency::RewardToken => {
+                    set_time(&context, REWARDS_TOKEN_ACCOUNT, bid_timestamp);
+                    contract.internal_bid_auction(bidder.clone(), *payment, currency) as usize
+                }
+            });
+        }
+        set_time(&context, USER_ACCOUNT, settle_timestamp);
+        contract.settle_auction(Some(1));
+        assert!(!contract.view_auction().unwrap().is_settled());
+        contract.settle_auction(None);
+        let settle_logs = get_logs();
+        for bidder in bidders.iter() {
+            set_time(&context, bidder.as_str(), settle_timestamp);
+            contract.claim_auction(0);
+        }
+
+        // perform assertions
+        let auction = contract.view_auction().unwrap();
+        assert!(auction.is_settled());
+        let (clearing_price, shares, sold) = if settle_timestamp == 150 {
+            // bids cleared at the price of 15: 1500 committed for 100 shares,
+            // first bid gets 46 shares refunding 10, second 53 refunding 5
+            (15, vec![46, 53], 99)
+        } else {
+            // 705 committed at floor price of 10, first bid refunds 5
+            (10, vec![30, 40], 70)
+        };
+        assert_eq!(auction.clearing_price, Some(U128(clearing_price)));
+        assert_eq!(auction.sold, U128(sold));
+        assert_eq!(auction.proceeds, U128(sold * clearing_price));
+        for (bidder, expected) in bidders.iter().zip(shares.iter()) {
+            assert_eq!(contract.ft_balance_of(bidder.clone()).0, *expected);
+            assert!(contract.view_auction_bid(0, bidder.clone()).is_none());
+        }
+        assert_eq!(
+            contract.ft_balance_of(OWNER_ACCOUNT.parse().unwrap()).0,
+            TOKEN_SUPPLY.0 - sold
+        );
+        assert_eq!(
+            contract.ft_balance_of(CONTRACT_ACCOUNT.parse().unwrap()).0,
+            0
+        );
+        if settle_timestamp == 150 {
+            // excess of the second bid over the supply value is refunded
+            match currency {
+                SaleCurrency::Near => assert_eq!(immediate_refunds[1], 1),
+                SaleCurrency::RewardToken => assert_eq!(immediate_refunds[1], 200),
+            }
+        }
+        assert!(settle_logs
+            .iter()
+            .any(|log| log.contains(r#""event":"auction_settled""#)));
+    }
+}
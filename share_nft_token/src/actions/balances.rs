@@ -0,0 +1,304 @@
+//! Balances actions module
+//!
+//! Every change of share balances, whether transfers, mints or burns,
+//! goes through internal_change_balances, so that each one is
+//! reflected in the same order:
+//! 1. rewards of the accounts involved are settled at their old balances
+//! 2. balances and supply change
+//! 3. the NEP-141 transfer, mint and burn events are logged
+//! 4. the NFTs of accounts left without shares are burned, revoking
+//!    their approvals, then the NFTs of accounts receiving their first
+//!    shares are minted
+//! 5. holders are updated, logging tier changes
+//!
+//! Changes applied together log a single combined event of each type.
+//! Callers are responsible for any check specific to their operation,
+//! such as pauses, supply caps or storage registration.
+
+use crate::balances::BalanceChange;
+use crate::*;
+use near_contract_standards::fungible_token::events::FtTransfer;
+
+impl Contract {
+    /// Transfers amount shares from sender_id to receiver_id
+    pub fn internal_transfer_shares(
+        &mut self,
+        sender_id: &AccountId,
+        receiver_id: &AccountId,
+        amount: u128,
+        memo: Option<String>,
+    ) {
+        self.internal_change_balances(&[BalanceChange::transfer(
+            sender_id,
+            receiver_id,
+            amount,
+            memo.as_deref(),
+        )]);
+    }
+
+    /// Issues amount new shares to account_id, which must be registered
+    pub fn internal_deposit_shares(&mut self, account_id: &AccountId, amount: u128, memo: Option<&str>) {
+        self.internal_change_balances(&[BalanceChange::mint(account_id, amount, memo)]);
+    }
+
+    /// Destroys amount shares of account_id
+    pub fn internal_burn_shares(&mut self, account_id: &AccountId, amount: u128, memo: Option<&str>) {
+        self.internal_change_balances(&[BalanceChange::burn(account_id, amount, memo)]);
+    }
+
+    /// Applies every change in order, logging the resulting events
+    pub fn internal_change_balances(&mut self, changes: &[BalanceChange]) {
+        let mut accounts: Vec<&AccountId> = Vec::new();
+        for change in changes.iter() {
+            change.assert_valid();
+            for account_id in change.sender_id.into_iter().chain(change.receiver_id) {
+                if !accounts.contains(&account_id) {
+                    accounts.push(account_id);
+                }
+            }
+        }
+        for account_id in accounts.iter() {
+            self.update_user_rps(account_id);
+        }
+        let were_holders: Vec<bool> = accounts
+            .iter()
+            .map(|account_id| self.ft_functionality.accounts.get(account_id).unwrap_or(0) > 0)
+            .collect();
+
+        for change in changes.iter() {
+            if let Some(sender_id) = change.sender_id {
+                self.ft_functionality.internal_withdraw(sender_id, change.amount);
+            }
+            if let Some(receiver_id) = change.receiver_id {
+                self.ft_functionality.internal_deposit(receiver_id, change.amount);
+            }
+        }
+
+        let amounts: Vec<U128> = changes.iter().map(|change| U128(change.amount)).collect();
+        let transfers: Vec<FtTransfer> = changes
+            .iter()
+            .zip(amounts.iter())
+            .filter_map(|(change, amount)| match (change.sender_id, change.receiver_id) {
+                (Some(old_owner_id), Some(new_owner_id)) => Some(FtTransfer {
+                    old_owner_id,
+                    new_owner_id,
+                    amount,
+                    memo: change.memo,
+                }),
+                _ => None,
+            })
+            .collect();
+        if !transfers.is_empty() {
+            FtTransfer::emit_many(&transfers);
+        }
+        let mints: Vec<FtMint> = changes
+            .iter()
+            .zip(amounts.iter())
+            .filter_map(|(change, amount)| match (change.sender_id, change.receiver_id) {
+                (None, Some(owner_id)) => Some(FtMint {
+                    owner_id,
+                    amount,
+                    memo: change.memo,
+                }),
+                _ => None,
+            })
+            .collect();
+        if !mints.is_empty() {
+            FtMint::emit_many(&mints);
+        }
+        let burns: Vec<FtBurn> = changes
+            .iter()
+            .zip(amounts.iter())
+            .filter_map(|(change, amount)| match (change.sender_id, change.receiver_id) {
+                (Some(owner_id), None) => Some(FtBurn {
+                    owner_id,
+                    amount,
+                    memo: change.memo,
+                }),
+                _ => None,
+            })
+            .collect();
+        if !burns.is_empty() {
+            FtBurn::emit_many(&burns);
+        }
+
+        let token_ids: Vec<[&str; 1]> = accounts
+            .iter()
+            .map(|account_id| [account_id.as_str()])
+            .collect();
+        let mut nft_burns = Vec::new();
+        let mut nft_mints = Vec::new();
+        for ((account_id, was_holder), token_ids) in
+            accounts.iter().zip(were_holders).zip(token_ids.iter())
+        {
+            let is_holder = self.ft_functionality.accounts.get(account_id).unwrap_or(0) > 0;
+            if was_holder && !is_holder {
+                let transferred = changes
+                    .iter()
+                    .any(|change| change.sender_id == Some(*account_id) && change.receiver_id.is_some());
+                nft_burns.push(NftBurn {
+                    owner_id: account_id,
+                    token_ids,
+                    authorized_id: None,
+                    memo: Some(if transferred {
+                        "transferred all shares"
+                    } else {
+                        "burned all shares"
+                    }),
+                });
+            } else if !was_holder && is_holder {
+                nft_mints.push(NftMint {
+                    owner_id: account_id,
+                    token_ids,
+                    memo: Some("received first shares"),
+                });
+            }
+        }
+        if !nft_burns.is_empty() {
+            NftBurn::emit_many(&nft_burns);
+        }
+        // approvals must not carry over to a later position
+        for nft_burn in nft_burns.iter() {
+            self.internal_clear_approvals(nft_burn.owner_id);
+        }
+        if !nft_mints.is_empty() {
+            NftMint::emit_many(&nft_mints);
+        }
+
+        for account_id in accounts {
+            self.internal_update_holder(account_id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::tiers::HolderTier;
+    use crate::tests::*;
+    use near_sdk::serde_json::Value;
+
+    /// Event names of the logs, in order
+    fn events() -> Vec<String> {
+        get_logs()
+            .iter()
+            .filter_map(|log| log.strip_prefix("EVENT_JSON:"))
+            .map(|log| {
+                let event: Value = near_sdk::serde_json::from_str(log).unwrap();
+                event["event"].as_str().unwrap().to_string()
+            })
+            .collect()
+    }
+
+    #[rstest]
+    /// Test events of transfers
+    /// ASSERT:
+    /// (1) Transfer is logged before the NFT of the sender left without
+    ///     shares is burned and the NFT of the new holder is minted
+    #[case(false, 100, vec!["ft_transfer", "nft_burn", "nft_mint"])]
+    /// (2) Partial transfers between holders only log the transfer
+    #[case(false, 40, vec!["ft_transfer"])]
+    /// (3) Tier changes are logged last
+    #[case(true, 100, vec!["ft_transfer", "nft_burn", "nft_mint", "holder_tier_changed", "holder_tier_changed"])]
+    fn test_transfer_events(#[case] tiers: bool, #[case] amount: u128, #[case] expected: Vec<&str>) {
+        // setup
+        let context = get_context(
+            vec![],
+            1,
+            0,
+            USER_ACCOUNT.parse().unwrap(),
+            0,
+            Gas(300u64 * 10u64.pow(12)),
+        );
+        testing_env!(context);
+        let mut contract = init_contract(1);
+        let user: AccountId = USER_ACCOUNT.parse().unwrap();
+        let new_user: AccountId = NEW_USER_ACCOUNT.parse().unwrap();
+        register_user(&mut contract, &user, 100, 0, 0);
+        let receiver = if amount == 100 {
+            register_user(&mut contract, &new_user, 0, 0, 0);
+            new_user
+        } else {
+            OWNER_ACCOUNT.parse().unwrap()
+        };
+        if tiers {
+            let tier = HolderTier {
+                name: "Collector".to_string(),
+                min_bps: 1,
+                title: "Collector".to_string(),
+                media: "ar://collector".to_string(),
+                media_hash: None,
+                reference: None,
+                reference_hash: None,
+            };
+            contract.tiers.set(&vec![tier]);
+            contract.holder_tiers.insert(&user, &"Collector".to_string());
+        }
+
+        // call tested method
+        contract.ft_transfer(receiver, U128(amount), None);
+
+        // perform assertions
+        assert_eq!(events(), expected);
+    }
+
+    #[rstest]
+    /// Test events of ft_resolve_transfer refunds
+    /// ASSERT:
+    /// (1) Refund restoring the sender from zero logs the transfer, the
+    ///     burn of the receiver's NFT and the mint of the sender's NFT
+    #[case(true, vec!["ft_transfer", "nft_burn", "nft_mint"], 0)]
+    /// (2) Refund to a deleted sender burns the shares, logged once
+    #[case(false, vec!["ft_burn", "nft_burn"], 100)]
+    fn test_resolve_transfer_events(
+        #[case] sender_registered: bool,
+        #[case] expected: Vec<&str>,
+        #[case] expected_used: u128,
+    ) {
+        // setup
+        let context = get_context(
+            vec![],
+            1,
+            10u128.pow(24),
+            USER_ACCOUNT.parse().unwrap(),
+            0,
+            Gas(300u64 * 10u64.pow(12)),
+        );
+        testing_env!(context.clone());
+        let mut contract = init_contract(1);
+        let user: AccountId = USER_ACCOUNT.parse().unwrap();
+        let new_user: AccountId = NEW_USER_ACCOUNT.parse().unwrap();
+        register_user(&mut contract, &user, 100, 0, 0);
+        register_user(&mut contract, &new_user, 0, 0, 0);
+        contract.ft_transfer_call(new_user.clone(), U128(100), None, "".to_string());
+        assert_eq!(events(), vec!["ft_transfer", "nft_burn", "nft_mint"]);
+        if !sender_registered {
+            contract.ft_functionality.accounts.remove(&user);
+        }
+
+        // call tested method
+        testing_env!(
+            VMContext {
+                predecessor_account_id: CONTRACT_ACCOUNT.parse().unwrap(),
+                storage_usage: env::storage_usage(),
+                ..context
+            },
+            near_sdk::VMConfig::test(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Successful(b"\"100\"".to_vec())],
+        );
+        let used = contract.ft_resolve_transfer(user.clone(), new_user.clone(), U128(100));
+
+        // perform assertions
+        assert_eq!(used, U128(expected_used));
+        assert_eq!(events(), expected);
+        assert_eq!(contract.ft_balance_of(new_user.clone()), U128(0));
+        assert!(!contract.holders.contains(&new_user));
+        assert_eq!(contract.holders.contains(&user), sender_registered);
+        if !sender_registered {
+            assert_eq!(contract.ft_total_supply(), TOKEN_SUPPLY);
+        }
+    }
+}
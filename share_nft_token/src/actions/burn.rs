@@ -0,0 +1,88 @@
+//! Burn actions module
+//!
+//! Allows holders to destroy their own shares, reducing the supply.
+//! The holder's rewards are settled before the burn, so rewards
+//! already accrued by the burned shares remain claimable. Rewards
+//! distributed afterwards are split among the remaining supply.
+
+use crate::*;
+
+#[near_bindgen]
+impl Contract {
+    #[payable]
+    pub fn ft_burn(&mut self, amount: U128, memo: Option<String>) {
+        assert_one_yocto();
+        self.assert_not_paused(&PauseFeature::Transfers);
+        self.assert_no_buyout();
+        assert!(amount.0 > 0, "The amount should be a positive number");
+        let account_id = env::predecessor_account_id();
+        assert!(
+            self.ft_functionality.ft_balance_of(account_id.clone()).0 >= amount.0,
+            "The account doesn't have enough balance"
+        );
+        self.internal_burn_shares(&account_id, amount.0, memo.as_deref());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::tests::*;
+
+    #[rstest]
+    /// Test ft_burn method
+    /// ASSERT:
+    /// (1) Call requires 1 yocto
+    #[should_panic = "Requires attached deposit of exactly 1 yoctoNEAR"]
+    #[case(0, 10)]
+    /// (2) Cannot burn more than the balance
+    #[should_panic = "The account doesn't have enough balance"]
+    #[case(1, 101)]
+    /// (3) Balance and supply are reduced, rewards accrued before
+    ///     the burn are kept and later rewards go to remaining holders
+    /// (4) NFT is burned once all shares are burned
+    #[case(1, 10)]
+    #[case(1, 100)]
+    fn test_ft_burn(#[case] deposit: u128, #[case] amount: u128) {
+        // setup
+        let context = get_context(
+            vec![],
+            deposit,
+            0,
+            USER_ACCOUNT.parse().unwrap(),
+            0,
+            Gas(300u64 * 10u64.pow(12)),
+        );
+        testing_env!(context);
+        let mut contract = init_contract(1);
+        let user: AccountId = USER_ACCOUNT.parse().unwrap();
+        let owner: AccountId = OWNER_ACCOUNT.parse().unwrap();
+        register_user(&mut contract, &user, 100, 0, 0);
+        let total_supply = contract.ft_total_supply().0;
+        contract.distribute_rewards_token(total_supply);
+
+        // call tested method
+        contract.ft_burn(U128(amount), Some("burn memo".to_string()));
+
+        // perform assertions
+        assert_eq!(contract.ft_balance_of(user.clone()).0, 100 - amount);
+        assert_eq!(contract.ft_total_supply().0, total_supply - amount);
+        let remaining_supply = total_supply - amount;
+        contract.distribute_rewards_token(remaining_supply);
+        let user_rewards = contract.view_claimable_rewards(user)[REWARDS_TOKEN_ACCOUNT].0;
+        let owner_rewards = contract.view_claimable_rewards(owner)[REWARDS_TOKEN_ACCOUNT].0;
+        assert_eq!(user_rewards, 100 + (100 - amount));
+        assert_eq!(owner_rewards, 2 * TOKEN_SUPPLY.0);
+        assert_eq!(contract.undistributed_rewards_token.0, 0);
+
+        let logs = get_logs();
+        assert!(logs[0].contains(r#""event":"ft_burn""#));
+        assert!(logs[0].contains("burn memo"));
+        if amount == 100 {
+            assert!(logs[1].contains(r#""event":"nft_burn""#));
+        } else {
+            assert_eq!(logs.len(), 1);
+        }
+    }
+}
@@ -0,0 +1,201 @@
+//! Buyback actions module
+//!
+//! Allows admins to offer to buy shares back from holders at a fixed
+//! price in the reward token, paid from a budget taken out of the
+//! reserve.
+//!
+//! Holders redeem by calling ft_transfer_call on this contract with
+//! receiver_id being the contract itself and msg "redeem". Their
+//! rewards are settled by the transfer, redeemed shares are burned
+//! and the payout is transferred in the reward token. Shares the
+//! budget cannot pay for are refunded.
+
+use crate::events::{BuybackClosed, BuybackOpened, SharesRedeemed};
+use crate::ext_interface::{ext_ft, ext_self, FT_TRANSFER_GAS, REWARD_WITHDRAW_CALLBACK_GAS};
+use crate::*;
+
+#[near_bindgen]
+impl Contract {
+    /// Opens a buyback at price reward tokens per share, moving
+    /// budget out of the reserve to pay for redemptions
+    #[payable]
+    pub fn open_buyback(&mut self, price: U128, budget: U128) {
+        assert_one_yocto();
+        self.assert_role(&Role::Admin);
+        self.assert_no_split();
+        assert!(self.buyback.is_none(), "A buyback is already open");
+        assert!(price.0 > 0, "price must be greater than 0");
+        assert!(
+            budget.0 >= price.0,
+            "budget must cover at least one share"
+        );
+        assert!(
+            budget.0 <= self.reserve_token.0,
+            "Reserve only holds {} reward tokens",
+            self.reserve_token.0
+        );
+        self.reserve_token = U128(self.reserve_token.0 - budget.0);
+        self.buyback = Some(Buyback { price, pool: budget });
+        BuybackOpened {
+            price: &price,
+            budget: &budget,
+        }
+        .emit();
+    }
+
+    /// Closes the open buyback, returning its unused budget to the reserve
+    #[payable]
+    pub fn close_buyback(&mut self) {
+        assert_one_yocto();
+        self.assert_role(&Role::Admin);
+        let buyback = self.buyback.take().expect("No buyback is open");
+        self.reserve_token = U128(self.reserve_token.0 + buyback.pool.0);
+        BuybackClosed {
+            returned: &buyback.pool,
+        }
+        .emit();
+    }
+
+    pub fn view_buyback(&self) -> Option<Buyback> {
+        self.buyback.clone()
+    }
+}
+
+impl Contract {
+    /// Burns shares received from sender_id and pays for them.
+    /// Returns the amount of shares to be refunded.
+    pub fn internal_redeem(&mut self, sender_id: AccountId, amount: U128) -> U128 {
+        self.assert_no_buyout();
+        let mut buyback = self.buyback.clone().expect("No buyback is open");
+        let redeemed = buyback.redeemable(amount.0);
+        assert!(redeemed > 0, "Buyback budget is exhausted");
+        let payout = redeemed * buyback.price.0;
+        buyback.pool = U128(buyback.pool.0 - payout);
+        self.buyback = Some(buyback);
+
+        let escrow = env::current_account_id();
+        self.internal_redistribute_escrow_rewards();
+        self.internal_burn_shares(&escrow, redeemed, Some("redeemed"));
+        SharesRedeemed {
+            account_id: &sender_id,
+            amount: &U128(redeemed),
+            payout: &U128(payout),
+        }
+        .emit();
+
+        ext_ft::ext(self.reward_token.clone())
+            .with_static_gas(FT_TRANSFER_GAS)
+            .with_attached_deposit(1)
+            .ft_transfer(sender_id.clone(), U128(payout), Some("buyback".to_string()))
+            .then(
+                ext_self::ext(env::current_account_id())
+                    .with_static_gas(REWARD_WITHDRAW_CALLBACK_GAS)
+                    .resolve_reward_transfer(sender_id, U128(payout)),
+            );
+        U128(amount.0 - redeemed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::tests::*;
+
+    #[rstest]
+    /// Test open_buyback and close_buyback methods
+    /// ASSERT:
+    /// (1) Only admins can open a buyback
+    #[should_panic = "user.testnet does not have the admin role"]
+    #[case(USER_ACCOUNT, 10, 100)]
+    /// (2) Budget must be funded by the reserve
+    #[should_panic = "Reserve only holds 100 reward tokens"]
+    #[case(ADMIN_ACCOUNT, 10, 101)]
+    /// (3) Budget must pay for at least one share
+    #[should_panic = "budget must cover at least one share"]
+    #[case(ADMIN_ACCOUNT, 10, 9)]
+    /// (4) Budget moves from the reserve and back on close
+    #[case(ADMIN_ACCOUNT, 10, 100)]
+    fn test_open_buyback(#[case] caller: &str, #[case] price: u128, #[case] budget: u128) {
+        // setup
+        let context = get_context(
+            vec![],
+            1,
+            0,
+            caller.parse().unwrap(),
+            0,
+            Gas(300u64 * 10u64.pow(12)),
+        );
+        testing_env!(context);
+        let mut contract = init_contract(1);
+        crate::tests::grant_role(&mut contract, ADMIN_ACCOUNT, Role::Admin);
+        contract.reserve_token = U128(100);
+
+        // call tested method
+        contract.open_buyback(U128(price), U128(budget));
+
+        // perform assertions
+        let buyback = contract.view_buyback().unwrap();
+        assert_eq!(buyback.price, U128(price));
+        assert_eq!(buyback.pool, U128(budget));
+        assert_eq!(contract.view_reserve(), U128(100 - budget));
+        assert!(get_logs()[0].contains(r#""event":"buyback_opened""#));
+
+        contract.close_buyback();
+        assert!(contract.view_buyback().is_none());
+        assert_eq!(contract.view_reserve(), U128(100));
+        assert!(get_logs()[1].contains(r#""event":"buyback_closed""#));
+    }
+
+    #[rstest]
+    /// Test redemption of shares through ft_on_transfer
+    /// ASSERT:
+    /// (1) A buyback must be open
+    #[should_panic = "No buyback is open"]
+    #[case(None, 10)]
+    /// (2) Shares are burned, paid for and refunded when the
+    ///     budget is not enough, rewards accrued by escrowed
+    ///     shares are redistributed
+    #[case(Some(100), 10)]
+    #[case(Some(35), 10)]
+    fn test_redeem(#[case] budget: Option<u128>, #[case] amount: u128) {
+        // setup
+        let context = get_context(
+            vec![],
+            0,
+            10u128.pow(24),
+            CONTRACT_ACCOUNT.parse().unwrap(),
+            0,
+            Gas(300u64 * 10u64.pow(12)),
+        );
+        testing_env!(context);
+        let mut contract = init_contract(1);
+        let owner: AccountId = OWNER_ACCOUNT.parse().unwrap();
+        let escrow: AccountId = CONTRACT_ACCOUNT.parse().unwrap();
+        contract.buyback = budget.map(|budget| Buyback {
+            price: U128(10),
+            pool: U128(budget),
+        });
+        // simulate ft_transfer_call of shares to the contract
+        contract.update_user_rps(&owner);
+        contract.ft_functionality.internal_transfer(&owner, &escrow, amount, None);
+        contract.distribute_rewards_token(TOKEN_SUPPLY.0);
+
+        // call tested method
+        let refund = contract.ft_on_transfer(owner.clone(), U128(amount), "redeem".to_string());
+
+        // perform assertions
+        let redeemed = std::cmp::min(amount, budget.unwrap() / 10);
+        assert_eq!(refund, U128(amount - redeemed));
+        assert_eq!(contract.ft_total_supply().0, TOKEN_SUPPLY.0 - redeemed);
+        assert_eq!(contract.ft_balance_of(escrow.clone()).0, amount - redeemed);
+        assert_eq!(contract.view_buyback().unwrap().pool.0, budget.unwrap() - redeemed * 10);
+        assert_eq!(contract.undistributed_rewards_token.0, amount);
+        let logs = get_logs();
+        assert!(logs.iter().any(|log| log.contains(r#""event":"ft_burn""#)));
+        assert!(logs.last().unwrap().contains(r#""event":"shares_redeemed""#));
+        let receipts = get_created_receipts();
+        assert_eq!(receipts.len(), 2);
+        assert_eq!(receipts[0].receiver_id, REWARDS_TOKEN_ACCOUNT.parse().unwrap());
+    }
+}
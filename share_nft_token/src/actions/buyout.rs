@@ -0,0 +1,601 @@
+//! Buyout actions module
+//!
+//! Provides an exit for the fractional owners: anyone can offer to buy
+//! the whole artwork at a price per share, by calling ft_transfer_call
+//! on the reward token with msg "offer_buyout". The offer escrows the
+//! price of every share in existence, the remainder is refunded.
+//!
+//! Offers at or above the reserve price set by the owner are accepted
+//! immediately. Other offers are put to a vote of the holders, each
+//! weighing the shares it held when the offer was made. Offers are
+//! accepted as soon as enough shares approve them and rejected once
+//! the voting period ends, which refunds the escrow to the bidder.
+//!
+//! Once an offer is accepted, shares are frozen for good and every
+//! holder can redeem its shares, which are burned, for their part
+//! of the escrow.
+
+use crate::buyout::{BuyoutPolicy, BuyoutStatus};
+use crate::events::{
+    BuyoutAccepted, BuyoutOffered, BuyoutPolicyUpdated, BuyoutRejected, BuyoutVoted, SharesRedeemed,
+};
+use crate::ext_interface::{ext_ft, ext_self, FT_TRANSFER_GAS, REWARD_WITHDRAW_CALLBACK_GAS};
+use crate::*;
+use near_sdk::json_types::U64;
+
+#[near_bindgen]
+impl Contract {
+    /// Replaces the rules for accepting buyouts, None disables new offers.
+    /// Offers already made keep the rules they were made under.
+    /// Caller pays for the storage of the policy, the excess is refunded.
+    #[payable]
+    pub fn set_buyout_policy(&mut self, policy: Option<BuyoutPolicy>) {
+        assert!(
+            env::attached_deposit() >= 1,
+            "Requires attached deposit of at least 1 yoctoNEAR"
+        );
+        self.assert_role(&Role::Owner);
+        if let Some(policy) = &policy {
+            policy.assert_valid();
+        }
+        BuyoutPolicyUpdated {
+            updated_by: &env::predecessor_account_id(),
+            policy: policy.as_ref(),
+        }
+        .emit();
+        let previous_size = self.buyout_policy.try_to_vec().unwrap().len();
+        let new_size = policy.try_to_vec().unwrap().len();
+        self.buyout_policy = policy;
+        self.charge_storage_bytes(
+            new_size.saturating_sub(previous_size) as StorageUsage,
+            &env::predecessor_account_id(),
+        );
+    }
+
+    /// Votes on the pending offer with the shares held when it was made.
+    /// The offer is accepted as soon as enough shares approve it.
+    pub fn vote_buyout(&mut self, approve: bool) {
+        let mut buyout = self.buyout.clone().expect("No buyout offer is pending");
+        assert!(
+            buyout.status == BuyoutStatus::Voting,
+            "Buyout offer was already accepted"
+        );
+        assert!(
+            env::block_timestamp() < buyout.voting_end.0,
+            "Voting has ended"
+        );
+        let account_id = env::predecessor_account_id();
+        assert!(
+            self.view_buyout_vote(buyout.id, account_id.clone()).is_none(),
+            "{} already voted",
+            account_id
+        );
+        let weight = self
+            .buyout_snapshots
+            .get(&account_id)
+            .filter(|(buyout_id, _)| *buyout_id == buyout.id)
+            .map(|(_, balance)| balance.0)
+            .unwrap_or_else(|| self.ft_functionality.accounts.get(&account_id).unwrap_or(0));
+        assert!(
+            weight > 0,
+            "{} held no shares when the offer was made",
+            account_id
+        );
+        self.buyout_votes.insert(&account_id, &(buyout.id, approve));
+        if approve {
+            buyout.votes_for = U128(buyout.votes_for.0 + weight);
+        } else {
+            buyout.votes_against = U128(buyout.votes_against.0 + weight);
+        }
+        BuyoutVoted {
+            buyout_id: buyout.id,
+            account_id: &account_id,
+            approve,
+            weight: &U128(weight),
+        }
+        .emit();
+        if buyout.is_approved() {
+            buyout.status = BuyoutStatus::Accepted;
+            BuyoutAccepted {
+                buyout_id: buyout.id,
+            }
+            .emit();
+        }
+        self.buyout = Some(buyout);
+    }
+
+    /// Rejects the pending offer once its vote ended without approval,
+    /// refunding the escrow to the bidder. Callable by anyone.
+    pub fn reject_buyout(&mut self) {
+        let buyout = self.buyout.take().expect("No buyout offer is pending");
+        assert!(
+            buyout.status == BuyoutStatus::Voting,
+            "Buyout offer was already accepted"
+        );
+        assert!(
+            env::block_timestamp() >= buyout.voting_end.0,
+            "Voting has not ended yet"
+        );
+        BuyoutRejected {
+            buyout_id: buyout.id,
+            refund: &buyout.escrow,
+        }
+        .emit();
+        self.internal_send_reward_token(buyout.bidder, buyout.escrow, "buyout rejected");
+    }
+
+    /// Burns every share of the caller in exchange for its part of
+    /// the escrow of the accepted buyout
+    pub fn redeem_buyout(&mut self) {
+        self.assert_not_paused(&PauseFeature::Claims);
+        let mut buyout = self.buyout.clone().expect("No buyout offer is pending");
+        assert!(buyout.is_accepted(), "Buyout offer was not accepted");
+        let account_id = env::predecessor_account_id();
+        let amount = self.ft_functionality.accounts.get(&account_id).unwrap_or(0);
+        assert!(amount > 0, "{} has no shares to redeem", account_id);
+        let payout = amount * buyout.price.0;
+        buyout.escrow = U128(buyout.escrow.0 - payout);
+        self.buyout = Some(buyout);
+
+        self.internal_burn_shares(&account_id, amount, Some("bought out"));
+        SharesRedeemed {
+            account_id: &account_id,
+            amount: &U128(amount),
+            payout: &U128(payout),
+        }
+        .emit();
+        self.internal_send_reward_token(account_id, U128(payout), "buyout");
+    }
+
+    pub fn view_buyout_policy(&self) -> Option<BuyoutPolicy> {
+        self.buyout_policy.clone()
+    }
+
+    pub fn view_buyout(&self) -> Option<Buyout> {
+        self.buyout.clone()
+    }
+
+    /// Vote of account_id on buyout offer buyout_id, if any
+    pub fn view_buyout_vote(&self, buyout_id: u64, account_id: AccountId) -> Option<bool> {
+        self.buyout_votes
+            .get(&account_id)
+            .filter(|(id, _)| *id == buyout_id)
+            .map(|(_, approve)| approve)
+    }
+}
+
+impl Contract {
+    /// Makes an offer for every share at the price amount can pay for.
+    /// Returns the part of amount to be refunded.
+    pub fn internal_offer_buyout(&mut self, bidder: AccountId, amount: u128) -> u128 {
+        self.assert_not_paused(&PauseFeature::Deposits);
+        let policy = self.buyout_policy.clone().expect("Buyouts are disabled");
+        assert!(self.buyout.is_none(), "A buyout offer is already pending");
+        assert!(
+            self.sale.is_none() && self.auction.as_ref().is_none_or(Auction::is_settled),
+            "Cannot make an offer while shares are being offered"
+        );
+        // only sales and auctions can escrow shares, see assert_not_escrow
+        assert!(
+            self.ft_functionality
+                .accounts
+                .get(&env::current_account_id())
+                .unwrap_or(0)
+                == 0,
+            "Cannot make an offer while shares are escrowed"
+        );
+        assert!(
+            self.accounts_rps.contains_key(&bidder),
+            "{} must be registered to make an offer, see storage_deposit",
+            bidder
+        );
+        let supply = self.ft_functionality.total_supply;
+        let price = amount / supply;
+        assert!(price > 0, "Offer must pay at least 1 reward token per share");
+        let escrow = price * supply;
+
+        let timestamp = env::block_timestamp();
+        let buyout = Buyout {
+            id: self.buyouts_count,
+            bidder,
+            price: U128(price),
+            supply: U128(supply),
+            escrow: U128(escrow),
+            voting_end: U64(timestamp + policy.voting_period.0),
+            votes_for: U128(0),
+            votes_against: U128(0),
+            approval_bps: policy.approval_bps,
+            status: if policy.reserve_price.is_some_and(|reserve| price >= reserve.0) {
+                BuyoutStatus::Accepted
+            } else {
+                BuyoutStatus::Voting
+            },
+        };
+        self.buyouts_count += 1;
+        BuyoutOffered {
+            buyout_id: buyout.id,
+            bidder: &buyout.bidder,
+            price: &buyout.price,
+            escrow: &buyout.escrow,
+            voting_end: &buyout.voting_end,
+        }
+        .emit();
+        if buyout.is_accepted() {
+            BuyoutAccepted {
+                buyout_id: buyout.id,
+            }
+            .emit();
+        }
+        self.buyout = Some(buyout);
+        amount - escrow
+    }
+
+    /// Records the balance of account_id if a buyout offer is being
+    /// voted on and it was not recorded yet, replacing its snapshot
+    /// of any previous offer.
+    /// Must be called before every change of a balance.
+    pub fn internal_checkpoint_buyout(&mut self, account_id: &AccountId) {
+        if let Some(buyout) = &self.buyout {
+            if buyout.status == BuyoutStatus::Voting {
+                let recorded = self
+                    .buyout_snapshots
+                    .get(account_id)
+                    .is_some_and(|(buyout_id, _)| buyout_id == buyout.id);
+                if !recorded {
+                    let balance = self.ft_functionality.accounts.get(account_id).unwrap_or(0);
+                    self.buyout_snapshots.insert(account_id, &(buyout.id, U128(balance)));
+                }
+            }
+        }
+    }
+
+    /// Panics while a buyout offer is pending or accepted. Must be called
+    /// by every operation changing the supply or escrowing shares.
+    pub fn assert_no_buyout(&self) {
+        assert!(
+            self.buyout.is_none(),
+            "Not allowed while a buyout offer is pending"
+        );
+    }
+
+    /// Transfers amount reward tokens to receiver_id, which can
+    /// claim them as rewards if the transfer fails
+    fn internal_send_reward_token(&mut self, receiver_id: AccountId, amount: U128, memo: &str) {
+        ext_ft::ext(self.reward_token.clone())
+            .with_static_gas(FT_TRANSFER_GAS)
+            .with_attached_deposit(1)
+            .ft_transfer(receiver_id.clone(), amount, Some(memo.to_string()))
+            .then(
+                ext_self::ext(env::current_account_id())
+                    .with_static_gas(REWARD_WITHDRAW_CALLBACK_GAS)
+                    .resolve_reward_transfer(receiver_id, amount),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::tests::*;
+
+    /// Offers below 100 reward tokens per share need more than
+    /// half of the shares to approve them within 100 nanoseconds
+    fn buyout_policy() -> BuyoutPolicy {
+        BuyoutPolicy {
+            reserve_price: Some(U128(100)),
+            voting_period: U64(100),
+            approval_bps: 5_001,
+        }
+    }
+
+    /// Registers user with as many shares as the owner and new_user
+    /// without shares, then makes an offer from new_user
+    fn setup_buyout(contract: &mut Contract, amount: u128) -> u128 {
+        register_user(contract, &USER_ACCOUNT.parse().unwrap(), TOKEN_SUPPLY.0, 0, 0);
+        register_user(contract, &NEW_USER_ACCOUNT.parse().unwrap(), 0, 0, 0);
+        contract.buyout_policy = Some(buyout_policy());
+        contract.internal_offer_buyout(NEW_USER_ACCOUNT.parse().unwrap(), amount)
+    }
+
+    fn set_context(context: &VMContext, predecessor: &str, timestamp: u64) {
+        testing_env!(VMContext {
+            predecessor_account_id: predecessor.parse().unwrap(),
+            block_timestamp: timestamp,
+            storage_usage: env::storage_usage(),
+            ..context.clone()
+        });
+    }
+
+    #[rstest]
+    /// Test set_buyout_policy method
+    /// ASSERT:
+    /// (1) Only the owner can set the policy
+    #[should_panic = "admin.testnet does not have the owner role"]
+    #[case(ADMIN_ACCOUNT, Some(buyout_policy()))]
+    /// (2) Policy is validated
+    #[should_panic = "approval_bps must be between 1 and 10000"]
+    #[case(OWNER_ACCOUNT, Some(BuyoutPolicy { approval_bps: 10_001, ..buyout_policy() }))]
+    /// (3) Policy is replaced and logged
+    #[case(OWNER_ACCOUNT, Some(buyout_policy()))]
+    #[case(OWNER_ACCOUNT, None)]
+    fn test_set_buyout_policy(#[case] caller: &str, #[case] policy: Option<BuyoutPolicy>) {
+        // setup
+        let context = get_context(
+            vec![],
+            10u128.pow(24),
+            0,
+            caller.parse().unwrap(),
+            0,
+            Gas(300u64 * 10u64.pow(12)),
+        );
+        testing_env!(context);
+        let mut contract = init_contract(1);
+
+        // call tested method
+        contract.set_buyout_policy(policy.clone());
+
+        // perform assertions
+        assert_eq!(contract.view_buyout_policy().is_some(), policy.is_some());
+        assert!(get_logs()[0].contains(r#""event":"buyout_policy_updated""#));
+    }
+
+    #[rstest]
+    /// Test offers through ft_on_transfer
+    /// ASSERT:
+    /// (1) Offers must pay for every share
+    #[should_panic = "Offer must pay at least 1 reward token per share"]
+    #[case(2 * TOKEN_SUPPLY.0 - 1, BuyoutStatus::Voting)]
+    /// (2) Offers below the reserve price are put to a vote,
+    ///     the remainder of the payment is refunded
+    #[case(2 * 2 * TOKEN_SUPPLY.0 + 7, BuyoutStatus::Voting)]
+    /// (3) Offers at the reserve price are accepted immediately
+    #[case(100 * 2 * TOKEN_SUPPLY.0 + 7, BuyoutStatus::Accepted)]
+    fn test_offer_buyout(#[case] amount: u128, #[case] status: BuyoutStatus) {
+        // setup
+        let context = get_context(
+            vec![],
+            0,
+            0,
+            REWARDS_TOKEN_ACCOUNT.parse().unwrap(),
+            0,
+            Gas(300u64 * 10u64.pow(12)),
+        );
+        testing_env!(context);
+        let mut contract = init_contract(1);
+        register_user(&mut contract, &USER_ACCOUNT.parse().unwrap(), TOKEN_SUPPLY.0, 0, 0);
+        register_user(&mut contract, &NEW_USER_ACCOUNT.parse().unwrap(), 0, 0, 0);
+        contract.buyout_policy = Some(buyout_policy());
+
+        // call tested method
+        let refund = contract.ft_on_transfer(
+            NEW_USER_ACCOUNT.parse().unwrap(),
+            U128(amount),
+            "offer_buyout".to_string(),
+        );
+
+        // perform assertions
+        let buyout = contract.view_buyout().unwrap();
+        assert_eq!(refund, U128(7));
+        assert_eq!(buyout.status, status);
+        assert_eq!(buyout.escrow.0, amount - 7);
+        assert_eq!(buyout.supply.0, 2 * TOKEN_SUPPLY.0);
+        assert_eq!(
+            get_logs()
+                .iter()
+                .any(|log| log.contains(r#""event":"buyout_accepted""#)),
+            status == BuyoutStatus::Accepted
+        );
+    }
+
+    #[rstest]
+    /// Test offers after a split
+    /// ASSERT:
+    /// (1) The reserve price is rescaled to the new share unit
+    #[case(2, 1, 50, BuyoutStatus::Accepted)]
+    #[case(2, 1, 49, BuyoutStatus::Voting)]
+    #[case(1, 2, 200, BuyoutStatus::Accepted)]
+    #[case(1, 2, 199, BuyoutStatus::Voting)]
+    #[case(3, 2, 67, BuyoutStatus::Accepted)]
+    #[case(3, 2, 66, BuyoutStatus::Voting)]
+    fn test_offer_buyout_after_split(
+        #[case] numerator: u32,
+        #[case] denominator: u32,
+        #[case] price: u128,
+        #[case] status: BuyoutStatus,
+    ) {
+        // setup
+        let context = get_context(
+            vec![],
+            1,
+            0,
+            OWNER_ACCOUNT.parse().unwrap(),
+            0,
+            Gas(300u64 * 10u64.pow(12)),
+        );
+        testing_env!(context.clone());
+        let mut contract = init_contract(1);
+        register_user(&mut contract, &USER_ACCOUNT.parse().unwrap(), TOKEN_SUPPLY.0, 0, 0);
+        register_user(&mut contract, &NEW_USER_ACCOUNT.parse().unwrap(), 0, 0, 0);
+        contract.buyout_policy = Some(buyout_policy());
+        contract.split(numerator, denominator, Some(U128(1)));
+        assert!(contract.view_split().is_none());
+
+        // call tested method
+        set_context(&context, REWARDS_TOKEN_ACCOUNT, 0);
+        let supply = contract.ft_total_supply().0;
+        contract.ft_on_transfer(
+            NEW_USER_ACCOUNT.parse().unwrap(),
+            U128(price * supply),
+            "offer_buyout".to_string(),
+        );
+
+        // perform assertions
+        assert_eq!(contract.view_buyout().unwrap().status, status);
+    }
+
+    /// Paths transferring shares without calling the receiver
+    enum TransferPath {
+        FtTransfer,
+        FtTransferBatch,
+        NftTransfer,
+        FtTransferFrom,
+    }
+
+    #[rstest]
+    /// Test that shares cannot be sent to the contract to block offers
+    /// ASSERT:
+    /// (1) Every transfer path rejects the contract account as receiver
+    #[should_panic = "Shares can only be sent to the contract through ft_transfer_call"]
+    #[case(TransferPath::FtTransfer, CONTRACT_ACCOUNT)]
+    #[should_panic = "Shares can only be sent to the contract through ft_transfer_call"]
+    #[case(TransferPath::FtTransferBatch, CONTRACT_ACCOUNT)]
+    #[should_panic = "Shares can only be sent to the contract through ft_transfer_call"]
+    #[case(TransferPath::NftTransfer, CONTRACT_ACCOUNT)]
+    #[should_panic = "Shares can only be sent to the contract through ft_transfer_call"]
+    #[case(TransferPath::FtTransferFrom, CONTRACT_ACCOUNT)]
+    /// (2) Offers can be made after transfers between holders
+    #[case(TransferPath::FtTransfer, NEW_USER_ACCOUNT)]
+    #[case(TransferPath::FtTransferFrom, NEW_USER_ACCOUNT)]
+    fn test_transfer_to_escrow(#[case] path: TransferPath, #[case] receiver: &str) {
+        // setup
+        let context = get_context(
+            vec![],
+            1,
+            10u128.pow(24),
+            OWNER_ACCOUNT.parse().unwrap(),
+            0,
+            Gas(300u64 * 10u64.pow(12)),
+        );
+        testing_env!(context);
+        let mut contract = init_contract(1);
+        contract.internal_register_escrow();
+        let user: AccountId = USER_ACCOUNT.parse().unwrap();
+        let owner: AccountId = OWNER_ACCOUNT.parse().unwrap();
+        register_user(&mut contract, &user, 100, 0, 0);
+        register_user(&mut contract, &NEW_USER_ACCOUNT.parse().unwrap(), 0, 0, 0);
+        contract.internal_set_allowance(&user, &owner, 1);
+        contract.buyout_policy = Some(buyout_policy());
+        let receiver: AccountId = receiver.parse().unwrap();
+
+        // call tested method
+        match path {
+            TransferPath::FtTransfer => contract.ft_transfer(receiver, U128(1), None),
+            TransferPath::FtTransferBatch => {
+                contract.ft_transfer_batch(vec![(receiver, U128(1), None)])
+            }
+            TransferPath::NftTransfer => {
+                contract.nft_transfer(receiver, owner.to_string(), None, None)
+            }
+            TransferPath::FtTransferFrom => {
+                contract.ft_transfer_from(user, receiver, U128(1), None)
+            }
+        }
+
+        // perform assertions
+        let escrowed = contract.ft_balance_of(CONTRACT_ACCOUNT.parse().unwrap());
+        assert_eq!(escrowed, U128(0));
+        contract.internal_offer_buyout(owner, 2 * TOKEN_SUPPLY.0 * 2);
+        assert!(contract.view_buyout().is_some());
+    }
+
+    #[rstest]
+    /// Test vote_buyout and reject_buyout methods
+    /// ASSERT:
+    /// (1) Votes weigh the shares held when the offer was made
+    #[should_panic = "new_user.testnet held no shares when the offer was made"]
+    #[case(vec![(NEW_USER_ACCOUNT, true)], false)]
+    /// (2) Accounts vote once
+    #[should_panic = "owner.testnet already voted"]
+    #[case(vec![(OWNER_ACCOUNT, true), (OWNER_ACCOUNT, true)], false)]
+    /// (3) Offers are accepted as soon as enough shares approve them,
+    ///     even after the approving shares were transferred
+    #[case(vec![(OWNER_ACCOUNT, true), (USER_ACCOUNT, true)], true)]
+    /// (4) Offers lacking approval are rejected once the vote ends
+    #[case(vec![(OWNER_ACCOUNT, true), (USER_ACCOUNT, false)], false)]
+    fn test_vote_buyout(#[case] votes: Vec<(&str, bool)>, #[case] accepted: bool) {
+        // setup
+        let context = get_context(
+            vec![],
+            1,
+            10u128.pow(24),
+            USER_ACCOUNT.parse().unwrap(),
+            0,
+            Gas(300u64 * 10u64.pow(12)),
+        );
+        testing_env!(context.clone());
+        let mut contract = init_contract(1);
+        setup_buyout(&mut contract, 2 * 2 * TOKEN_SUPPLY.0);
+        contract.ft_transfer(NEW_USER_ACCOUNT.parse().unwrap(), U128(TOKEN_SUPPLY.0), None);
+
+        // call tested method
+        for (voter, approve) in votes {
+            set_context(&context, voter, 50);
+            contract.vote_buyout(approve);
+        }
+        if !accepted {
+            set_context(&context, USER_ACCOUNT, 100);
+            contract.reject_buyout();
+        }
+
+        // perform assertions
+        match contract.view_buyout() {
+            Some(buyout) => {
+                assert!(accepted);
+                assert!(buyout.is_accepted());
+                assert_eq!(buyout.votes_for.0, 2 * TOKEN_SUPPLY.0);
+            }
+            None => {
+                assert!(!accepted);
+                let receipts = get_created_receipts();
+                assert_eq!(receipts[0].receiver_id, REWARDS_TOKEN_ACCOUNT.parse().unwrap());
+                assert!(get_logs()[0].contains(r#""event":"buyout_rejected""#));
+            }
+        }
+        assert_eq!(
+            contract.view_buyout_vote(0, OWNER_ACCOUNT.parse().unwrap()),
+            Some(true)
+        );
+    }
+
+    #[rstest]
+    /// Test redeem_buyout method
+    /// ASSERT:
+    /// (1) Shares are frozen once an offer is accepted
+    #[should_panic = "Shares are frozen by an accepted buyout"]
+    #[case(true)]
+    /// (2) Holders burn their shares for their part of the escrow
+    #[case(false)]
+    fn test_redeem_buyout(#[case] transfer: bool) {
+        // setup
+        let context = get_context(
+            vec![],
+            1,
+            10u128.pow(24),
+            USER_ACCOUNT.parse().unwrap(),
+            0,
+            Gas(300u64 * 10u64.pow(12)),
+        );
+        testing_env!(context);
+        let mut contract = init_contract(1);
+        setup_buyout(&mut contract, 100 * 2 * TOKEN_SUPPLY.0);
+        if transfer {
+            contract.ft_transfer(NEW_USER_ACCOUNT.parse().unwrap(), U128(1), None);
+        }
+
+        // call tested method
+        contract.redeem_buyout();
+
+        // perform assertions
+        let buyout = contract.view_buyout().unwrap();
+        assert_eq!(buyout.escrow.0, 100 * TOKEN_SUPPLY.0);
+        assert_eq!(contract.ft_balance_of(USER_ACCOUNT.parse().unwrap()).0, 0);
+        assert_eq!(contract.ft_total_supply(), TOKEN_SUPPLY);
+        assert!(!contract.holders.contains(&USER_ACCOUNT.parse().unwrap()));
+        let receipts = get_created_receipts();
+        assert_eq!(receipts[0].receiver_id, REWARDS_TOKEN_ACCOUNT.parse().unwrap());
+        assert!(get_logs()
+            .iter()
+            .any(|log| log.contains(r#""event":"shares_redeemed""#)));
+    }
+}
@@ -0,0 +1,596 @@
+//! Compliance actions module
+//!
+//! Fractional artwork shares are securities in several jurisdictions.
+//! Allows compliance officers to restrict who can transfer shares:
+//! - allowlist only mode, where both sides must be allowlisted
+//! - freezing of individual accounts
+//! - blocking of jurisdictions, recorded as an attribute of each account
+//!
+//! The rules apply to every transfer between holders: ft_transfer,
+//! ft_transfer_call, ft_transfer_from, ft_transfer_batch and the
+//! transfer of position NFTs. The spender of ft_transfer_from is checked
+//! as well as the holder, and every other path crediting shares checks
+//! its receiver: sale and rights purchases, auction bids and claims and
+//! ft_mint. The contract account is exempt, so shares can still be
+//! escrowed for sales, auctions and buyback redemptions, and returned
+//! to issuers and bidders.
+//!
+//! Wallets query detect_transfer_restriction before users sign.
+
+use crate::compliance::{AccountAttributes, TransferRestriction, TransferRestrictions};
+use crate::events::{AccountAttributesUpdated, TransferRestrictionsUpdated};
+use crate::*;
+
+#[near_bindgen]
+impl Contract {
+    #[payable]
+    pub fn set_transfer_restrictions(&mut self, restrictions: TransferRestrictions) {
+        assert_one_yocto();
+        self.assert_role(&Role::ComplianceOfficer);
+        restrictions.assert_valid();
+        self.transfer_restrictions = restrictions;
+        TransferRestrictionsUpdated {
+            restrictions: &self.transfer_restrictions,
+            updated_by: &env::predecessor_account_id(),
+        }
+        .emit();
+    }
+
+    /// Replaces the attributes of account_id, default attributes remove
+    /// them. Caller pays for their storage, the excess is refunded.
+    #[payable]
+    pub fn set_account_attributes(&mut self, account_id: AccountId, attributes: AccountAttributes) {
+        assert!(
+            env::attached_deposit() >= 1,
+            "Requires attached deposit of at least 1 yoctoNEAR"
+        );
+        self.assert_role(&Role::ComplianceOfficer);
+        attributes.assert_valid();
+        let initial_storage_usage = env::storage_usage();
+        if attributes == AccountAttributes::default() {
+            self.account_attributes.remove(&account_id);
+        } else {
+            self.account_attributes.insert(&account_id, &attributes);
+        }
+        AccountAttributesUpdated {
+            account_id: &account_id,
+            attributes: &attributes,
+            updated_by: &env::predecessor_account_id(),
+        }
+        .emit();
+        self.charge_storage_deposit(initial_storage_usage, &env::predecessor_account_id());
+    }
+
+    pub fn view_transfer_restrictions(&self) -> TransferRestrictions {
+        self.transfer_restrictions.clone()
+    }
+
+    pub fn view_account_attributes(&self, account_id: AccountId) -> AccountAttributes {
+        self.account_attributes.get(&account_id).unwrap_or_default()
+    }
+
+    /// Reason why ft_transfer of amount shares from from_id to to_id
+    /// would fail, "none" if it would succeed
+    pub fn detect_transfer_restriction(
+        &self,
+        from_id: AccountId,
+        to_id: AccountId,
+        amount: U128,
+    ) -> TransferRestriction {
+        let paused = self.pause_flags.is_paused(&PauseFeature::Transfers)
+            || self.buyout.as_ref().is_some_and(|buyout| buyout.is_accepted())
+            || self.split.is_some();
+        if paused {
+            TransferRestriction::TransfersPaused
+        } else if amount.0 == 0 || from_id == to_id {
+            TransferRestriction::InvalidAmount
+        } else if self.ft_functionality.ft_balance_of(from_id.clone()).0 < amount.0 {
+            TransferRestriction::InsufficientBalance
+        } else if !self.accounts_rps.contains_key(&to_id) {
+            TransferRestriction::ReceiverNotRegistered
+        } else {
+            self.internal_compliance_restriction(&from_id, &to_id)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::auction::{Auction, AuctionBid, AuctionConfig};
+    use crate::compliance::TransferParty;
+    use crate::minting::MintingPolicy;
+    use crate::sale::{RightsPhase, Sale, SaleConfig, SaleCurrency};
+    use crate::tests::*;
+    use near_sdk::json_types::U64;
+
+    /// Paths crediting shares to an account other than by transfer
+    enum CreditPath {
+        BuySharesNear,
+        BuySharesToken,
+        RightsPurchase,
+        BidAuction,
+        ClaimAuction,
+        ClaimAuctionRefund,
+        Mint,
+    }
+
+    fn attributes(allowlisted: bool, frozen: bool, jurisdiction: Option<&str>) -> AccountAttributes {
+        AccountAttributes {
+            allowlisted,
+            frozen,
+            jurisdiction: jurisdiction.map(String::from),
+        }
+    }
+
+    #[rstest]
+    /// Test set_transfer_restrictions and set_account_attributes methods
+    /// ASSERT:
+    /// (1) Only compliance officers can set restrictions
+    #[should_panic = "user.testnet does not have the compliance_officer role"]
+    #[case(USER_ACCOUNT, "US")]
+    /// (2) Jurisdictions must be ISO 3166-1 alpha-2 codes
+    #[should_panic = "Invalid jurisdiction us, must be an ISO 3166-1 alpha-2 code"]
+    #[case(ADMIN_ACCOUNT, "us")]
+    /// (3) Restrictions and attributes are stored and logged,
+    ///     default attributes are removed
+    #[case(ADMIN_ACCOUNT, "US")]
+    fn test_set_compliance_rules(#[case] caller: &str, #[case] jurisdiction: &str) {
+        // setup
+        let context = get_context(
+            vec![],
+            1,
+            10u128.pow(24),
+            caller.parse().unwrap(),
+            0,
+            Gas(300u64 * 10u64.pow(12)),
+        );
+        testing_env!(context.clone());
+        let mut contract = init_contract(1);
+        grant_role(&mut contract, ADMIN_ACCOUNT, Role::ComplianceOfficer);
+        let user: AccountId = USER_ACCOUNT.parse().unwrap();
+
+        // call tested method
+        contract.set_transfer_restrictions(TransferRestrictions {
+            allowlist_only: true,
+            blocked_jurisdictions: vec![jurisdiction.to_string()],
+        });
+        testing_env!(VMContext {
+            attached_deposit: 10u128.pow(24),
+            storage_usage: env::storage_usage(),
+            ..context.clone()
+        });
+        contract.set_account_attributes(user.clone(), attributes(true, false, Some(jurisdiction)));
+
+        // perform assertions
+        let restrictions = contract.view_transfer_restrictions();
+        assert!(restrictions.allowlist_only);
+        assert_eq!(restrictions.blocked_jurisdictions, vec![jurisdiction.to_string()]);
+        assert_eq!(
+            contract.view_account_attributes(user.clone()),
+            attributes(true, false, Some(jurisdiction))
+        );
+        assert!(get_logs()[0].contains(r#""event":"account_attributes_updated""#));
+
+        testing_env!(VMContext {
+            attached_deposit: 1,
+            storage_usage: env::storage_usage(),
+            ..context
+        });
+        contract.set_account_attributes(user.clone(), AccountAttributes::default());
+        assert!(!contract.account_attributes.contains_key(&user));
+    }
+
+    #[rstest]
+    /// Test detect_transfer_restriction method
+    /// ASSERT:
+    /// (1) Each rule is reported with its reason code
+    #[case(false, vec![], attributes(false, false, None), TransferRestriction::None)]
+    #[case(false, vec![], attributes(false, true, None), TransferRestriction::SenderFrozen)]
+    #[case(true, vec![], attributes(true, false, None), TransferRestriction::None)]
+    #[case(true, vec![], attributes(false, false, None), TransferRestriction::SenderNotAllowlisted)]
+    #[case(false, vec!["US"], attributes(false, false, Some("FR")), TransferRestriction::None)]
+    #[case(
+        false,
+        vec!["US"],
+        attributes(false, false, Some("US")),
+        TransferRestriction::SenderJurisdictionBlocked
+    )]
+    fn test_detect_transfer_restriction(
+        #[case] allowlist_only: bool,
+        #[case] blocked_jurisdictions: Vec<&str>,
+        #[case] sender_attributes: AccountAttributes,
+        #[case] expected: TransferRestriction,
+    ) {
+        // setup
+        let context = get_context(
+            vec![],
+            0,
+            10u128.pow(24),
+            USER_ACCOUNT.parse().unwrap(),
+            0,
+            Gas(300u64 * 10u64.pow(12)),
+        );
+        testing_env!(context);
+        let mut contract = init_contract(1);
+        let user: AccountId = USER_ACCOUNT.parse().unwrap();
+        let new_user: AccountId = NEW_USER_ACCOUNT.parse().unwrap();
+        register_user(&mut contract, &user, 100, 0, 0);
+        register_user(&mut contract, &new_user, 0, 0, 0);
+        contract.transfer_restrictions = TransferRestrictions {
+            allowlist_only,
+            blocked_jurisdictions: blocked_jurisdictions.iter().map(|j| j.to_string()).collect(),
+        };
+        contract.account_attributes.insert(&user, &sender_attributes);
+        contract
+            .account_attributes
+            .insert(&new_user, &attributes(true, false, None));
+
+        // call tested method
+        let restriction = contract.detect_transfer_restriction(user, new_user, U128(10));
+
+        // perform assertions
+        assert_eq!(restriction, expected);
+    }
+
+    #[rstest]
+    /// Test enforcement of transfer restrictions in ft_transfer
+    /// ASSERT:
+    /// (1) Frozen receivers cannot receive shares
+    #[should_panic = "Transfer restricted: receiver_frozen"]
+    #[case(attributes(true, true, None))]
+    /// (2) Receivers must be allowlisted in allowlist only mode
+    #[should_panic = "Transfer restricted: receiver_not_allowlisted"]
+    #[case(attributes(false, false, None))]
+    /// (3) Receivers in blocked jurisdictions cannot receive shares
+    #[should_panic = "Transfer restricted: receiver_jurisdiction_blocked"]
+    #[case(attributes(true, false, Some("US")))]
+    /// (4) Transfers allowed by every rule go through
+    #[case(attributes(true, false, Some("FR")))]
+    fn test_ft_transfer_restricted(#[case] receiver_attributes: AccountAttributes) {
+        // setup
+        let context = get_context(
+            vec![],
+            1,
+            10u128.pow(24),
+            USER_ACCOUNT.parse().unwrap(),
+            0,
+            Gas(300u64 * 10u64.pow(12)),
+        );
+        testing_env!(context);
+        let mut contract = init_contract(1);
+        let user: AccountId = USER_ACCOUNT.parse().unwrap();
+        let new_user: AccountId = NEW_USER_ACCOUNT.parse().unwrap();
+        register_user(&mut contract, &user, 100, 0, 0);
+        register_user(&mut contract, &new_user, 0, 0, 0);
+        contract.transfer_restrictions = TransferRestrictions {
+            allowlist_only: true,
+            blocked_jurisdictions: vec!["US".to_string()],
+        };
+        contract
+            .account_attributes
+            .insert(&user, &attributes(true, false, None));
+        contract.account_attributes.insert(&new_user, &receiver_attributes);
+
+        // call tested method
+        contract.ft_transfer(new_user.clone(), U128(10), None);
+
+        // perform assertions
+        assert_eq!(contract.ft_balance_of(user), U128(90));
+        assert_eq!(contract.ft_balance_of(new_user), U128(10));
+    }
+
+    #[rstest]
+    /// Test detect_transfer_restriction method
+    /// ASSERT:
+    /// (1) Transfers that would fail regardless of compliance rules
+    ///     are reported before them
+    #[case(NEW_USER_ACCOUNT, 0, TransferRestriction::InvalidAmount)]
+    #[case(NEW_USER_ACCOUNT, 101, TransferRestriction::InsufficientBalance)]
+    #[case("unregistered.testnet", 10, TransferRestriction::ReceiverNotRegistered)]
+    #[case(NEW_USER_ACCOUNT, 10, TransferRestriction::ReceiverFrozen)]
+    fn test_detect_transfer_restriction_preconditions(
+        #[case] receiver: &str,
+        #[case] amount: u128,
+        #[case] expected: TransferRestriction,
+    ) {
+        // setup
+        let context = get_context(
+            vec![],
+            0,
+            10u128.pow(24),
+            USER_ACCOUNT.parse().unwrap(),
+            0,
+            Gas(300u64 * 10u64.pow(12)),
+        );
+        testing_env!(context);
+        let mut contract = init_contract(1);
+        let user: AccountId = USER_ACCOUNT.parse().unwrap();
+        let new_user: AccountId = NEW_USER_ACCOUNT.parse().unwrap();
+        register_user(&mut contract, &user, 100, 0, 0);
+        register_user(&mut contract, &new_user, 0, 0, 0);
+        contract
+            .account_attributes
+            .insert(&new_user, &attributes(false, true, None));
+
+        // call tested method
+        let restriction =
+            contract.detect_transfer_restriction(user, receiver.parse().unwrap(), U128(amount));
+
+        // perform assertions
+        assert_eq!(restriction, expected);
+    }
+
+    fn sale(currency: SaleCurrency, rights: Option<RightsPhase>) -> Sale {
+        Sale {
+            id: 0,
+            config: SaleConfig {
+                issuer: OWNER_ACCOUNT.parse().unwrap(),
+                treasury: ADMIN_ACCOUNT.parse().unwrap(),
+                currency,
+                price: U128(10),
+                start: U64(0),
+                end: U64(20),
+                min_per_account: U128(1),
+                max_per_account: U128(50),
+                hard_cap: U128(100),
+            },
+            allocation: U128(100),
+            sold: U128(0),
+            proceeds: U128(0),
+            rights,
+        }
+    }
+
+    #[rstest]
+    /// Test enforcement of transfer restrictions on paths crediting shares
+    /// ASSERT:
+    /// (1) Receivers must be allowlisted in allowlist only mode to buy
+    ///     shares, bid on and claim auctions and be minted shares
+    #[should_panic = "Transfer restricted: receiver_not_allowlisted"]
+    #[case(CreditPath::BuySharesNear, false)]
+    #[should_panic = "Transfer restricted: receiver_not_allowlisted"]
+    #[case(CreditPath::BuySharesToken, false)]
+    #[should_panic = "Transfer restricted: receiver_not_allowlisted"]
+    #[case(CreditPath::RightsPurchase, false)]
+    #[should_panic = "Transfer restricted: receiver_not_allowlisted"]
+    #[case(CreditPath::BidAuction, false)]
+    #[should_panic = "Transfer restricted: receiver_not_allowlisted"]
+    #[case(CreditPath::ClaimAuction, false)]
+    #[should_panic = "Transfer restricted: receiver_not_allowlisted"]
+    #[case(CreditPath::Mint, false)]
+    /// (2) Allowlisted receivers are credited
+    #[case(CreditPath::BuySharesNear, true)]
+    #[case(CreditPath::BuySharesToken, true)]
+    #[case(CreditPath::RightsPurchase, true)]
+    #[case(CreditPath::BidAuction, true)]
+    #[case(CreditPath::ClaimAuction, true)]
+    #[case(CreditPath::Mint, true)]
+    /// (3) Bidders are refunded whatever the restrictions
+    #[case(CreditPath::ClaimAuctionRefund, false)]
+    fn test_credit_restricted(#[case] path: CreditPath, #[case] allowlisted: bool) {
+        // setup
+        let context = get_context(
+            vec![],
+            1,
+            10u128.pow(24),
+            OWNER_ACCOUNT.parse().unwrap(),
+            10,
+            Gas(300u64 * 10u64.pow(12)),
+        );
+        testing_env!(context.clone());
+        let mut contract = init_contract(1);
+        let owner: AccountId = OWNER_ACCOUNT.parse().unwrap();
+        let user: AccountId = USER_ACCOUNT.parse().unwrap();
+        let escrow: AccountId = CONTRACT_ACCOUNT.parse().unwrap();
+        register_user(&mut contract, &user, 0, 0, 0);
+        contract.ft_functionality.internal_transfer(&owner, &escrow, 100, None);
+        contract.internal_update_holder(&escrow);
+        contract.transfer_restrictions = TransferRestrictions {
+            allowlist_only: true,
+            blocked_jurisdictions: vec![],
+        };
+        contract
+            .account_attributes
+            .insert(&user, &attributes(allowlisted, false, None));
+
+        // call tested method
+        let user_context = VMContext {
+            predecessor_account_id: user.clone(),
+            attached_deposit: 100,
+            storage_usage: env::storage_usage(),
+            ..context.clone()
+        };
+        let token_context = VMContext {
+            predecessor_account_id: REWARDS_TOKEN_ACCOUNT.parse().unwrap(),
+            storage_usage: env::storage_usage(),
+            ..context.clone()
+        };
+        match path {
+            CreditPath::BuySharesNear => {
+                contract.sale = Some(sale(SaleCurrency::Near, None));
+                testing_env!(user_context);
+                contract.buy_shares();
+            }
+            CreditPath::BuySharesToken => {
+                contract.sale = Some(sale(SaleCurrency::RewardToken, None));
+                testing_env!(token_context);
+                contract.ft_on_transfer(user.clone(), U128(100), "buy_shares".to_string());
+            }
+            CreditPath::RightsPurchase => {
+                let rights = RightsPhase {
+                    end: U64(5),
+                    snapshot_supply: TOKEN_SUPPLY,
+                };
+                contract.sale = Some(sale(SaleCurrency::RewardToken, Some(rights)));
+                contract.minting_policy = MintingPolicy::new(U128(2 * TOKEN_SUPPLY.0), vec![]);
+                testing_env!(token_context);
+                contract.ft_on_transfer(user.clone(), U128(100), "buy_shares".to_string());
+            }
+            CreditPath::BidAuction => {
+                contract.auction = Some(Auction {
+                    id: 0,
+                    config: AuctionConfig {
+                        issuer: owner,
+                        treasury: ADMIN_ACCOUNT.parse().unwrap(),
+                        currency: SaleCurrency::Near,
+                        supply: U128(100),
+                        start_price: U128(10),
+                        floor_price: U128(10),
+                        start: U64(0),
+                        end: U64(20),
+                    },
+                    funded: true,
+                    committed: U128(0),
+                    bidders: 0,
+                    clearing_price: None,
+                    settled_bidders: 0,
+                    sold: U128(0),
+                    proceeds: U128(0),
+                });
+                testing_env!(user_context);
+                contract.bid_auction();
+            }
+            CreditPath::ClaimAuction | CreditPath::ClaimAuctionRefund => {
+                let shares = match path {
+                    CreditPath::ClaimAuction => 10,
+                    _ => 0,
+                };
+                contract.auction_bids.insert(
+                    &(0, user.clone()),
+                    &AuctionBid {
+                        currency: SaleCurrency::Near,
+                        committed: U128(100),
+                        shares: U128(shares),
+                        refund: U128(100 - 10 * shares),
+                    },
+                );
+                testing_env!(user_context);
+                contract.claim_auction(0);
+            }
+            CreditPath::Mint => {
+                contract.minting_policy = MintingPolicy::new(U128(2 * TOKEN_SUPPLY.0), vec![]);
+                testing_env!(VMContext {
+                    attached_deposit: 10u128.pow(23),
+                    storage_usage: env::storage_usage(),
+                    ..context
+                });
+                contract.ft_mint(user.clone(), U128(10), None);
+            }
+        }
+
+        // perform assertions
+        let expected = match path {
+            CreditPath::BidAuction | CreditPath::ClaimAuctionRefund => 0,
+            _ => 10,
+        };
+        assert_eq!(contract.ft_balance_of(user.clone()).0, expected);
+        match path {
+            CreditPath::BidAuction => assert!(contract.view_auction_bid(0, user).is_some()),
+            CreditPath::ClaimAuctionRefund => assert_eq!(get_created_receipts().len(), 1),
+            _ => (),
+        }
+    }
+
+    #[rstest]
+    /// Test enforcement of transfer restrictions on spenders of ft_transfer_from
+    /// ASSERT:
+    /// (1) Frozen spenders cannot move shares of holders
+    #[should_panic = "Transfer restricted: spender_frozen"]
+    #[case(attributes(true, true, None))]
+    /// (2) Spenders must be allowlisted in allowlist only mode
+    #[should_panic = "Transfer restricted: spender_not_allowlisted"]
+    #[case(attributes(false, false, None))]
+    /// (3) Spenders in blocked jurisdictions cannot move shares of holders
+    #[should_panic = "Transfer restricted: spender_jurisdiction_blocked"]
+    #[case(attributes(true, false, Some("US")))]
+    /// (4) Spenders allowed by every rule move shares
+    #[case(attributes(true, false, Some("FR")))]
+    fn test_ft_transfer_from_restricted(#[case] spender_attributes: AccountAttributes) {
+        // setup
+        let context = get_context(
+            vec![],
+            1,
+            10u128.pow(24),
+            ADMIN_ACCOUNT.parse().unwrap(),
+            0,
+            Gas(300u64 * 10u64.pow(12)),
+        );
+        testing_env!(context);
+        let mut contract = init_contract(1);
+        let user: AccountId = USER_ACCOUNT.parse().unwrap();
+        let new_user: AccountId = NEW_USER_ACCOUNT.parse().unwrap();
+        let spender: AccountId = ADMIN_ACCOUNT.parse().unwrap();
+        register_user(&mut contract, &user, 100, 0, 0);
+        register_user(&mut contract, &new_user, 0, 0, 0);
+        contract.internal_set_allowance(&user, &spender, 10);
+        contract.transfer_restrictions = TransferRestrictions {
+            allowlist_only: true,
+            blocked_jurisdictions: vec!["US".to_string()],
+        };
+        contract
+            .account_attributes
+            .insert(&user, &attributes(true, false, None));
+        contract
+            .account_attributes
+            .insert(&new_user, &attributes(true, false, None));
+        contract.account_attributes.insert(&spender, &spender_attributes);
+
+        // call tested method
+        contract.ft_transfer_from(user.clone(), new_user.clone(), U128(10), None);
+
+        // perform assertions
+        assert_eq!(contract.ft_balance_of(user).0, 90);
+        assert_eq!(contract.ft_balance_of(new_user).0, 10);
+    }
+
+    #[rstest]
+    /// Test escrow of shares in allowlist only mode
+    /// ASSERT:
+    /// (1) The contract account is exempt from restrictions, so
+    ///     allowlisted issuers can fund sales through ft_transfer_call
+    fn test_escrow_exempt() {
+        // setup
+        let context = get_context(
+            vec![],
+            1,
+            10u128.pow(24),
+            OWNER_ACCOUNT.parse().unwrap(),
+            0,
+            Gas(300u64 * 10u64.pow(12)),
+        );
+        testing_env!(context.clone());
+        let mut contract = init_contract(1);
+        let owner: AccountId = OWNER_ACCOUNT.parse().unwrap();
+        let escrow: AccountId = CONTRACT_ACCOUNT.parse().unwrap();
+        contract.sale = Some(Sale {
+            allocation: U128(0),
+            ..sale(SaleCurrency::Near, None)
+        });
+        contract.transfer_restrictions = TransferRestrictions {
+            allowlist_only: true,
+            blocked_jurisdictions: vec![],
+        };
+        contract
+            .account_attributes
+            .insert(&owner, &attributes(true, false, None));
+        assert_eq!(
+            contract.internal_party_restriction(&escrow, TransferParty::Receiver),
+            TransferRestriction::None
+        );
+
+        // call tested method
+        let _ = contract.ft_transfer_call(escrow.clone(), U128(100), None, "fund_sale".to_string());
+        testing_env!(VMContext {
+            predecessor_account_id: escrow.clone(),
+            storage_usage: env::storage_usage(),
+            ..context
+        });
+        contract.ft_on_transfer(owner, U128(100), "fund_sale".to_string());
+
+        // perform assertions
+        assert_eq!(contract.ft_balance_of(escrow).0, 100);
+        assert_eq!(contract.view_sale().unwrap().allocation, U128(100));
+    }
+}
@@ -0,0 +1,177 @@
+//! Batch transfer module
+//!
+//! Allows a holder to distribute shares to many accounts in a single
+//! call, e.g. after a sale. Every party's rewards are settled once,
+//! receivers that are not registered yet are registered using the
+//! NEAR attached by the sender, and a single combined event is emitted
+//! for each NEP-141 and NEP-171 event type.
+
+use std::collections::HashSet;
+
+use crate::balances::BalanceChange;
+use crate::*;
+use near_contract_standards::storage_management::StorageManagement;
+
+#[near_bindgen]
+impl Contract {
+    /// Transfers shares from the caller to each (receiver, amount, memo).
+    /// Requires at least 1 yoctoNEAR plus the storage deposit of every
+    /// unregistered receiver, the excess is refunded.
+    #[payable]
+    pub fn ft_transfer_batch(&mut self, transfers: Vec<(AccountId, U128, Option<String>)>) {
+        self.assert_not_paused(&PauseFeature::Transfers);
+        assert!(!transfers.is_empty(), "transfers cannot be empty");
+        let attached_deposit = env::attached_deposit();
+        assert!(
+            attached_deposit >= 1,
+            "Requires attached deposit of at least 1 yoctoNEAR"
+        );
+        let sender = env::predecessor_account_id();
+        let storage_cost = self.storage_balance_bounds().min.0;
+
+        let mut receivers = HashSet::new();
+        let mut registrations: u128 = 0;
+        for (receiver_id, amount, _) in transfers.iter() {
+            assert!(*receiver_id != sender, "Sender and receiver should be different");
+            self.assert_not_escrow(receiver_id);
+            assert!(amount.0 > 0, "The amount should be a positive number");
+            self.assert_transfer_allowed(&sender, receiver_id);
+            if !receivers.insert(receiver_id.clone()) {
+                continue;
+            }
+            if !self.accounts_rps.contains_key(receiver_id) {
+                self.internal_register_account(receiver_id, storage_cost);
+                registrations += 1;
+            }
+        }
+
+        let total_amount = transfers.iter().fold(0u128, |total, (_, amount, _)| {
+            total.checked_add(amount.0).expect("Total amount overflow")
+        });
+        assert!(
+            self.ft_functionality.ft_balance_of(sender.clone()).0 >= total_amount,
+            "The account doesn't have enough balance"
+        );
+        let total_storage_cost = registrations * storage_cost;
+        assert!(
+            attached_deposit >= total_storage_cost,
+            "Must attach {} yoctoNEAR to register {} receivers",
+            total_storage_cost,
+            registrations
+        );
+
+        self.internal_change_balances(
+            &transfers
+                .iter()
+                .map(|(receiver_id, amount, memo)| {
+                    BalanceChange::transfer(&sender, receiver_id, amount.0, memo.as_deref())
+                })
+                .collect::<Vec<_>>(),
+        );
+
+        let refund = attached_deposit - total_storage_cost;
+        if refund > 0 {
+            Promise::new(sender).transfer(refund);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::tests::*;
+
+    /// Attached deposit large enough to register any receiver
+    const STORAGE_DEPOSIT: u128 = 10u128.pow(24);
+
+    #[rstest]
+    /// Test ft_transfer_batch method
+    /// ASSERT:
+    /// (1) Call requires at least 1 yocto
+    #[should_panic = "Requires attached deposit of at least 1 yoctoNEAR"]
+    #[case(0, vec![(USER_ACCOUNT, 10)])]
+    /// (2) Registering receivers must be paid for
+    #[should_panic = "to register 1 receivers"]
+    #[case(1, vec![(USER_ACCOUNT, 10), (NEW_USER_ACCOUNT, 10)])]
+    /// (3) Sender must hold enough shares for the whole batch
+    #[should_panic = "The account doesn't have enough balance"]
+    #[case(1, vec![(USER_ACCOUNT, TOKEN_SUPPLY.0), (USER_ACCOUNT, 1)])]
+    /// (4) Self transfers are not allowed
+    #[should_panic = "Sender and receiver should be different"]
+    #[case(1, vec![(OWNER_ACCOUNT, 10)])]
+    /// (5) Balances move, unregistered receivers get registered,
+    ///     rewards of every party are settled and excess deposit refunded
+    #[case(1, vec![(USER_ACCOUNT, 10), (USER_ACCOUNT, 5)])]
+    #[case(STORAGE_DEPOSIT, vec![(USER_ACCOUNT, 10), (NEW_USER_ACCOUNT, 20)])]
+    fn test_ft_transfer_batch(#[case] deposit: u128, #[case] transfers: Vec<(&str, u128)>) {
+        // setup
+        let context = get_context(
+            vec![],
+            deposit,
+            STORAGE_DEPOSIT,
+            OWNER_ACCOUNT.parse().unwrap(),
+            0,
+            Gas(300u64 * 10u64.pow(12)),
+        );
+        testing_env!(context);
+        let mut contract = init_contract(1);
+        let user: AccountId = USER_ACCOUNT.parse().unwrap();
+        let new_user: AccountId = NEW_USER_ACCOUNT.parse().unwrap();
+        register_user(&mut contract, &user, 100, 0, 0);
+        contract.contract_rps_token = U128(3);
+
+        // call tested method
+        contract.ft_transfer_batch(
+            transfers
+                .iter()
+                .map(|(receiver, amount)| (receiver.parse().unwrap(), U128(*amount), None))
+                .collect(),
+        );
+
+        // perform assertions
+        let expected = |account: &str| -> u128 {
+            transfers
+                .iter()
+                .filter(|(receiver, _)| *receiver == account)
+                .map(|(_, amount)| amount)
+                .sum()
+        };
+        let transferred: u128 = transfers.iter().map(|(_, amount)| amount).sum();
+        assert_eq!(
+            contract.ft_balance_of(OWNER_ACCOUNT.parse().unwrap()).0,
+            TOKEN_SUPPLY.0 - transferred
+        );
+        assert_eq!(contract.ft_balance_of(user.clone()).0, 100 + expected(USER_ACCOUNT));
+        assert_eq!(contract.ft_balance_of(new_user.clone()).0, expected(NEW_USER_ACCOUNT));
+
+        // rewards were settled with balances prior to the transfer
+        let owner_rps = contract.accounts_rps.get(&OWNER_ACCOUNT.parse().unwrap()).unwrap();
+        assert_eq!(owner_rps.rewards_balance_token.0, TOKEN_SUPPLY.0 * 3);
+        let user_rps = contract.accounts_rps.get(&user).unwrap();
+        assert_eq!(user_rps.rewards_balance_token.0, 100 * 3);
+
+        let logs = get_logs();
+        assert!(logs[0].contains(r#""event":"ft_transfer""#));
+        assert_eq!(logs.iter().filter(|log| log.contains("ft_transfer")).count(), 1);
+        let registrations = if expected(NEW_USER_ACCOUNT) > 0 {
+            assert!(contract.accounts_rps.contains_key(&new_user));
+            assert_eq!(contract.accounts_rps.get(&new_user).unwrap().account_rps_token.0, 3);
+            assert!(logs[1].contains(r#""event":"nft_mint""#));
+            1
+        } else {
+            assert_eq!(logs.len(), 1);
+            0
+        };
+        let receipts = get_created_receipts();
+        assert_eq!(receipts.len(), 1);
+        if let VmAction::Transfer { deposit: refund } = receipts[0].actions[0].clone() {
+            assert_eq!(
+                refund,
+                deposit - registrations * contract.storage_balance_bounds().min.0
+            );
+        } else {
+            panic!()
+        };
+    }
+}
@@ -3,25 +3,30 @@
 //! Utilizes standard routing of blockchain exposed methods to internal
 //! methods of ft_functionality.
 //! 
-//! ft_transfer and ft_transfer_call also call update_user_rps to ensure
-//! correct rewards distribution before any balance modification.
-//! 
-//! ft_transfer and ft_transfer_call also call emit_receiver_nft_events
-//! and emit_sender_nft_events to enforce events for NEP-171 standard
+//! ft_transfer, ft_transfer_call and the refunds of ft_resolve_transfer
+//! move shares through internal_transfer_shares, which settles rewards
+//! before any balance modification and logs the events of both the
+//! NEP-141 and the NEP-171 standards, see the balances module.
+//!
+//! Both transfer methods are halted while transfers are paused and
+//! subject to the transfer restrictions of the compliance module.
 
+use crate::ext_interface::{FT_RESOLVE_TRANSFER_GAS, FT_TRANSFER_CALL_GAS};
 use crate::*;
+use near_contract_standards::fungible_token::receiver::ext_ft_receiver;
+use near_contract_standards::fungible_token::resolver::ext_ft_resolver;
+use near_sdk::PromiseResult;
 
 #[near_bindgen]
 impl FungibleTokenCore for Contract {
     #[payable]
     fn ft_transfer(&mut self, receiver_id: AccountId, amount: U128, memo: Option<String>) {
+        assert_one_yocto();
+        self.assert_not_paused(&PauseFeature::Transfers);
+        self.assert_not_escrow(&receiver_id);
         let sender = env::predecessor_account_id();
-        self.update_user_rps(&sender);
-        self.update_user_rps(&receiver_id);
-        self.emit_receiver_nft_events(&receiver_id);
-        self.ft_functionality
-            .ft_transfer(receiver_id.clone(), amount, memo);
-        self.emit_sender_nft_events(&sender);
+        self.assert_transfer_allowed(&sender, &receiver_id);
+        self.internal_transfer_shares(&sender, &receiver_id, amount.0, memo);
     }
 
     #[payable]
@@ -32,14 +37,28 @@ impl FungibleTokenCore for Contract {
         memo: Option<String>,
         msg: String,
     ) -> PromiseOrValue<U128> {
-      let sender = env::predecessor_account_id();
-        self.update_user_rps(&sender);
-        self.update_user_rps(&receiver_id);
-        self.emit_receiver_nft_events(&receiver_id);
-        let promise = self.ft_functionality
-            .ft_transfer_call(receiver_id, amount, memo, msg);
-        self.emit_sender_nft_events(&sender);
-        promise
+        assert_one_yocto();
+        self.assert_not_paused(&PauseFeature::Transfers);
+        assert!(
+            env::prepaid_gas() > FT_TRANSFER_CALL_GAS,
+            "More gas is required"
+        );
+        let sender = env::predecessor_account_id();
+        self.assert_transfer_allowed(&sender, &receiver_id);
+        self.internal_transfer_shares(&sender, &receiver_id, amount.0, memo);
+        let receiver_gas = env::prepaid_gas()
+            .0
+            .checked_sub(env::used_gas().0 + FT_TRANSFER_CALL_GAS.0)
+            .expect("More gas is required");
+        ext_ft_receiver::ext(receiver_id.clone())
+            .with_static_gas(Gas(receiver_gas))
+            .ft_on_transfer(sender.clone(), amount, msg)
+            .then(
+                ext_ft_resolver::ext(env::current_account_id())
+                    .with_static_gas(FT_RESOLVE_TRANSFER_GAS)
+                    .ft_resolve_transfer(sender, receiver_id, amount),
+            )
+            .into()
     }
 
     fn ft_total_supply(&self) -> U128 {
@@ -60,13 +79,30 @@ impl FungibleTokenResolver for Contract {
         receiver_id: AccountId,
         amount: U128,
     ) -> U128 {
-        let (used_amount, burned_amount) =
-            self.ft_functionality
-                .internal_ft_resolve_transfer(&sender_id, receiver_id, amount);
-        if burned_amount > 0 {
-            self.on_tokens_burned(sender_id, burned_amount);
+        let unused_amount = match env::promise_result(0) {
+            PromiseResult::Successful(value) => {
+                match near_sdk::serde_json::from_slice::<U128>(&value) {
+                    Ok(unused_amount) => std::cmp::min(amount.0, unused_amount.0),
+                    Err(_) => amount.0,
+                }
+            }
+            _ => amount.0,
+        };
+        let refund = std::cmp::min(
+            unused_amount,
+            self.ft_functionality.accounts.get(&receiver_id).unwrap_or(0),
+        );
+        if refund == 0 {
+            return amount;
+        }
+        if self.ft_functionality.accounts.contains_key(&sender_id) {
+            self.internal_transfer_shares(&receiver_id, &sender_id, refund, Some("refund".to_string()));
+            U128(amount.0 - refund)
+        } else {
+            log!("The account of the sender was deleted");
+            self.internal_burn_shares(&receiver_id, refund, Some("refund"));
+            amount
         }
-        used_amount.into()
     }
 }
 
@@ -0,0 +1,87 @@
+//! Holders actions module
+//!
+//! The contract keeps the set of accounts holding shares, so that
+//! corporate actions such as splits can reach every holder.
+//!
+//! Contracts migrated from v1.0.0, which did not keep that set,
+//! index each holder on its next balance change. index_holders
+//! indexes the others, splits are refused until every holder is.
+
+use crate::*;
+
+#[near_bindgen]
+impl Contract {
+    /// Adds accounts holding shares to the holders set. Callable by
+    /// anyone, since the set only reflects existing balances.
+    pub fn index_holders(&mut self, account_ids: Vec<AccountId>) {
+        for account_id in account_ids.iter() {
+            self.internal_index_holder(account_id);
+            self.internal_update_holder(account_id);
+        }
+    }
+
+    /// Returns shares whose holders are not indexed yet
+    pub fn view_unindexed_supply(&self) -> U128 {
+        self.unindexed_supply
+    }
+
+    /// Returns accounts holding shares, in no particular order
+    pub fn view_holders(&self, from_index: Option<u64>, limit: Option<u64>) -> Vec<AccountId> {
+        let from_index = from_index.unwrap_or(0);
+        let limit = limit.unwrap_or(self.holders.len());
+        self.holders
+            .iter()
+            .skip(from_index as usize)
+            .take(limit as usize)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::tests::*;
+
+    #[rstest]
+    /// Test index_holders and view_holders methods
+    /// ASSERT:
+    /// (1) Holders are tracked as balances change
+    /// (2) Only accounts holding shares can be indexed
+    fn test_index_holders() {
+        // setup
+        let context = get_context(
+            vec![],
+            1,
+            0,
+            OWNER_ACCOUNT.parse().unwrap(),
+            0,
+            Gas(300u64 * 10u64.pow(12)),
+        );
+        testing_env!(context);
+        let mut contract = init_contract(1);
+        let user: AccountId = USER_ACCOUNT.parse().unwrap();
+        let new_user: AccountId = NEW_USER_ACCOUNT.parse().unwrap();
+        register_user(&mut contract, &user, 0, 0, 0);
+        assert_eq!(contract.view_holders(None, None), vec![contract.owner_id.clone()]);
+
+        contract.ft_transfer(user.clone(), TOKEN_SUPPLY, None);
+        assert_eq!(contract.view_holders(None, None), vec![user.clone()]);
+
+        // simulate holders not indexed, as in state migrated from v1.0.0
+        contract.holders.clear();
+        contract.ft_functionality.accounts.insert(&new_user, &10);
+        contract.ft_functionality.total_supply += 10;
+        contract.unindexed_supply = U128(TOKEN_SUPPLY.0 + 10);
+
+        // call tested method
+        contract.index_holders(vec![user.clone(), new_user.clone(), contract.owner_id.clone()]);
+
+        // perform assertions
+        let mut holders = contract.view_holders(None, None);
+        holders.sort();
+        assert_eq!(holders, vec![new_user, user]);
+        assert_eq!(contract.view_holders(Some(1), Some(5)).len(), 1);
+        assert_eq!(contract.view_unindexed_supply().0, 0);
+    }
+}
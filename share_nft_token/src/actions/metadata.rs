@@ -0,0 +1,322 @@
+//! Metadata actions module
+//!
+//! Allows metadata managers to replace the NEP-141 metadata, the
+//! NEP-171 contract metadata and the metadata displayed for each
+//! holder's NFT, as intellectual property references move. Holder
+//! NFTs can also display an SVG rendered on-chain for each holder.
+//!
+//! Every new reference must come with its sha256 reference_hash.
+//! Every update is appended to an audit history, paid for by the
+//! caller's attached deposit, and logged as an event.
+
+use crate::events::MetadataUpdated;
+use crate::metadata::{
+    assert_valid_hash, assert_verifiable_reference, MetadataChange, MetadataSnapshot,
+};
+use crate::*;
+use near_sdk::json_types::U64;
+
+#[near_bindgen]
+impl Contract {
+    /// Replaces NEP-141 metadata. Decimals cannot be changed, since
+    /// that would change the meaning of every balance.
+    /// Returns the index of the change in the metadata history.
+    #[payable]
+    pub fn update_token_metadata(&mut self, metadata: FungibleTokenMetadata) -> u64 {
+        self.assert_role(&Role::MetadataManager);
+        assert_verifiable_reference(metadata.reference.as_deref(), metadata.reference_hash.as_ref());
+        metadata.assert_valid();
+        assert_eq!(
+            metadata.decimals,
+            self.token_metadata.get().unwrap().decimals,
+            "Decimals cannot be changed"
+        );
+        self.token_metadata.set(&metadata);
+        self.internal_record_metadata_change(MetadataSnapshot::Token(metadata))
+    }
+
+    /// Replaces NEP-171 contract metadata.
+    /// Returns the index of the change in the metadata history.
+    #[payable]
+    pub fn update_nft_contract_metadata(&mut self, metadata: NFTContractMetadata) -> u64 {
+        self.assert_role(&Role::MetadataManager);
+        assert_verifiable_reference(metadata.reference.as_deref(), metadata.reference_hash.as_ref());
+        metadata.assert_valid();
+        self.nft_contract_metadata.set(&metadata);
+        self.internal_record_metadata_change(MetadataSnapshot::NftContract(metadata))
+    }
+
+    /// Replaces the data displayed in each holder's NFT.
+    /// Returns the index of the change in the metadata history.
+    #[payable]
+    pub fn update_nft_instance_metadata(&mut self, metadata: NftInstanceData) -> u64 {
+        self.assert_role(&Role::MetadataManager);
+        assert_verifiable_reference(
+            metadata.reference.as_deref(),
+            metadata.reference_hash.as_ref(),
+        );
+        if let Some(media_hash) = &metadata.media_hash {
+            assert_valid_hash(media_hash, "media_hash");
+        }
+        self.nft_instance_metadata.set(&metadata);
+        self.internal_record_metadata_change(MetadataSnapshot::NftInstance(metadata))
+    }
+
+    /// Replaces the settings of the SVG rendered as media of each
+    /// holder's NFT, None displays the media of the instance metadata.
+    /// Returns the index of the change in the metadata history.
+    #[payable]
+    pub fn update_svg_media(&mut self, config: Option<SvgMediaConfig>) -> u64 {
+        self.assert_role(&Role::MetadataManager);
+        match &config {
+            Some(config) => {
+                config.assert_valid();
+                self.svg_media.set(config);
+            }
+            None => {
+                self.svg_media.remove();
+            }
+        }
+        self.internal_record_metadata_change(MetadataSnapshot::SvgMedia(config))
+    }
+
+    /// Returns metadata changes, oldest first
+    pub fn view_metadata_history(
+        &self,
+        from_index: Option<u64>,
+        limit: Option<u64>,
+    ) -> Vec<MetadataChange> {
+        let from_index = from_index.unwrap_or(0);
+        let limit = limit.unwrap_or(self.metadata_history.len());
+        (from_index..std::cmp::min(from_index.saturating_add(limit), self.metadata_history.len()))
+            .filter_map(|index| self.metadata_history.get(index))
+            .collect()
+    }
+}
+
+impl Contract {
+    fn internal_record_metadata_change(&mut self, metadata: MetadataSnapshot) -> u64 {
+        let initial_storage_usage = env::storage_usage();
+        let updated_by = env::predecessor_account_id();
+        let history_index = self.metadata_history.len();
+        MetadataUpdated {
+            kind: metadata.kind(),
+            updated_by: &updated_by,
+            history_index,
+            reference: metadata.reference(),
+            reference_hash: metadata.reference_hash(),
+        }
+        .emit();
+        self.metadata_history.push(&MetadataChange {
+            updated_by: updated_by.clone(),
+            timestamp: U64(env::block_timestamp()),
+            metadata,
+        });
+        self.charge_storage_deposit(initial_storage_usage, &updated_by);
+        history_index
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::tests::*;
+    use near_sdk::json_types::Base64VecU8;
+
+    /// Attached deposit large enough to cover any update
+    const STORAGE_DEPOSIT: u128 = 10u128.pow(24);
+
+    fn reference_hash() -> Option<Base64VecU8> {
+        Some(Base64VecU8(vec![1; 32]))
+    }
+
+    fn token_metadata(reference_hash: Option<Base64VecU8>, decimals: u8) -> FungibleTokenMetadata {
+        FungibleTokenMetadata {
+            spec: "ft-1.0.0".to_string(),
+            name: "new_name".to_string(),
+            symbol: "new_symbol".to_string(),
+            icon: None,
+            reference: Some("ar://new_reference".to_string()),
+            reference_hash,
+            decimals,
+        }
+    }
+
+    #[rstest]
+    /// Test update_token_metadata method
+    /// ASSERT:
+    /// (1) Only metadata managers can update
+    #[should_panic = "user.testnet does not have the metadata_manager role"]
+    #[case(USER_ACCOUNT, STORAGE_DEPOSIT, token_metadata(reference_hash(), 0))]
+    /// (2) Reference requires a reference_hash
+    #[should_panic = "reference_hash is required with reference"]
+    #[case(OWNER_ACCOUNT, STORAGE_DEPOSIT, token_metadata(None, 0))]
+    /// (3) reference_hash must be a sha256 digest
+    #[should_panic = "reference_hash has to be 32 bytes"]
+    #[case(OWNER_ACCOUNT, STORAGE_DEPOSIT, token_metadata(Some(Base64VecU8(vec![1; 31])), 0))]
+    /// (4) Decimals cannot change
+    #[should_panic = "Decimals cannot be changed"]
+    #[case(OWNER_ACCOUNT, STORAGE_DEPOSIT, token_metadata(reference_hash(), 8))]
+    /// (5) Caller pays for history storage
+    #[should_panic = "to cover storage"]
+    #[case(OWNER_ACCOUNT, 1, token_metadata(reference_hash(), 0))]
+    /// (6) Metadata is replaced, change is recorded and logged
+    #[case(METADATA_MANAGER_ACCOUNT, STORAGE_DEPOSIT, token_metadata(reference_hash(), 0))]
+    fn test_update_token_metadata(
+        #[case] caller: &str,
+        #[case] deposit: u128,
+        #[case] metadata: FungibleTokenMetadata,
+    ) {
+        // setup
+        let context = get_context(
+            vec![],
+            deposit,
+            0,
+            caller.parse().unwrap(),
+            10,
+            Gas(300u64 * 10u64.pow(12)),
+        );
+        testing_env!(context);
+        let mut contract = init_contract(1);
+        crate::tests::grant_role(&mut contract, METADATA_MANAGER_ACCOUNT, Role::MetadataManager);
+
+        // call tested method
+        let index = contract.update_token_metadata(metadata);
+
+        // perform assertions
+        assert_eq!(index, 0);
+        assert_eq!(contract.ft_metadata().symbol, "new_symbol");
+        let history = contract.view_metadata_history(None, None);
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].updated_by, caller.parse().unwrap());
+        assert_eq!(history[0].timestamp, U64(10));
+        assert_eq!(history[0].metadata.kind(), "token");
+        assert!(get_logs()[0].contains(r#""event":"metadata_updated""#));
+
+        // excess deposit is refunded
+        let receipts = get_created_receipts();
+        assert_eq!(receipts.len(), 1);
+        assert_eq!(receipts[0].receiver_id, caller.parse().unwrap());
+    }
+
+    #[rstest]
+    /// Test update_nft_contract_metadata and update_nft_instance_metadata methods
+    /// ASSERT:
+    /// (1) Instance reference requires a reference_hash
+    #[should_panic = "reference_hash is required with reference"]
+    #[case(None, None)]
+    /// (2) media_hash must be a sha256 digest
+    #[should_panic = "media_hash has to be 32 bytes"]
+    #[case(reference_hash(), Some(Base64VecU8(vec![1; 3])))]
+    /// (3) Both metadata are replaced and history keeps the order of changes
+    #[case(reference_hash(), None)]
+    #[case(reference_hash(), reference_hash())]
+    fn test_update_nft_metadata(
+        #[case] instance_reference_hash: Option<Base64VecU8>,
+        #[case] media_hash: Option<Base64VecU8>,
+    ) {
+        // setup
+        let context = get_context(
+            vec![],
+            STORAGE_DEPOSIT,
+            STORAGE_DEPOSIT,
+            OWNER_ACCOUNT.parse().unwrap(),
+            0,
+            Gas(300u64 * 10u64.pow(12)),
+        );
+        testing_env!(context);
+        let mut contract = init_contract(1);
+
+        // call tested methods
+        contract.update_nft_contract_metadata(NFTContractMetadata {
+            spec: "nft-1.0.0".to_string(),
+            name: "new_name".to_string(),
+            symbol: "new_symbol".to_string(),
+            icon: None,
+            base_uri: None,
+            reference: None,
+            reference_hash: None,
+        });
+        contract.update_nft_instance_metadata(NftInstanceData {
+            title: "new_title".to_string(),
+            description: "new_description".to_string(),
+            media: "ar://new_media".to_string(),
+            media_hash: media_hash.clone(),
+            reference: Some("ar://new_reference".to_string()),
+            reference_hash: instance_reference_hash,
+        });
+
+        // perform assertions
+        assert_eq!(contract.nft_metadata().name, "new_name");
+        assert_eq!(contract.nft_instance_metadata.get().unwrap().title, "new_title");
+        let history = contract.view_metadata_history(Some(1), Some(10));
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].metadata.kind(), "nft_instance");
+        assert_eq!(contract.view_metadata_history(None, Some(1))[0].metadata.kind(), "nft_contract");
+        let token = contract
+            .nft_tokens_for_owner(OWNER_ACCOUNT.parse().unwrap(), None, None)
+            .pop()
+            .unwrap();
+        assert_eq!(token.metadata.unwrap().media_hash, media_hash);
+    }
+
+    #[rstest]
+    /// Test update_svg_media method
+    /// ASSERT:
+    /// (1) Color cannot inject markup
+    #[should_panic = "color must be a CSS color name or hex code"]
+    #[case("red\"/><script>", None)]
+    /// (2) Holder NFTs display an SVG of their position, hashed in media_hash
+    #[case("#1a2b3c", None)]
+    #[case("teal", Some("ar://thumbnail&size=small".to_string()))]
+    fn test_update_svg_media(#[case] color: &str, #[case] thumbnail: Option<String>) {
+        // setup
+        let context = get_context(
+            vec![],
+            STORAGE_DEPOSIT,
+            STORAGE_DEPOSIT,
+            OWNER_ACCOUNT.parse().unwrap(),
+            0,
+            Gas(300u64 * 10u64.pow(12)),
+        );
+        testing_env!(context);
+        let mut contract = init_contract(1);
+        let user: AccountId = USER_ACCOUNT.parse().unwrap();
+        register_user(&mut contract, &user, TOKEN_SUPPLY.0 / 3, 0, 0);
+
+        // call tested method
+        let history_index = contract.update_svg_media(Some(SvgMediaConfig {
+            thumbnail: thumbnail.clone(),
+            color: color.to_string(),
+        }));
+
+        // perform assertions
+        assert_eq!(
+            contract.view_metadata_history(Some(history_index), Some(1))[0].metadata.kind(),
+            "svg_media"
+        );
+        let metadata = contract.nft_token(USER_ACCOUNT.to_string()).unwrap().metadata.unwrap();
+        let media = metadata.media.unwrap();
+        let svg = near_sdk::base64::decode(
+            media.strip_prefix("data:image/svg+xml;base64,").unwrap(),
+        )
+        .unwrap();
+        assert_eq!(metadata.media_hash.unwrap().0, env::sha256(&svg));
+        let svg = String::from_utf8(svg).unwrap();
+        assert!(svg.contains(&format!("{} shares", TOKEN_SUPPLY.0 / 3)));
+        assert!(svg.contains("24.99% of "));
+        assert!(svg.contains(&format!(r#"fill="{}""#, color)));
+        match thumbnail {
+            Some(_) => assert!(svg.contains(r#"href="ar://thumbnail&amp;size=small""#)),
+            None => assert!(svg.contains(&format!(
+                r#"href="{}""#,
+                contract.nft_instance_metadata.get().unwrap().media
+            ))),
+        }
+
+        contract.update_svg_media(None);
+        let metadata = contract.nft_token(USER_ACCOUNT.to_string()).unwrap().metadata.unwrap();
+        assert_eq!(metadata.media, Some(contract.nft_instance_metadata.get().unwrap().media));
+    }
+}
@@ -0,0 +1,265 @@
+//! Minting actions module
+//!
+//! Allows minters to issue new shares for follow-on offerings,
+//! within the supply cap and schedule set by the owner.
+//!
+//! The recipient's rewards are settled before its balance grows,
+//! so new shares only take part in rewards deposited after their
+//! issuance and dividends already accrued are never diluted.
+
+use crate::compliance::TransferParty;
+use crate::events::MintingPolicyUpdated;
+use crate::minting::{MintAllowance, MintingPolicy};
+use crate::*;
+
+#[near_bindgen]
+impl Contract {
+    /// Replaces the supply cap and minting schedule. Shares already
+    /// minted keep counting against the new schedule.
+    /// Caller pays for the storage of the schedule, the excess is refunded.
+    #[payable]
+    pub fn set_minting_policy(&mut self, supply_cap: U128, schedule: Vec<MintAllowance>) {
+        assert!(
+            env::attached_deposit() >= 1,
+            "Requires attached deposit of at least 1 yoctoNEAR"
+        );
+        self.assert_role(&Role::Owner);
+        let policy = MintingPolicy {
+            supply_cap,
+            schedule,
+            minted: self.minting_policy.minted,
+        };
+        policy.assert_valid(self.ft_functionality.ft_total_supply().0);
+        MintingPolicyUpdated {
+            updated_by: &env::predecessor_account_id(),
+            supply_cap: &policy.supply_cap,
+            schedule: &policy.schedule,
+        }
+        .emit();
+        let previous_size = self.minting_policy.try_to_vec().unwrap().len();
+        let new_size = policy.try_to_vec().unwrap().len();
+        self.minting_policy = policy;
+        self.charge_storage_bytes(
+            new_size.saturating_sub(previous_size) as StorageUsage,
+            &env::predecessor_account_id(),
+        );
+    }
+
+    /// Issues amount new shares to account_id, registering it if
+    /// needed. Caller pays for the registration, the excess is refunded.
+    #[payable]
+    pub fn ft_mint(&mut self, account_id: AccountId, amount: U128, memo: Option<String>) {
+        assert!(
+            env::attached_deposit() >= 1,
+            "Requires attached deposit of at least 1 yoctoNEAR"
+        );
+        self.assert_role(&Role::Minter);
+        let registered = self.accounts_rps.contains_key(&account_id);
+        let initial_storage_usage = env::storage_usage();
+        self.internal_mint(&account_id, amount.0, memo);
+        if registered {
+            self.charge_storage_deposit(initial_storage_usage, &env::predecessor_account_id());
+        } else {
+            self.charge_storage_bytes(STORAGE_BYTES_PER_USER, &env::predecessor_account_id());
+        }
+    }
+
+    pub fn view_minting_policy(&self) -> MintingPolicy {
+        self.minting_policy.clone()
+    }
+
+    /// Shares that can be minted right now
+    pub fn view_mintable_amount(&self) -> U128 {
+        U128(self.minting_policy.mintable(
+            self.ft_functionality.ft_total_supply().0,
+            env::block_timestamp(),
+        ))
+    }
+}
+
+impl Contract {
+    /// Issues amount new shares to account_id, registering it if needed,
+    /// within the supply cap and schedule
+    pub fn internal_mint(&mut self, account_id: &AccountId, amount: u128, memo: Option<String>) {
+        self.assert_not_paused(&PauseFeature::Transfers);
+        self.assert_no_buyout();
+        self.assert_party_allowed(account_id, TransferParty::Receiver);
+        assert!(amount > 0, "The amount should be a positive number");
+        let total_supply = self.ft_functionality.ft_total_supply().0;
+        assert!(
+            total_supply
+                .checked_add(amount)
+                .is_some_and(|supply| supply <= self.minting_policy.supply_cap.0),
+            "Minting {} shares would exceed the supply cap",
+            amount
+        );
+        let mintable = self
+            .minting_policy
+            .mintable(total_supply, env::block_timestamp());
+        assert!(
+            amount <= mintable,
+            "Minting schedule only allows {} more shares",
+            mintable
+        );
+
+        if self.accounts_rps.contains_key(account_id) {
+            self.update_user_rps(account_id);
+        } else {
+            let deposit = Balance::from(STORAGE_BYTES_PER_USER) * env::storage_byte_cost();
+            self.internal_register_account(account_id, deposit);
+        }
+        self.internal_deposit_shares(account_id, amount, memo.as_deref());
+        self.minting_policy.minted = U128(self.minting_policy.minted.0 + amount);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::tests::*;
+    use near_sdk::json_types::U64;
+
+    /// Attached deposit large enough to register any receiver
+    const STORAGE_DEPOSIT: u128 = 10u128.pow(24);
+
+    fn allowance(available_from: u64, amount: u128) -> MintAllowance {
+        MintAllowance {
+            available_from: U64(available_from),
+            amount: U128(amount),
+        }
+    }
+
+    #[rstest]
+    /// Test set_minting_policy method
+    /// ASSERT:
+    /// (1) Only the owner can set the policy
+    #[should_panic = "admin.testnet does not have the owner role"]
+    #[case(ADMIN_ACCOUNT, TOKEN_SUPPLY.0, vec![])]
+    /// (2) Cap cannot be lower than the supply
+    #[should_panic = "supply_cap cannot be lower than the current supply"]
+    #[case(OWNER_ACCOUNT, TOKEN_SUPPLY.0 - 1, vec![])]
+    /// (3) Schedule amounts must be positive
+    #[should_panic = "Minting schedule amounts must be greater than 0"]
+    #[case(OWNER_ACCOUNT, TOKEN_SUPPLY.0, vec![allowance(0, 0)])]
+    /// (4) Policy is replaced and logged
+    #[case(OWNER_ACCOUNT, 2 * TOKEN_SUPPLY.0, vec![])]
+    #[case(OWNER_ACCOUNT, 2 * TOKEN_SUPPLY.0, vec![allowance(0, 10), allowance(100, 10)])]
+    fn test_set_minting_policy(
+        #[case] caller: &str,
+        #[case] supply_cap: u128,
+        #[case] schedule: Vec<MintAllowance>,
+    ) {
+        // setup
+        let context = get_context(
+            vec![],
+            STORAGE_DEPOSIT,
+            STORAGE_DEPOSIT,
+            caller.parse().unwrap(),
+            0,
+            Gas(300u64 * 10u64.pow(12)),
+        );
+        testing_env!(context);
+        let mut contract = init_contract(1);
+        crate::tests::grant_role(&mut contract, ADMIN_ACCOUNT, Role::Admin);
+
+        // call tested method
+        contract.set_minting_policy(U128(supply_cap), schedule.clone());
+
+        // perform assertions
+        let policy = contract.view_minting_policy();
+        assert_eq!(policy.supply_cap, U128(supply_cap));
+        assert_eq!(policy.schedule.len(), schedule.len());
+        assert!(get_logs()[0].contains(r#""event":"minting_policy_updated""#));
+        // excess deposit is refunded
+        assert_eq!(get_created_receipts().len(), 1);
+    }
+
+    #[rstest]
+    /// Test ft_mint method
+    /// ASSERT:
+    /// (1) Only minters can mint
+    #[should_panic = "user.testnet does not have the minter role"]
+    #[case(USER_ACCOUNT, NEW_USER_ACCOUNT, 10, vec![], 0)]
+    /// (2) Supply cannot exceed the cap
+    #[should_panic = "Minting 101 shares would exceed the supply cap"]
+    #[case(OWNER_ACCOUNT, NEW_USER_ACCOUNT, 101, vec![], 0)]
+    /// (3) Only allowances already available can be minted
+    #[should_panic = "Minting schedule only allows 10 more shares"]
+    #[case(OWNER_ACCOUNT, NEW_USER_ACCOUNT, 20, vec![allowance(0, 10), allowance(100, 10)], 50)]
+    /// (4) Shares are minted to registered and unregistered accounts
+    #[case(OWNER_ACCOUNT, NEW_USER_ACCOUNT, 100, vec![], 0)]
+    #[case(OWNER_ACCOUNT, USER_ACCOUNT, 20, vec![allowance(0, 10), allowance(100, 10)], 100)]
+    fn test_ft_mint(
+        #[case] caller: &str,
+        #[case] receiver: &str,
+        #[case] amount: u128,
+        #[case] schedule: Vec<MintAllowance>,
+        #[case] timestamp: u64,
+    ) {
+        // setup
+        let context = get_context(
+            vec![],
+            STORAGE_DEPOSIT,
+            STORAGE_DEPOSIT,
+            caller.parse().unwrap(),
+            timestamp,
+            Gas(300u64 * 10u64.pow(12)),
+        );
+        testing_env!(context);
+        let mut contract = init_contract(1);
+        let user: AccountId = USER_ACCOUNT.parse().unwrap();
+        register_user(&mut contract, &user, 0, 0, 0);
+        let total_supply = contract.ft_total_supply().0;
+        contract.minting_policy = MintingPolicy::new(U128(total_supply + 100), schedule);
+
+        // call tested method
+        contract.ft_mint(receiver.parse().unwrap(), U128(amount), None);
+
+        // perform assertions
+        let receiver: AccountId = receiver.parse().unwrap();
+        assert_eq!(contract.ft_balance_of(receiver.clone()).0, amount);
+        assert_eq!(contract.ft_total_supply().0, total_supply + amount);
+        assert_eq!(contract.view_minting_policy().minted, U128(amount));
+        assert!(contract.accounts_rps.contains_key(&receiver));
+        let logs = get_logs();
+        assert!(logs[0].contains(r#""event":"ft_mint""#));
+        assert!(logs[1].contains(r#""event":"nft_mint""#));
+    }
+
+    #[rstest]
+    /// Test reward accounting across ft_mint
+    /// ASSERT:
+    /// (1) Rewards deposited before minting are not diluted
+    /// (2) New shares take part in rewards deposited afterwards
+    /// (3) Remainders of uneven distributions are carried over
+    fn test_ft_mint_rewards() {
+        // setup
+        let context = get_context(
+            vec![],
+            STORAGE_DEPOSIT,
+            STORAGE_DEPOSIT,
+            OWNER_ACCOUNT.parse().unwrap(),
+            0,
+            Gas(300u64 * 10u64.pow(12)),
+        );
+        testing_env!(context);
+        let mut contract = init_contract(1);
+        let owner: AccountId = OWNER_ACCOUNT.parse().unwrap();
+        let new_user: AccountId = NEW_USER_ACCOUNT.parse().unwrap();
+        contract.minting_policy = MintingPolicy::new(U128(2 * TOKEN_SUPPLY.0), vec![]);
+
+        // call tested method
+        contract.distribute_rewards_token(2 * TOKEN_SUPPLY.0 + 1);
+        contract.ft_mint(new_user.clone(), TOKEN_SUPPLY, None);
+        contract.distribute_rewards_token(2 * TOKEN_SUPPLY.0 + 1);
+
+        // perform assertions
+        let owner_rewards = contract.view_claimable_rewards(owner)[REWARDS_TOKEN_ACCOUNT];
+        let new_user_rewards = contract.view_claimable_rewards(new_user)[REWARDS_TOKEN_ACCOUNT];
+        assert_eq!(owner_rewards.0, 2 * TOKEN_SUPPLY.0 + TOKEN_SUPPLY.0);
+        assert_eq!(new_user_rewards.0, TOKEN_SUPPLY.0);
+        assert_eq!(contract.undistributed_rewards_token.0, 2);
+        assert_eq!(contract.reward_tokens_all_time_count_token.0, 4 * TOKEN_SUPPLY.0 + 2);
+    }
+}
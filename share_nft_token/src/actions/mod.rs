@@ -1,5 +1,25 @@
+mod allowances;
+mod auction;
+mod balances;
+mod burn;
+mod buyback;
+mod buyout;
+mod compliance;
+mod ft_batch;
 mod ft_impl;
+mod holders;
+mod metadata;
+mod minting;
 mod nft_impl;
+mod pause;
+mod reserve;
 mod storage_impl;
 mod token_receiver;
-mod rewards;
\ No newline at end of file
+mod rewards;
+mod roles;
+mod royalties;
+mod sale;
+mod split;
+mod tiers;
+mod upgrade;
+mod vault;
\ No newline at end of file
@@ -1,231 +1,367 @@
 //! Implementation of NEP-171 interface (nft standard)
-//! 
-//! Implements most methods with no functionality, since all
-//! transfers happen through the NEP-141 interface.
-//! 
-//! View methods to show user NFTs personalize their metadata
-//! according to the owner and the amount of shares that they own.
-
-use std::vec;
+//!
+//! nft_transfer and nft_transfer_call move the whole position of a
+//! holder, through the same rewards settling path as ft_transfer.
+//! Holders can approve other accounts, such as marketplaces, to
+//! transfer their position on their behalf (NEP-178). Approvals are
+//! cleared whenever the holder is left without shares, so they never
+//! carry over to a position it acquires later.
+//!
+//! Each holder's position is an NFT whose token id is the holder's
+//! account id. View methods personalize its metadata according to
+//! the amount of shares that the holder owns.
+
+use crate::ext_interface::{
+    ext_nft_approval_receiver, ext_nft_receiver, ext_self, NFT_ON_APPROVE_GAS,
+    NFT_TRANSFER_CALL_GAS, POSITION_RESOLVE_GAS,
+};
+use crate::svg::PositionSvg;
 use crate::*;
+use near_sdk::PromiseResult;
 
 #[near_bindgen]
 impl NonFungibleTokenCore for Contract {
-    /// nft_transfer cannot be user, all transfer must happen
-    /// throguh NEP-141 interface
-    #[allow(unused_variables)]
+    /// Transfers the whole position token_id to receiver_id. The caller
+    /// must own the position or be approved by its owner.
+    #[payable]
     fn nft_transfer(
         &mut self,
         receiver_id: AccountId,
-        token_id: String,
+        token_id: TokenId,
         approval_id: Option<u64>,
         memo: Option<String>,
     ) {
+        assert_one_yocto();
+        let sender_id = env::predecessor_account_id();
+        self.internal_transfer_position(&sender_id, &receiver_id, &token_id, approval_id, memo);
     }
 
-    /// nft_transfer_call cannot be user, all transfer must happen
-    /// throguh NEP-141 interface
-    #[allow(unused_variables)]
+    /// Transfers the whole position token_id to receiver_id and calls
+    /// nft_on_transfer on it. The position is returned if receiver_id
+    /// returns true or the call fails.
+    #[payable]
     fn nft_transfer_call(
         &mut self,
         receiver_id: AccountId,
-        token_id: String,
+        token_id: TokenId,
         approval_id: Option<u64>,
         memo: Option<String>,
         msg: String,
     ) -> PromiseOrValue<bool> {
-        PromiseOrValue::Value(false)
-    }
-
-    /// Displays data of the NFT representation of the artwork shares.
-    /// Contract only supports nft_id == "0", other values return None.
-    fn nft_token(&self, token_id: String) -> Option<Token> {
-        let token_data = self.nft_instance_metadata.get().unwrap();
-        match token_id.as_str() {
-            "0" => Some(Token {
-                token_id: "0".to_string(),
-                owner_id: env::current_account_id(),
-                metadata: Some(TokenMetadata {
-                    title: Some(token_data.title),
-                    description: Some(format!(
-                        "{}. {}/{}",
-                        token_data.description,
-                        0,
-                        self.ft_functionality.ft_total_supply().0
-                    )),
-                    media: Some(token_data.media),
-                    media_hash: None,
-                    copies: None,
-                    issued_at: None,
-                    expires_at: None,
-                    starts_at: None,
-                    updated_at: None,
-                    extra: None,
-                    reference: Some(token_data.reference),
-                    reference_hash: None,
-                }),
-                approved_account_ids: None,
-            }),
-            _ => None,
-        }
+        assert_one_yocto();
+        let sender_id = env::predecessor_account_id();
+        let (owner_id, amount) =
+            self.internal_transfer_position(&sender_id, &receiver_id, &token_id, approval_id, memo);
+        let receiver_gas = env::prepaid_gas()
+            .0
+            .checked_sub(env::used_gas().0 + NFT_TRANSFER_CALL_GAS.0)
+            .expect("Not enough gas attached to nft_transfer_call");
+        ext_nft_receiver::ext(receiver_id.clone())
+            .with_static_gas(Gas(receiver_gas))
+            .nft_on_transfer(sender_id, owner_id.clone(), token_id, msg)
+            .then(
+                ext_self::ext(env::current_account_id())
+                    .with_static_gas(POSITION_RESOLVE_GAS)
+                    .resolve_position_transfer(owner_id, receiver_id, U128(amount)),
+            )
+            .into()
+    }
+
+    /// Displays the position of the holder whose account id is token_id.
+    /// Returns None if that account holds no shares.
+    fn nft_token(&self, token_id: TokenId) -> Option<Token> {
+        let account_id = AccountId::try_from(token_id).ok()?;
+        self.internal_holder_token(&account_id)
     }
 }
 
 #[near_bindgen]
 impl NonFungibleTokenEnumeration for Contract {
-    /// Method supposed to return the total quantity of NFTs
-    /// in the contract. Always return 1 since the only NFT
-    /// in the contract is a picture representing the artwork.
+    /// Each holder owns exactly one NFT representing its position.
+    /// Every holder must be indexed, see index_holders.
     fn nft_total_supply(&self) -> U128 {
-        U128(1)
+        self.assert_holders_indexed();
+        U128(self.holders.len() as u128)
     }
 
-    /// Displays data of the NFT representation of the artwork shares.
-    /// Contract only supports nft_id == "0", other values return None.
+    /// Displays the positions of holders, paginated over the holders index.
+    /// Every holder must be indexed, see index_holders.
     fn nft_tokens(
         &self,
         from_index: Option<U128>, // default: "0"
         limit: Option<u64>,       // default: unlimited (could fail due to gas limit)
     ) -> Vec<Token> {
-        let token_data = self.nft_instance_metadata.get().unwrap();
-        let token_instance = Token {
-            token_id: "0".to_string(),
-            owner_id: env::current_account_id(),
-            metadata: Some(TokenMetadata {
-                title: Some(token_data.title),
-                description: Some(format!(
-                    "{}. {}/{}",
-                    token_data.description,
-                    0,
-                    self.ft_functionality.ft_total_supply().0
-                )),
-                media: Some(token_data.media),
-                media_hash: None,
-                copies: None,
-                issued_at: None,
-                expires_at: None,
-                starts_at: None,
-                updated_at: None,
-                extra: None,
-                reference: Some(token_data.reference),
-                reference_hash: None,
-            }),
-            approved_account_ids: None,
-        };
-        match from_index {
-            Some(index) => {
-                let limit = limit.unwrap_or(1);
-                if index.0 == 0 && limit > 0 {
-                    vec![token_instance]
-                } else {
-                    vec![]
-                }
-            }
-            None => {
-                let limit = limit.unwrap_or(1);
-                if limit > 0 {
-                    vec![token_instance]
-                } else {
-                    vec![]
-                }
-            }
-        }
+        self.assert_holders_indexed();
+        let from_index = from_index.map_or(0, |index| index.0 as usize);
+        let limit = limit.map_or(usize::MAX, |limit| limit as usize);
+        self.holders
+            .iter()
+            .skip(from_index)
+            .take(limit)
+            .filter_map(|account_id| self.internal_holder_token(&account_id))
+            .collect()
     }
 
     fn nft_supply_for_owner(&self, account_id: AccountId) -> U128 {
-        if self.ft_functionality.ft_balance_of(account_id).0 > 0 {
+        if account_id != env::current_account_id()
+            && self.ft_functionality.ft_balance_of(account_id).0 > 0
+        {
             U128(1)
         } else {
             U128(0)
         }
     }
 
-    /// Displays data of the NFT representation of the artwork shares.
-    /// Contract only supports from_index == "0" or None, 
-    /// other values always return None.
-    /// Only returns the NFT if the account_id owns shares. NFT is
-    /// personalized to show the amount of shares that the account_id
-    /// owns in this contract.
+    /// Displays the position of account_id, if it owns shares.
+    /// Accounts own at most one NFT, so only from_index == "0"
+    /// or None return it.
     fn nft_tokens_for_owner(
         &self,
         account_id: AccountId,
         from_index: Option<U128>,
         limit: Option<u64>,
     ) -> Vec<Token> {
-        let token_data = self.nft_instance_metadata.get().unwrap();
-        let has_nft = self.ft_functionality.ft_balance_of(account_id.clone()).0 > 0;
-        let user_nft = Token {
-            token_id: "0".to_string(),
-            owner_id: env::current_account_id(),
+        if from_index.unwrap_or(U128(0)).0 > 0 || limit == Some(0) {
+            return vec![];
+        }
+        self.internal_holder_token(&account_id)
+            .into_iter()
+            .collect()
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Returns the shares that receiver_id did not keep after
+    /// nft_on_transfer to previous_owner_id. Returns true if the
+    /// position was kept by receiver_id.
+    #[private]
+    pub fn resolve_position_transfer(
+        &mut self,
+        previous_owner_id: AccountId,
+        receiver_id: AccountId,
+        amount: U128,
+    ) -> bool {
+        let returned = match env::promise_result(0) {
+            PromiseResult::Successful(value) => {
+                near_sdk::serde_json::from_slice::<bool>(&value).unwrap_or(true)
+            }
+            _ => true,
+        };
+        if !returned || !self.accounts_rps.contains_key(&previous_owner_id) {
+            return true;
+        }
+        let refund = std::cmp::min(
+            amount.0,
+            self.ft_functionality.accounts.get(&receiver_id).unwrap_or(0),
+        );
+        if refund > 0 {
+            self.internal_transfer_shares(
+                &receiver_id,
+                &previous_owner_id,
+                refund,
+                Some("position returned".to_string()),
+            );
+        }
+        false
+    }
+}
+
+impl Contract {
+    /// Transfers every share of the owner of token_id to receiver_id on
+    /// request of sender_id, which must be the owner or approved by it.
+    /// Returns the owner and the amount of shares transferred.
+    pub fn internal_transfer_position(
+        &mut self,
+        sender_id: &AccountId,
+        receiver_id: &AccountId,
+        token_id: &TokenId,
+        approval_id: Option<u64>,
+        memo: Option<String>,
+    ) -> (AccountId, u128) {
+        self.assert_not_paused(&PauseFeature::Transfers);
+        self.assert_not_escrow(receiver_id);
+        let owner_id = self.internal_position_owner(token_id);
+        if sender_id != &owner_id {
+            assert!(
+                self.position_approvals
+                    .get(&owner_id)
+                    .is_some_and(|approvals| approvals.is_approved(sender_id, approval_id)),
+                "{} is not approved to transfer token {}",
+                sender_id,
+                token_id
+            );
+        }
+        assert!(
+            self.accounts_rps.contains_key(receiver_id),
+            "{} must be registered to receive shares, see storage_deposit",
+            receiver_id
+        );
+        self.assert_transfer_allowed(&owner_id, receiver_id);
+        let amount = self.ft_functionality.accounts.get(&owner_id).unwrap();
+        self.internal_transfer_shares(&owner_id, receiver_id, amount, memo);
+        (owner_id, amount)
+    }
+
+    /// Holder owning the position token_id, which is its account id
+    pub fn internal_position_owner(&self, token_id: &TokenId) -> AccountId {
+        match token_id.parse::<AccountId>() {
+            Ok(owner_id) if self.ft_functionality.accounts.get(&owner_id).unwrap_or(0) > 0 => {
+                owner_id
+            }
+            _ => panic!("Token {} does not exist", token_id),
+        }
+    }
+
+    /// Revokes every approval of owner_id and refunds their storage
+    pub fn internal_clear_approvals(&mut self, owner_id: &AccountId) {
+        if let Some(mut approvals) = self.position_approvals.get(owner_id) {
+            if approvals.approved_account_ids.is_empty() {
+                return;
+            }
+            let initial_storage_usage = env::storage_usage();
+            approvals.approved_account_ids.clear();
+            self.position_approvals.insert(owner_id, &approvals);
+            self.refund_storage_release(initial_storage_usage, owner_id);
+        }
+    }
+
+    /// NFT representing the position of account_id, its token id being
+    /// the account id. Metadata is personalized to show the amount of
+    /// shares the account owns and its tier.
+    pub fn internal_holder_token(&self, account_id: &AccountId) -> Option<Token> {
+        let balance = self.ft_functionality.accounts.get(account_id).unwrap_or(0);
+        // shares escrowed by the contract are not a position
+        if balance == 0 || *account_id == env::current_account_id() {
+            return None;
+        }
+        let mut token_data = self.nft_instance_metadata.get().unwrap();
+        let total_supply = self.ft_functionality.total_supply;
+        let tier = self.internal_tier(account_id);
+        if let Some(tier) = &tier {
+            token_data.title = tier.title.clone();
+            token_data.media = tier.media.clone();
+            token_data.media_hash = tier.media_hash.clone();
+            token_data.reference = tier.reference.clone();
+            token_data.reference_hash = tier.reference_hash.clone();
+        }
+        let (media, media_hash) = match self.svg_media.get() {
+            Some(config) => {
+                let (media, media_hash) = PositionSvg {
+                    title: &token_data.title,
+                    thumbnail: config.thumbnail.as_deref().unwrap_or(&token_data.media),
+                    color: &config.color,
+                    balance,
+                    total_supply,
+                    badge: tier.as_ref().map(|tier| tier.name.as_str()),
+                }
+                .data_url();
+                (media, Some(media_hash))
+            }
+            None => (token_data.media, token_data.media_hash),
+        };
+        Some(Token {
+            token_id: account_id.to_string(),
+            owner_id: account_id.clone(),
             metadata: Some(TokenMetadata {
-                title: Some(format!(
-                    "{}. {}/{}",
-                    token_data.title,
-                    self.ft_functionality.ft_balance_of(account_id.clone()).0,
-                    self.ft_functionality.ft_total_supply().0
-                )),
+                title: Some(format!("{}. {}/{}", token_data.title, balance, total_supply)),
                 description: Some(format!(
                     "{}. {}/{}",
-                    token_data.description,
-                    self.ft_functionality.ft_balance_of(account_id).0,
-                    self.ft_functionality.ft_total_supply().0
+                    token_data.description, balance, total_supply
                 )),
-                media: Some(token_data.media),
-                media_hash: None,
+                media: Some(media),
+                media_hash,
                 copies: None,
                 issued_at: None,
                 expires_at: None,
                 starts_at: None,
                 updated_at: None,
-                extra: None,
-                reference: Some(token_data.reference),
-                reference_hash: None,
+                extra: self.vault.as_ref().and_then(Vault::token_extra),
+                reference: token_data.reference,
+                reference_hash: token_data.reference_hash,
             }),
-            approved_account_ids: None,
-        };
-        if !has_nft {
-            vec![]
-        } else {
-            match from_index {
-                Some(index) => {
-                    let limit = limit.unwrap_or(1);
-                    if index.0 == 0 && limit > 0 {
-                        vec![user_nft]
-                    } else {
-                        vec![]
-                    }
-                }
-                None => {
-                    let limit = limit.unwrap_or(1);
-                    if limit > 0 {
-                        vec![user_nft]
-                    } else {
-                        vec![]
-                    }
-                }
-            }
-        }
+            approved_account_ids: Some(
+                self.position_approvals
+                    .get(account_id)
+                    .map(|approvals| approvals.approved_account_ids)
+                    .unwrap_or_default(),
+            ),
+        })
     }
 }
 
-/// Implements approval method for NEP-171. Only implements the interface
-/// for compliance with standard, methods produce no effect, all transfers
-/// must use NEP-141 interface
-#[allow(unused_variables)]
+/// Implements NEP-178 approvals of position NFTs. Approved accounts
+/// can transfer the whole position of the holder.
 #[near_bindgen]
 impl NonFungibleTokenApproval for Contract {
+    /// Approves account_id to transfer the position of the caller,
+    /// calling nft_on_approve on it if msg is given. Storage must be
+    /// paid for by the caller.
+    #[payable]
     fn nft_approve(
         &mut self,
         token_id: TokenId,
         account_id: AccountId,
         msg: Option<String>,
     ) -> Option<Promise> {
-        None
+        assert!(
+            env::attached_deposit() > 0,
+            "Requires attached deposit of at least 1 yoctoNEAR"
+        );
+        let owner_id = env::predecessor_account_id();
+        assert!(
+            self.internal_position_owner(&token_id) == owner_id,
+            "Token {} is not owned by {}",
+            token_id,
+            owner_id
+        );
+        let initial_storage_usage = env::storage_usage();
+        let mut approvals = self.position_approvals.get(&owner_id).unwrap_or_default();
+        let approval_id = approvals.approve(&account_id);
+        self.position_approvals.insert(&owner_id, &approvals);
+        self.charge_storage_deposit(initial_storage_usage, &owner_id);
+        msg.map(|msg| {
+            ext_nft_approval_receiver::ext(account_id)
+                .with_static_gas(NFT_ON_APPROVE_GAS)
+                .nft_on_approve(token_id, owner_id, approval_id, msg)
+        })
     }
 
-    fn nft_revoke(&mut self, token_id: TokenId, account_id: AccountId) {}
+    /// Revokes the approval of account_id over the position of the
+    /// caller and refunds its storage
+    #[payable]
+    fn nft_revoke(&mut self, token_id: TokenId, account_id: AccountId) {
+        assert_one_yocto();
+        let owner_id = env::predecessor_account_id();
+        assert!(
+            token_id == owner_id.as_str(),
+            "Token {} is not owned by {}",
+            token_id,
+            owner_id
+        );
+        if let Some(mut approvals) = self.position_approvals.get(&owner_id) {
+            let initial_storage_usage = env::storage_usage();
+            if approvals.approved_account_ids.remove(&account_id).is_some() {
+                self.position_approvals.insert(&owner_id, &approvals);
+                self.refund_storage_release(initial_storage_usage, &owner_id);
+            }
+        }
+    }
 
-    fn nft_revoke_all(&mut self, token_id: TokenId) {}
+    /// Revokes every approval over the position of the caller
+    /// and refunds their storage
+    #[payable]
+    fn nft_revoke_all(&mut self, token_id: TokenId) {
+        assert_one_yocto();
+        let owner_id = env::predecessor_account_id();
+        assert!(
+            token_id == owner_id.as_str(),
+            "Token {} is not owned by {}",
+            token_id,
+            owner_id
+        );
+        self.internal_clear_approvals(&owner_id);
+    }
 
     fn nft_is_approved(
         &self,
@@ -233,7 +369,10 @@ impl NonFungibleTokenApproval for Contract {
         approved_account_id: AccountId,
         approval_id: Option<u64>,
     ) -> bool {
-        false
+        let owner_id = self.internal_position_owner(&token_id);
+        self.position_approvals
+            .get(&owner_id)
+            .is_some_and(|approvals| approvals.is_approved(&approved_account_id, approval_id))
     }
 }
 
@@ -245,3 +384,339 @@ impl NonFungibleTokenMetadataProvider for Contract {
         self.nft_contract_metadata.get().unwrap()
     }
 }
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::tests::*;
+
+    #[rstest]
+    /// Test nft_token method
+    /// ASSERT:
+    /// (1) Holders own the token whose id is their account id
+    #[case(USER_ACCOUNT, Some(10))]
+    /// (2) Accounts without shares and invalid ids have no token
+    #[case(NEW_USER_ACCOUNT, None)]
+    #[case("Invalid Account", None)]
+    fn test_nft_token(#[case] token_id: &str, #[case] balance: Option<u128>) {
+        // setup
+        let context = get_context(
+            vec![],
+            0,
+            0,
+            OWNER_ACCOUNT.parse().unwrap(),
+            0,
+            Gas(300u64 * 10u64.pow(12)),
+        );
+        testing_env!(context);
+        let mut contract = init_contract(1);
+        register_user(&mut contract, &USER_ACCOUNT.parse().unwrap(), 10, 0, 0);
+        register_user(&mut contract, &NEW_USER_ACCOUNT.parse().unwrap(), 0, 0, 0);
+
+        // call tested method
+        let token = contract.nft_token(token_id.to_string());
+
+        // perform assertions
+        match balance {
+            Some(balance) => {
+                let token = token.unwrap();
+                assert_eq!(token.token_id, token_id);
+                assert_eq!(token.owner_id, token_id.parse().unwrap());
+                assert!(token
+                    .metadata
+                    .unwrap()
+                    .title
+                    .unwrap()
+                    .ends_with(&format!("{}/{}", balance, TOKEN_SUPPLY.0 + 10)));
+            }
+            None => assert!(token.is_none()),
+        }
+    }
+
+    #[rstest]
+    /// Test nft_tokens and nft_total_supply methods
+    /// ASSERT:
+    /// (1) Every holder owns one token, paginated over holders
+    /// (2) Shares escrowed by the contract are not a position
+    #[case(None, None, vec![OWNER_ACCOUNT, USER_ACCOUNT, ADMIN_ACCOUNT])]
+    #[case(Some(U128(1)), Some(1), vec![USER_ACCOUNT])]
+    #[case(Some(U128(3)), None, vec![])]
+    fn test_nft_tokens(
+        #[case] from_index: Option<U128>,
+        #[case] limit: Option<u64>,
+        #[case] owners: Vec<&str>,
+    ) {
+        // setup
+        let context = get_context(
+            vec![],
+            0,
+            0,
+            OWNER_ACCOUNT.parse().unwrap(),
+            0,
+            Gas(300u64 * 10u64.pow(12)),
+        );
+        testing_env!(context);
+        let mut contract = init_contract(1);
+        register_user(&mut contract, &USER_ACCOUNT.parse().unwrap(), 10, 0, 0);
+        register_user(&mut contract, &NEW_USER_ACCOUNT.parse().unwrap(), 0, 0, 0);
+        register_user(&mut contract, &ADMIN_ACCOUNT.parse().unwrap(), 5, 0, 0);
+        let escrow: AccountId = CONTRACT_ACCOUNT.parse().unwrap();
+        contract.internal_transfer_shares(&contract.owner_id.clone(), &escrow, 10, None);
+
+        // call tested method
+        let tokens = contract.nft_tokens(from_index, limit);
+
+        // perform assertions
+        assert_eq!(contract.nft_total_supply(), U128(3));
+        assert!(contract.nft_token(escrow.to_string()).is_none());
+        assert_eq!(contract.nft_supply_for_owner(escrow), U128(0));
+        assert_eq!(
+            tokens
+                .iter()
+                .map(|token| token.owner_id.to_string())
+                .collect::<Vec<_>>(),
+            owners
+        );
+    }
+
+    #[rstest]
+    /// Test nft_transfer method
+    /// ASSERT:
+    /// (1) Caller can only transfer positions it owns or is approved for
+    #[should_panic = "user.testnet is not approved to transfer token owner.testnet"]
+    #[case(USER_ACCOUNT, OWNER_ACCOUNT)]
+    /// (2) Position must hold shares
+    #[should_panic = "Token admin.testnet does not exist"]
+    #[case(ADMIN_ACCOUNT, ADMIN_ACCOUNT)]
+    /// (3) Whole balance moves to the receiver, whose rewards are settled
+    #[case(USER_ACCOUNT, USER_ACCOUNT)]
+    fn test_nft_transfer(#[case] caller: &str, #[case] token_id: &str) {
+        // setup
+        let context = get_context(
+            vec![],
+            1,
+            0,
+            caller.parse().unwrap(),
+            0,
+            Gas(300u64 * 10u64.pow(12)),
+        );
+        testing_env!(context);
+        let mut contract = init_contract(1);
+        let user: AccountId = USER_ACCOUNT.parse().unwrap();
+        let new_user: AccountId = NEW_USER_ACCOUNT.parse().unwrap();
+        register_user(&mut contract, &user, 10, 0, 0);
+        register_user(&mut contract, &new_user, 0, 0, 0);
+        register_user(&mut contract, &ADMIN_ACCOUNT.parse().unwrap(), 0, 0, 0);
+        let total_supply = contract.ft_total_supply().0;
+        contract.distribute_rewards_token(total_supply);
+
+        // call tested method
+        contract.nft_transfer(new_user.clone(), token_id.to_string(), None, None);
+
+        // perform assertions
+        assert_eq!(contract.ft_balance_of(user.clone()), U128(0));
+        assert_eq!(contract.ft_balance_of(new_user.clone()), U128(10));
+        assert!(contract.nft_token(USER_ACCOUNT.to_string()).is_none());
+        assert!(contract.nft_token(NEW_USER_ACCOUNT.to_string()).is_some());
+        let rewards = |contract: &Contract, account: AccountId| {
+            contract.view_claimable_rewards(account)[REWARDS_TOKEN_ACCOUNT].0
+        };
+        assert_eq!(rewards(&contract, user), 10);
+        assert_eq!(rewards(&contract, new_user), 0);
+        let logs = get_logs();
+        assert!(logs[0].contains(r#""event":"ft_transfer""#));
+        assert!(logs[1].contains(r#""event":"nft_burn""#));
+        assert!(logs[2].contains(r#""event":"nft_mint""#));
+    }
+
+    #[rstest]
+    /// Test nft_transfer_call and resolve_position_transfer methods
+    /// ASSERT:
+    /// (1) Position is returned if the receiver returns true
+    #[case(PromiseResult::Successful(b"true".to_vec()), false)]
+    /// (2) Position is returned if the receiver fails
+    #[case(PromiseResult::Failed, false)]
+    /// (3) Position is kept if the receiver returns false
+    #[case(PromiseResult::Successful(b"false".to_vec()), true)]
+    fn test_nft_transfer_call(#[case] result: PromiseResult, #[case] kept: bool) {
+        // setup
+        let context = get_context(
+            vec![],
+            1,
+            10u128.pow(24),
+            USER_ACCOUNT.parse().unwrap(),
+            0,
+            Gas(300u64 * 10u64.pow(12)),
+        );
+        testing_env!(context.clone());
+        let mut contract = init_contract(1);
+        let user: AccountId = USER_ACCOUNT.parse().unwrap();
+        let new_user: AccountId = NEW_USER_ACCOUNT.parse().unwrap();
+        register_user(&mut contract, &user, 10, 0, 0);
+        register_user(&mut contract, &new_user, 0, 0, 0);
+
+        // call tested method
+        contract.nft_transfer_call(
+            new_user.clone(),
+            USER_ACCOUNT.to_string(),
+            None,
+            None,
+            "".to_string(),
+        );
+        testing_env!(
+            VMContext {
+                predecessor_account_id: CONTRACT_ACCOUNT.parse().unwrap(),
+                storage_usage: env::storage_usage(),
+                ..context
+            },
+            near_sdk::VMConfig::test(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![result],
+        );
+        let resolved = contract.resolve_position_transfer(user.clone(), new_user.clone(), U128(10));
+
+        // perform assertions
+        assert_eq!(resolved, kept);
+        let (user_balance, new_user_balance) = if kept { (0, 10) } else { (10, 0) };
+        assert_eq!(contract.ft_balance_of(user), U128(user_balance));
+        assert_eq!(contract.ft_balance_of(new_user), U128(new_user_balance));
+    }
+
+    #[rstest]
+    /// Test nft_approve, nft_is_approved and nft_revoke methods
+    /// ASSERT:
+    /// (1) Only the holder can approve its position
+    #[should_panic = "Token owner.testnet is not owned by user.testnet"]
+    #[case(USER_ACCOUNT, OWNER_ACCOUNT, false)]
+    /// (2) Approved account can transfer the position with a matching
+    ///     approval id, which clears every approval
+    #[case(USER_ACCOUNT, USER_ACCOUNT, false)]
+    /// (3) Revoked accounts are no longer approved
+    #[case(USER_ACCOUNT, USER_ACCOUNT, true)]
+    fn test_nft_approve(#[case] caller: &str, #[case] token_id: &str, #[case] revoke: bool) {
+        // setup
+        let context = get_context(
+            vec![],
+            10u128.pow(24),
+            10u128.pow(24),
+            caller.parse().unwrap(),
+            0,
+            Gas(300u64 * 10u64.pow(12)),
+        );
+        testing_env!(context.clone());
+        let mut contract = init_contract(1);
+        let user: AccountId = USER_ACCOUNT.parse().unwrap();
+        let market: AccountId = ADMIN_ACCOUNT.parse().unwrap();
+        let new_user: AccountId = NEW_USER_ACCOUNT.parse().unwrap();
+        register_user(&mut contract, &user, 10, 0, 0);
+        register_user(&mut contract, &new_user, 0, 0, 0);
+
+        // call tested method
+        contract.nft_approve(token_id.to_string(), new_user.clone(), None);
+        contract.nft_approve(token_id.to_string(), market.clone(), Some("list".to_string()));
+
+        // perform assertions
+        assert!(contract.nft_is_approved(USER_ACCOUNT.to_string(), market.clone(), Some(1)));
+        assert!(!contract.nft_is_approved(USER_ACCOUNT.to_string(), market.clone(), Some(0)));
+        let approved = contract
+            .nft_token(USER_ACCOUNT.to_string())
+            .unwrap()
+            .approved_account_ids
+            .unwrap();
+        assert_eq!(approved.len(), 2);
+        assert_eq!(approved[&market], 1);
+
+        testing_env!(VMContext {
+            attached_deposit: 1,
+            storage_usage: env::storage_usage(),
+            ..context.clone()
+        });
+        if revoke {
+            contract.nft_revoke(USER_ACCOUNT.to_string(), market.clone());
+            assert!(!contract.nft_is_approved(USER_ACCOUNT.to_string(), market, None));
+            assert!(contract.nft_is_approved(USER_ACCOUNT.to_string(), new_user, None));
+            return;
+        }
+        testing_env!(VMContext {
+            predecessor_account_id: market.clone(),
+            attached_deposit: 1,
+            storage_usage: env::storage_usage(),
+            ..context
+        });
+        contract.nft_transfer(new_user.clone(), USER_ACCOUNT.to_string(), Some(1), None);
+        assert_eq!(contract.ft_balance_of(user), U128(0));
+        assert_eq!(contract.ft_balance_of(new_user.clone()), U128(10));
+        assert!(contract
+            .nft_token(NEW_USER_ACCOUNT.to_string())
+            .unwrap()
+            .approved_account_ids
+            .unwrap()
+            .is_empty());
+        assert!(contract
+            .position_approvals
+            .get(&USER_ACCOUNT.parse().unwrap())
+            .unwrap()
+            .approved_account_ids
+            .is_empty());
+    }
+
+    #[rstest]
+    /// Test approvals of positions left without shares
+    /// ASSERT:
+    /// (1) Approvals are revoked, with their storage refunded, once the
+    ///     balance reaches 0 through ft_transfer or ft_burn, and do not
+    ///     apply to the position acquired afterwards
+    #[case(10, false)]
+    #[case(10, true)]
+    /// (2) Approvals are kept while the holder has shares
+    #[case(5, false)]
+    fn test_approvals_cleared(#[case] amount: u128, #[case] burn: bool) {
+        // setup
+        let context = get_context(
+            vec![],
+            10u128.pow(24),
+            10u128.pow(24),
+            USER_ACCOUNT.parse().unwrap(),
+            0,
+            Gas(300u64 * 10u64.pow(12)),
+        );
+        testing_env!(context.clone());
+        let mut contract = init_contract(1);
+        let user: AccountId = USER_ACCOUNT.parse().unwrap();
+        let market: AccountId = ADMIN_ACCOUNT.parse().unwrap();
+        let new_user: AccountId = NEW_USER_ACCOUNT.parse().unwrap();
+        register_user(&mut contract, &user, 10, 0, 0);
+        register_user(&mut contract, &new_user, 0, 0, 0);
+        contract.nft_approve(USER_ACCOUNT.to_string(), market.clone(), None);
+        testing_env!(VMContext {
+            attached_deposit: 1,
+            storage_usage: env::storage_usage(),
+            ..context.clone()
+        });
+
+        // call tested method
+        if burn {
+            contract.ft_burn(U128(amount), None);
+        } else {
+            contract.ft_transfer(new_user.clone(), U128(amount), None);
+        }
+
+        // perform assertions
+        let cleared = amount == 10;
+        let receipts = get_created_receipts();
+        assert_eq!(receipts.len(), if cleared { 1 } else { 0 });
+        testing_env!(VMContext {
+            predecessor_account_id: OWNER_ACCOUNT.parse().unwrap(),
+            attached_deposit: 1,
+            storage_usage: env::storage_usage(),
+            ..context
+        });
+        contract.ft_transfer(user, U128(10), None);
+        assert_eq!(
+            contract.nft_is_approved(USER_ACCOUNT.to_string(), market, None),
+            !cleared
+        );
+    }
+}
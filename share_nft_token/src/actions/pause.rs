@@ -0,0 +1,214 @@
+//! Pause actions module
+//!
+//! Allows pausers to halt share transfers, reward deposits
+//! and reward claims independently, or all at once through
+//! emergency_stop, in case a bug is discovered.
+
+use crate::events::PauseChanged;
+use crate::pause::{PauseFeature, PauseFlags};
+use crate::*;
+
+#[near_bindgen]
+impl Contract {
+    #[payable]
+    pub fn pause(&mut self, feature: PauseFeature) {
+        assert_one_yocto();
+        self.assert_role(&Role::Pauser);
+        self.internal_set_paused(&feature, true);
+    }
+
+    #[payable]
+    pub fn unpause(&mut self, feature: PauseFeature) {
+        assert_one_yocto();
+        self.assert_role(&Role::Pauser);
+        self.internal_set_paused(&feature, false);
+    }
+
+    /// Pauses every pausable feature in a single call
+    #[payable]
+    pub fn emergency_stop(&mut self) {
+        assert_one_yocto();
+        self.assert_role(&Role::Pauser);
+        for feature in [
+            PauseFeature::Transfers,
+            PauseFeature::Deposits,
+            PauseFeature::Claims,
+        ] {
+            self.internal_set_paused(&feature, true);
+        }
+    }
+
+    pub fn view_pause_state(&self) -> PauseFlags {
+        self.pause_flags.clone()
+    }
+}
+
+impl Contract {
+    fn internal_set_paused(&mut self, feature: &PauseFeature, paused: bool) {
+        if self.pause_flags.is_paused(feature) == paused {
+            return;
+        }
+        self.pause_flags.set(feature, paused);
+        let event = PauseChanged {
+            feature,
+            account_id: &env::predecessor_account_id(),
+        };
+        if paused {
+            event.emit_paused();
+        } else {
+            event.emit_unpaused();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::tests::*;
+
+    #[rstest]
+    /// Test pause and unpause methods
+    /// ASSERT:
+    /// (1) Call requires 1 yocto
+    #[should_panic = "Requires attached deposit of exactly 1 yoctoNEAR"]
+    #[case(0, OWNER_ACCOUNT, PauseFeature::Transfers)]
+    /// (2) Only pausers can pause
+    #[should_panic = "user.testnet does not have the pauser role"]
+    #[case(1, USER_ACCOUNT, PauseFeature::Transfers)]
+    /// (3) Flag is set, reported by view and unset by unpause
+    #[case(1, OWNER_ACCOUNT, PauseFeature::Transfers)]
+    #[case(1, OWNER_ACCOUNT, PauseFeature::Deposits)]
+    #[case(1, PAUSER_ACCOUNT, PauseFeature::Claims)]
+    fn test_pause(#[case] deposit: u128, #[case] caller: &str, #[case] feature: PauseFeature) {
+        // setup
+        let context = get_context(
+            vec![],
+            deposit,
+            0,
+            caller.parse().unwrap(),
+            0,
+            Gas(300u64 * 10u64.pow(12)),
+        );
+        testing_env!(context);
+        let mut contract = init_contract(1);
+        crate::tests::grant_role(&mut contract, PAUSER_ACCOUNT, Role::Pauser);
+
+        // call tested method
+        contract.pause(feature);
+
+        // perform assertions
+        let state = contract.view_pause_state();
+        assert!(state.is_paused(&feature));
+        assert_eq!(
+            state.transfers as u8 + state.deposits as u8 + state.claims as u8,
+            1
+        );
+        assert!(get_logs()[0].contains(r#""event":"feature_paused""#));
+
+        contract.unpause(feature);
+        assert!(!contract.view_pause_state().is_paused(&feature));
+        assert!(get_logs()[1].contains(r#""event":"feature_unpaused""#));
+    }
+
+    #[rstest]
+    /// Test emergency_stop method
+    /// ASSERT:
+    /// (1) Every feature gets paused
+    fn test_emergency_stop() {
+        // setup
+        let context = get_context(
+            vec![],
+            1,
+            0,
+            OWNER_ACCOUNT.parse().unwrap(),
+            0,
+            Gas(300u64 * 10u64.pow(12)),
+        );
+        testing_env!(context);
+        let mut contract = init_contract(1);
+
+        // call tested method
+        contract.emergency_stop();
+
+        // perform assertions
+        let state = contract.view_pause_state();
+        assert!(state.transfers && state.deposits && state.claims);
+        assert_eq!(get_logs().len(), 3);
+    }
+
+    /// Every entry point guarded by a pause flag
+    #[derive(Debug)]
+    pub enum EntryPoint {
+        FtTransfer,
+        FtTransferCall,
+        ClaimRewards,
+        NearDepositRewards,
+        FtOnTransfer,
+    }
+
+    #[rstest]
+    /// Test guarded entry points
+    /// ASSERT:
+    /// (1) Every entry point panics when its feature is paused
+    #[should_panic = "Contract transfers are paused"]
+    #[case(EntryPoint::FtTransfer, PauseFeature::Transfers)]
+    #[should_panic = "Contract transfers are paused"]
+    #[case(EntryPoint::FtTransferCall, PauseFeature::Transfers)]
+    #[should_panic = "Contract claims are paused"]
+    #[case(EntryPoint::ClaimRewards, PauseFeature::Claims)]
+    #[should_panic = "Contract deposits are paused"]
+    #[case(EntryPoint::NearDepositRewards, PauseFeature::Deposits)]
+    #[should_panic = "Contract deposits are paused"]
+    #[case(EntryPoint::FtOnTransfer, PauseFeature::Deposits)]
+    /// (2) Pausing other features does not affect the entry point
+    #[case(EntryPoint::FtTransfer, PauseFeature::Deposits)]
+    #[case(EntryPoint::FtTransferCall, PauseFeature::Claims)]
+    #[case(EntryPoint::ClaimRewards, PauseFeature::Transfers)]
+    #[case(EntryPoint::NearDepositRewards, PauseFeature::Claims)]
+    #[case(EntryPoint::FtOnTransfer, PauseFeature::Transfers)]
+    fn test_guarded_entry_points(
+        #[case] entry_point: EntryPoint,
+        #[case] paused_feature: PauseFeature,
+    ) {
+        // setup
+        let context = get_context(
+            vec![],
+            1,
+            0,
+            OWNER_ACCOUNT.parse().unwrap(),
+            0,
+            Gas(300u64 * 10u64.pow(12)),
+        );
+        testing_env!(context.clone());
+        let mut contract = init_contract(1);
+        let user: AccountId = USER_ACCOUNT.parse().unwrap();
+        register_user(&mut contract, &user, 0, 0, 0);
+        contract.pause(paused_feature);
+
+        let predecessor = match entry_point {
+            EntryPoint::FtOnTransfer => REWARDS_TOKEN_ACCOUNT.parse().unwrap(),
+            _ => OWNER_ACCOUNT.parse().unwrap(),
+        };
+        testing_env!(VMContext {
+            predecessor_account_id: predecessor,
+            storage_usage: env::storage_usage(),
+            ..context
+        });
+
+        // call tested method
+        match entry_point {
+            EntryPoint::FtTransfer => contract.ft_transfer(user, U128(10), None),
+            EntryPoint::FtTransferCall => {
+                contract.ft_transfer_call(user, U128(10), None, "".to_string());
+            }
+            EntryPoint::ClaimRewards => {
+                contract.claim_rewards();
+            }
+            EntryPoint::NearDepositRewards => contract.near_deposit_rewards(),
+            EntryPoint::FtOnTransfer => {
+                contract.ft_on_transfer(user, U128(10), "deposit_profits".to_string());
+            }
+        }
+    }
+}
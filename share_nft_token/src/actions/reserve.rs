@@ -0,0 +1,100 @@
+//! Reserve actions module
+//!
+//! The issuer can deposit reward tokens into a reserve, kept apart
+//! from dividends, by calling ft_transfer_call on the reward token
+//! with msg "fund_reserve". The reserve funds corporate actions,
+//! such as cash outs of fractional shares on splits.
+
+use crate::ext_interface::{ext_ft, ext_self, FT_TRANSFER_GAS, REWARD_WITHDRAW_CALLBACK_GAS};
+use crate::*;
+use near_sdk::is_promise_success;
+
+#[near_bindgen]
+impl Contract {
+    /// Transfers amount of the reserve back to the owner
+    #[payable]
+    pub fn withdraw_reserve(&mut self, amount: U128) -> Promise {
+        assert_one_yocto();
+        self.assert_role(&Role::Owner);
+        assert!(
+            amount.0 > 0 && amount.0 <= self.reserve_token.0,
+            "Reserve only holds {} reward tokens",
+            self.reserve_token.0
+        );
+        self.reserve_token = U128(self.reserve_token.0 - amount.0);
+        ext_ft::ext(self.reward_token.clone())
+            .with_static_gas(FT_TRANSFER_GAS)
+            .with_attached_deposit(1)
+            .ft_transfer(self.owner_id.clone(), amount, None)
+            .then(
+                ext_self::ext(env::current_account_id())
+                    .with_static_gas(REWARD_WITHDRAW_CALLBACK_GAS)
+                    .resolve_reserve_withdraw(amount),
+            )
+    }
+
+    #[private]
+    pub fn resolve_reserve_withdraw(&mut self, amount: U128) {
+        if !is_promise_success() {
+            self.reserve_token = U128(self.reserve_token.0 + amount.0);
+        }
+    }
+
+    pub fn view_reserve(&self) -> U128 {
+        self.reserve_token
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::tests::*;
+
+    #[rstest]
+    /// Test reserve funding and withdraw_reserve method
+    /// ASSERT:
+    /// (1) Only the owner can withdraw
+    #[should_panic = "admin.testnet does not have the owner role"]
+    #[case(ADMIN_ACCOUNT, 10)]
+    /// (2) Cannot withdraw more than the reserve
+    #[should_panic = "Reserve only holds 100 reward tokens"]
+    #[case(OWNER_ACCOUNT, 101)]
+    /// (3) Reserve is reduced and transferred with a callback
+    #[case(OWNER_ACCOUNT, 100)]
+    fn test_withdraw_reserve(#[case] caller: &str, #[case] amount: u128) {
+        // setup
+        let context = get_context(
+            vec![],
+            1,
+            0,
+            REWARDS_TOKEN_ACCOUNT.parse().unwrap(),
+            0,
+            Gas(300u64 * 10u64.pow(12)),
+        );
+        testing_env!(context.clone());
+        let mut contract = init_contract(1);
+        crate::tests::grant_role(&mut contract, ADMIN_ACCOUNT, Role::Admin);
+        contract.ft_on_transfer(
+            OWNER_ACCOUNT.parse().unwrap(),
+            U128(100),
+            "fund_reserve".to_string(),
+        );
+        assert_eq!(contract.view_reserve(), U128(100));
+        assert_eq!(contract.contract_rps_token, U128(0));
+        testing_env!(VMContext {
+            predecessor_account_id: caller.parse().unwrap(),
+            storage_usage: env::storage_usage(),
+            ..context
+        });
+
+        // call tested method
+        contract.withdraw_reserve(U128(amount));
+
+        // perform assertions
+        assert_eq!(contract.view_reserve(), U128(100 - amount));
+        let receipts = get_created_receipts();
+        assert_eq!(receipts.len(), 2);
+        assert_eq!(receipts[0].receiver_id, REWARDS_TOKEN_ACCOUNT.parse().unwrap());
+    }
+}
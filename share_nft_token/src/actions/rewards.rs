@@ -14,6 +14,7 @@ impl Contract {
     #[payable]
     pub fn claim_rewards(&mut self) -> PromiseOrValue<bool> {
         assert_one_yocto();
+        self.assert_not_paused(&PauseFeature::Claims);
         let account_id = env::predecessor_account_id();
         let withdraw_value = self.withdraw_rewards(&account_id);
 
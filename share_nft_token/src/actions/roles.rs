@@ -0,0 +1,259 @@
+//! Roles actions module
+//!
+//! Allows the owner and admins to delegate administrative
+//! roles to other accounts and the owner to hand over the
+//! contract to a new owner.
+//!
+//! Only the owner can grant or revoke the admin role, admins
+//! can grant or revoke every other role. The owner role itself
+//! can only change hands through transfer_ownership.
+
+use crate::events::{OwnershipTransferred, RoleGranted, RoleRevoked};
+use crate::roles::Role;
+use crate::*;
+
+#[near_bindgen]
+impl Contract {
+    #[payable]
+    pub fn grant_role(&mut self, account_id: AccountId, role: Role) {
+        assert_one_yocto();
+        self.assert_can_manage_role(&role);
+        let mut members = self
+            .role_members
+            .get(&role)
+            .unwrap_or_else(|| UnorderedSet::new(StorageKey::RoleMembers { role }));
+        if members.insert(&account_id) {
+            self.role_members.insert(&role, &members);
+            RoleGranted {
+                account_id: &account_id,
+                role: &role,
+                granted_by: &env::predecessor_account_id(),
+            }
+            .emit();
+        }
+    }
+
+    #[payable]
+    pub fn revoke_role(&mut self, account_id: AccountId, role: Role) {
+        assert_one_yocto();
+        self.assert_can_manage_role(&role);
+        self.internal_remove_role(&account_id, &role, &env::predecessor_account_id());
+    }
+
+    /// Allows any role member to give up one of its roles
+    #[payable]
+    pub fn renounce_role(&mut self, role: Role) {
+        assert_one_yocto();
+        assert!(role != Role::Owner, "Owner must use transfer_ownership");
+        let account_id = env::predecessor_account_id();
+        self.internal_remove_role(&account_id, &role, &account_id);
+    }
+
+    #[payable]
+    pub fn transfer_ownership(&mut self, new_owner_id: AccountId) {
+        assert_one_yocto();
+        self.assert_role(&Role::Owner);
+        let old_owner_id = std::mem::replace(&mut self.owner_id, new_owner_id);
+        OwnershipTransferred {
+            old_owner_id: &old_owner_id,
+            new_owner_id: &self.owner_id,
+        }
+        .emit();
+    }
+
+    pub fn view_owner(&self) -> AccountId {
+        self.owner_id.clone()
+    }
+
+    /// Returns every role held by account_id, including roles
+    /// implied by being owner or admin.
+    pub fn view_roles(&self, account_id: AccountId) -> Vec<Role> {
+        [
+            Role::Owner,
+            Role::Admin,
+            Role::Pauser,
+            Role::MetadataManager,
+            Role::ComplianceOfficer,
+            Role::Minter,
+        ]
+        .into_iter()
+        .filter(|role| self.has_role(&account_id, role))
+        .collect()
+    }
+
+    /// Returns accounts that were explicitly granted role
+    pub fn view_role_members(&self, role: Role) -> Vec<AccountId> {
+        match role {
+            Role::Owner => vec![self.owner_id.clone()],
+            _ => self
+                .role_members
+                .get(&role)
+                .map(|members| members.to_vec())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+impl Contract {
+    fn assert_can_manage_role(&self, role: &Role) {
+        match role {
+            Role::Owner => panic!("Owner must use transfer_ownership"),
+            Role::Admin => self.assert_role(&Role::Owner),
+            _ => self.assert_role(&Role::Admin),
+        }
+    }
+
+    fn internal_remove_role(&mut self, account_id: &AccountId, role: &Role, revoked_by: &AccountId) {
+        if let Some(mut members) = self.role_members.get(role) {
+            if members.remove(account_id) {
+                self.role_members.insert(role, &members);
+                RoleRevoked {
+                    account_id,
+                    role,
+                    revoked_by,
+                }
+                .emit();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::tests::*;
+
+    #[rstest]
+    /// Test grant_role method
+    /// ASSERT:
+    /// (1) Call requires 1 yocto
+    #[should_panic = "Requires attached deposit of exactly 1 yoctoNEAR"]
+    #[case(0, OWNER_ACCOUNT, Role::Pauser)]
+    /// (2) Owner role cannot be granted
+    #[should_panic = "Owner must use transfer_ownership"]
+    #[case(1, OWNER_ACCOUNT, Role::Owner)]
+    /// (3) Only owner can grant admin
+    #[should_panic = "admin.testnet does not have the owner role"]
+    #[case(1, ADMIN_ACCOUNT, Role::Admin)]
+    /// (4) Regular users cannot grant roles
+    #[should_panic = "user.testnet does not have the admin role"]
+    #[case(1, USER_ACCOUNT, Role::Pauser)]
+    /// (5) Owner and admins can grant roles and an event is emitted
+    #[case(1, OWNER_ACCOUNT, Role::Admin)]
+    #[case(1, ADMIN_ACCOUNT, Role::MetadataManager)]
+    fn test_grant_role(#[case] deposit: u128, #[case] caller: &str, #[case] role: Role) {
+        // setup
+        let context = get_context(
+            vec![],
+            deposit,
+            0,
+            caller.parse().unwrap(),
+            0,
+            Gas(300u64 * 10u64.pow(12)),
+        );
+        testing_env!(context);
+        let mut contract = init_contract(1);
+        crate::tests::grant_role(&mut contract, ADMIN_ACCOUNT, Role::Admin);
+        let user: AccountId = "grantee.testnet".parse().unwrap();
+
+        // call tested method
+        contract.grant_role(user.clone(), role);
+
+        // perform assertions
+        assert!(contract.has_role(&user, &role));
+        assert!(contract.view_role_members(role).contains(&user));
+        let logs = get_logs();
+        assert_eq!(logs.len(), 1);
+        assert!(logs[0].contains(r#""event":"role_granted""#));
+    }
+
+    #[rstest]
+    /// Test revoke_role and renounce_role methods
+    /// ASSERT:
+    /// (1) Admin can revoke non admin roles
+    /// (2) Members can renounce their own roles
+    /// (3) Revoked accounts no longer pass assert_role
+    #[case(ADMIN_ACCOUNT, false)]
+    #[case(USER_ACCOUNT, true)]
+    fn test_revoke_role(#[case] caller: &str, #[case] renounce: bool) {
+        // setup
+        let context = get_context(
+            vec![],
+            1,
+            0,
+            OWNER_ACCOUNT.parse().unwrap(),
+            0,
+            Gas(300u64 * 10u64.pow(12)),
+        );
+        testing_env!(context.clone());
+        let mut contract = init_contract(1);
+        let user: AccountId = USER_ACCOUNT.parse().unwrap();
+        contract.grant_role(ADMIN_ACCOUNT.parse().unwrap(), Role::Admin);
+        contract.grant_role(user.clone(), Role::Pauser);
+        assert!(contract.has_role(&user, &Role::Pauser));
+
+        testing_env!(VMContext {
+            predecessor_account_id: caller.parse().unwrap(),
+            storage_usage: env::storage_usage(),
+            ..context
+        });
+
+        // call tested method
+        if renounce {
+            contract.renounce_role(Role::Pauser);
+        } else {
+            contract.revoke_role(user.clone(), Role::Pauser);
+        }
+
+        // perform assertions
+        assert!(!contract.has_role(&user, &Role::Pauser));
+        assert!(get_logs()
+            .last()
+            .unwrap()
+            .contains(r#""event":"role_revoked""#));
+    }
+
+    #[rstest]
+    /// Test transfer_ownership method
+    /// ASSERT:
+    /// (1) Only owner can transfer ownership
+    #[should_panic = "admin.testnet does not have the owner role"]
+    #[case(ADMIN_ACCOUNT)]
+    /// (2) New owner holds every role and old owner none
+    #[case(OWNER_ACCOUNT)]
+    fn test_transfer_ownership(#[case] caller: &str) {
+        // setup
+        let context = get_context(
+            vec![],
+            1,
+            0,
+            OWNER_ACCOUNT.parse().unwrap(),
+            0,
+            Gas(300u64 * 10u64.pow(12)),
+        );
+        testing_env!(context.clone());
+        let mut contract = init_contract(1);
+        contract.grant_role(ADMIN_ACCOUNT.parse().unwrap(), Role::Admin);
+        testing_env!(VMContext {
+            predecessor_account_id: caller.parse().unwrap(),
+            storage_usage: env::storage_usage(),
+            ..context
+        });
+        let new_owner: AccountId = USER_ACCOUNT.parse().unwrap();
+
+        // call tested method
+        contract.transfer_ownership(new_owner.clone());
+
+        // perform assertions
+        assert_eq!(contract.view_owner(), new_owner);
+        assert_eq!(contract.view_roles(new_owner).len(), 6);
+        assert!(contract
+            .view_roles(OWNER_ACCOUNT.parse().unwrap())
+            .is_empty());
+        assert!(get_logs()
+            .last()
+            .unwrap()
+            .contains(r#""event":"ownership_transferred""#));
+    }
+}
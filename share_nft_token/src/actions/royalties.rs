@@ -0,0 +1,409 @@
+//! Royalties actions module
+//!
+//! Implements NEP-199 payouts, so marketplaces selling a position NFT
+//! pay royalties out of its price. The owner configures a part of the
+//! price for each royalty account, such as the artist, and a part for
+//! the dividend pool, the seller receives the rest.
+//!
+//! The dividend pool part is listed under an account set by the owner,
+//! since marketplaces pay payouts by plain transfers, which the
+//! contract could not tell apart from its other funds. That account
+//! deposits it through deposit_royalties, or through ft_transfer_call
+//! with msg "deposit_royalties" in the reward token. Royalties are
+//! distributed among holders as they are received, the payout itself
+//! credits nothing.
+
+use crate::events::{RoyaltiesDeposited, RoyaltiesUpdated, RoyaltyOwed};
+use crate::royalties::Payout;
+use crate::sale::SaleCurrency;
+use crate::*;
+use std::collections::HashMap;
+
+#[near_bindgen]
+impl Contract {
+    /// Replaces the royalties paid on sales of positions, None disables
+    /// them. Caller pays for the storage of the royalties, the excess
+    /// is refunded.
+    #[payable]
+    pub fn set_royalties(&mut self, royalties: Option<RoyaltyConfig>) {
+        assert!(
+            env::attached_deposit() >= 1,
+            "Requires attached deposit of at least 1 yoctoNEAR"
+        );
+        self.assert_role(&Role::Owner);
+        if let Some(royalties) = &royalties {
+            royalties.assert_valid();
+        }
+        RoyaltiesUpdated {
+            updated_by: &env::predecessor_account_id(),
+            royalties: royalties.as_ref(),
+        }
+        .emit();
+        let previous_size = self.royalties.try_to_vec().unwrap().len();
+        let new_size = royalties.try_to_vec().unwrap().len();
+        self.royalties = royalties;
+        self.charge_storage_bytes(
+            new_size.saturating_sub(previous_size) as StorageUsage,
+            &env::predecessor_account_id(),
+        );
+    }
+
+    /// Amounts owed to each account if position token_id is sold for
+    /// balance. Panics if they must be split among more than
+    /// max_len_payout accounts.
+    pub fn nft_payout(&self, token_id: TokenId, balance: U128, max_len_payout: Option<u32>) -> Payout {
+        let owner_id = self.internal_position_owner(&token_id);
+        self.internal_payout(&owner_id, balance.0, max_len_payout).0
+    }
+
+    /// Transfers position token_id to receiver_id, as nft_transfer, and
+    /// returns the amounts the marketplace owes to each account for
+    /// balance, see nft_payout
+    #[payable]
+    #[allow(clippy::too_many_arguments)]
+    pub fn nft_transfer_payout(
+        &mut self,
+        receiver_id: AccountId,
+        token_id: TokenId,
+        approval_id: Option<u64>,
+        memo: Option<String>,
+        balance: U128,
+        max_len_payout: Option<u32>,
+    ) -> Payout {
+        assert_one_yocto();
+        let owner_id = self.internal_position_owner(&token_id);
+        let (payout, pool_royalty) = self.internal_payout(&owner_id, balance.0, max_len_payout);
+        let sender_id = env::predecessor_account_id();
+        self.internal_transfer_position(&sender_id, &receiver_id, &token_id, approval_id, memo);
+        if pool_royalty > 0 {
+            RoyaltyOwed {
+                token_id: &token_id,
+                balance: &balance,
+                amount: &U128(pool_royalty),
+            }
+            .emit();
+        }
+        payout
+    }
+
+    /// Distributes the attached NEAR, royalties owed to the dividend
+    /// pool, among holders as NEAR rewards
+    #[payable]
+    pub fn deposit_royalties(&mut self) {
+        self.internal_deposit_royalties(
+            &env::predecessor_account_id(),
+            SaleCurrency::Near,
+            env::attached_deposit(),
+        );
+    }
+
+    pub fn view_royalties(&self) -> Option<RoyaltyConfig> {
+        self.royalties.clone()
+    }
+}
+
+impl Contract {
+    /// Distributes amount of royalties received in currency among holders
+    pub fn internal_deposit_royalties(&mut self, depositor: &AccountId, currency: SaleCurrency, amount: u128) {
+        self.assert_not_paused(&PauseFeature::Deposits);
+        assert!(amount > 0, "Royalties must be greater than 0");
+        match currency {
+            SaleCurrency::Near => self.distribute_rewards_near(amount),
+            SaleCurrency::RewardToken => self.distribute_rewards_token(amount),
+        }
+        RoyaltiesDeposited {
+            depositor,
+            currency: &currency,
+            amount: &U128(amount),
+        }
+        .emit();
+    }
+
+    /// Splits balance, the price of the position of owner_id, among
+    /// royalty accounts, the dividend pool and owner_id. Returns the
+    /// payout and the part of the dividend pool.
+    fn internal_payout(
+        &self,
+        owner_id: &AccountId,
+        balance: u128,
+        max_len_payout: Option<u32>,
+    ) -> (Payout, u128) {
+        let mut payout: HashMap<AccountId, U128> = HashMap::new();
+        let mut royalties_paid = 0;
+        let mut pool_royalty = 0;
+        let mut add = |account_id: &AccountId, amount: u128| {
+            let entry = payout.entry(account_id.clone()).or_insert(U128(0));
+            entry.0 += amount;
+        };
+        if let Some(royalties) = &self.royalties {
+            for (account_id, bps) in royalties.accounts.iter() {
+                let amount = RoyaltyConfig::royalty(balance, *bps);
+                add(account_id, amount);
+                royalties_paid += amount;
+            }
+            pool_royalty = RoyaltyConfig::royalty(balance, royalties.dividend_pool_bps);
+            if pool_royalty > 0 {
+                add(&royalties.dividend_pool_account_id, pool_royalty);
+                royalties_paid += pool_royalty;
+            }
+        }
+        add(owner_id, balance - royalties_paid);
+        if let Some(max_len_payout) = max_len_payout {
+            assert!(
+                payout.len() <= max_len_payout as usize,
+                "Payout cannot be split among more than {} accounts",
+                max_len_payout
+            );
+        }
+        (Payout { payout }, pool_royalty)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::tests::*;
+
+    /// Account collecting the dividend pool part of payouts
+    const POOL_ACCOUNT: &str = "pool.testnet";
+
+    /// Royalties of 5% to the artist and 2% to the dividend pool
+    fn royalties() -> RoyaltyConfig {
+        RoyaltyConfig {
+            accounts: HashMap::from([(ADMIN_ACCOUNT.parse().unwrap(), 500)]),
+            dividend_pool_bps: 200,
+            dividend_pool_account_id: POOL_ACCOUNT.parse().unwrap(),
+        }
+    }
+
+    #[rstest]
+    /// Test set_royalties method
+    /// ASSERT:
+    /// (1) Only the owner can set royalties
+    #[should_panic = "admin.testnet does not have the owner role"]
+    #[case(ADMIN_ACCOUNT, 500, 200, POOL_ACCOUNT)]
+    /// (2) Royalties are capped
+    #[should_panic = "Royalties cannot exceed 5000 basis points"]
+    #[case(OWNER_ACCOUNT, 4000, 1001, POOL_ACCOUNT)]
+    /// (3) Dividend pool royalties cannot be paid to the contract
+    #[should_panic = "Dividend pool royalties cannot be paid to the contract itself"]
+    #[case(OWNER_ACCOUNT, 500, 200, CONTRACT_ACCOUNT)]
+    /// (4) Royalties are stored
+    #[case(OWNER_ACCOUNT, 500, 200, POOL_ACCOUNT)]
+    fn test_set_royalties(
+        #[case] caller: &str,
+        #[case] artist_bps: u16,
+        #[case] dividend_pool_bps: u16,
+        #[case] dividend_pool_account_id: &str,
+    ) {
+        // setup
+        let context = get_context(
+            vec![],
+            10u128.pow(24),
+            10u128.pow(24),
+            caller.parse().unwrap(),
+            0,
+            Gas(300u64 * 10u64.pow(12)),
+        );
+        testing_env!(context);
+        let mut contract = init_contract(1);
+
+        // call tested method
+        contract.set_royalties(Some(RoyaltyConfig {
+            accounts: HashMap::from([(ADMIN_ACCOUNT.parse().unwrap(), artist_bps)]),
+            dividend_pool_bps,
+            dividend_pool_account_id: dividend_pool_account_id.parse().unwrap(),
+        }));
+
+        // perform assertions
+        let royalties = contract.view_royalties().unwrap();
+        assert_eq!(royalties.dividend_pool_bps, dividend_pool_bps);
+        assert!(get_logs()[0].contains(r#""event":"royalties_updated""#));
+    }
+
+    #[rstest]
+    /// Test nft_payout method
+    /// ASSERT:
+    /// (1) Payout must fit in max_len_payout
+    #[should_panic = "Payout cannot be split among more than 2 accounts"]
+    #[case(true, Some(2))]
+    /// (2) Royalties are paid out of the price, the seller gets the rest
+    #[case(true, Some(3))]
+    /// (3) Seller gets the whole price without royalties
+    #[case(false, None)]
+    fn test_nft_payout(#[case] with_royalties: bool, #[case] max_len_payout: Option<u32>) {
+        // setup
+        let context = get_context(
+            vec![],
+            0,
+            0,
+            USER_ACCOUNT.parse().unwrap(),
+            0,
+            Gas(300u64 * 10u64.pow(12)),
+        );
+        testing_env!(context);
+        let mut contract = init_contract(1);
+        register_user(&mut contract, &USER_ACCOUNT.parse().unwrap(), 10, 0, 0);
+        if with_royalties {
+            contract.royalties = Some(royalties());
+        }
+
+        // call tested method
+        let payout = contract
+            .nft_payout(USER_ACCOUNT.to_string(), U128(1_000), max_len_payout)
+            .payout;
+
+        // perform assertions
+        let amount = |account: &str| payout[&account.parse::<AccountId>().unwrap()].0;
+        if with_royalties {
+            assert_eq!(payout.len(), 3);
+            assert_eq!(amount(ADMIN_ACCOUNT), 50);
+            assert_eq!(amount(POOL_ACCOUNT), 20);
+            assert_eq!(amount(USER_ACCOUNT), 930);
+        } else {
+            assert_eq!(payout.len(), 1);
+            assert_eq!(amount(USER_ACCOUNT), 1_000);
+        }
+    }
+
+    #[rstest]
+    /// Test nft_transfer_payout method
+    /// ASSERT:
+    /// (1) Position is transferred and the pool royalty is logged,
+    ///     without crediting any reward to holders
+    fn test_nft_transfer_payout() {
+        // setup
+        let context = get_context(
+            vec![],
+            1,
+            0,
+            USER_ACCOUNT.parse().unwrap(),
+            0,
+            Gas(300u64 * 10u64.pow(12)),
+        );
+        testing_env!(context);
+        let mut contract = init_contract(1);
+        let new_user: AccountId = NEW_USER_ACCOUNT.parse().unwrap();
+        register_user(&mut contract, &USER_ACCOUNT.parse().unwrap(), 10, 0, 0);
+        register_user(&mut contract, &new_user, 0, 0, 0);
+        contract.royalties = Some(royalties());
+
+        // call tested method
+        let payout = contract.nft_transfer_payout(
+            new_user.clone(),
+            USER_ACCOUNT.to_string(),
+            None,
+            None,
+            U128(10u128.pow(30)),
+            Some(10),
+        );
+
+        // perform assertions
+        assert_eq!(payout.payout.len(), 3);
+        assert_eq!(contract.ft_balance_of(new_user), U128(10));
+        assert!(get_logs().last().unwrap().contains(r#""event":"royalty_owed""#));
+        assert_eq!(contract.reward_tokens_all_time_count_near, U128(0));
+        assert_eq!(contract.contract_rps_near, U128(0));
+        assert_eq!(contract.undistributed_rewards_near, U128(0));
+    }
+
+    #[rstest]
+    /// Test deposit_royalties method and ft_on_transfer with msg
+    /// "deposit_royalties"
+    /// ASSERT:
+    /// (1) Royalties must not be empty
+    #[should_panic = "Royalties must be greater than 0"]
+    #[case(SaleCurrency::Near, 0)]
+    /// (2) Received royalties are distributed among holders
+    #[case(SaleCurrency::Near, 2 * TOKEN_SUPPLY.0)]
+    #[case(SaleCurrency::RewardToken, 2 * TOKEN_SUPPLY.0)]
+    fn test_deposit_royalties(#[case] currency: SaleCurrency, #[case] amount: u128) {
+        // setup
+        let context = get_context(
+            vec![],
+            amount,
+            0,
+            REWARDS_TOKEN_ACCOUNT.parse().unwrap(),
+            0,
+            Gas(300u64 * 10u64.pow(12)),
+        );
+        testing_env!(context);
+        let mut contract = init_contract(1);
+
+        // call tested method
+        match currency {
+            SaleCurrency::Near => contract.deposit_royalties(),
+            SaleCurrency::RewardToken => {
+                let refund = contract.ft_on_transfer(
+                    ADMIN_ACCOUNT.parse().unwrap(),
+                    U128(amount),
+                    "deposit_royalties".to_string(),
+                );
+                assert_eq!(refund, U128(0));
+            }
+        }
+
+        // perform assertions
+        let rewards = contract.view_claimable_rewards(OWNER_ACCOUNT.parse().unwrap());
+        let reward_key = match currency {
+            SaleCurrency::Near => "NEAR",
+            SaleCurrency::RewardToken => REWARDS_TOKEN_ACCOUNT,
+        };
+        assert_eq!(rewards[reward_key].0, 2 * TOKEN_SUPPLY.0);
+        assert!(get_logs()[0].contains(r#""event":"royalties_deposited""#));
+    }
+
+    #[rstest]
+    /// Test royalties paid by plain transfer
+    /// ASSERT:
+    /// (1) The dividend pool part is paid to the pool account, which
+    ///     deposits it for holders to claim
+    fn test_pool_royalty_paid_by_transfer() {
+        // setup
+        let context = get_context(
+            vec![],
+            1,
+            0,
+            USER_ACCOUNT.parse().unwrap(),
+            0,
+            Gas(300u64 * 10u64.pow(12)),
+        );
+        testing_env!(context.clone());
+        let mut contract = init_contract(1);
+        let user: AccountId = USER_ACCOUNT.parse().unwrap();
+        let new_user: AccountId = NEW_USER_ACCOUNT.parse().unwrap();
+        register_user(&mut contract, &user, TOKEN_SUPPLY.0, 0, 0);
+        register_user(&mut contract, &new_user, 0, 0, 0);
+        contract.royalties = Some(royalties());
+        let payout = contract.nft_transfer_payout(
+            new_user.clone(),
+            USER_ACCOUNT.to_string(),
+            None,
+            None,
+            U128(200 * TOKEN_SUPPLY.0),
+            Some(10),
+        );
+
+        // call tested method
+        // the marketplace pays every account of the payout by plain
+        // transfer, the pool account then deposits what it received
+        let pool_royalty = payout.payout[&POOL_ACCOUNT.parse::<AccountId>().unwrap()].0;
+        testing_env!(VMContext {
+            predecessor_account_id: POOL_ACCOUNT.parse().unwrap(),
+            attached_deposit: pool_royalty,
+            storage_usage: env::storage_usage(),
+            ..context
+        });
+        contract.deposit_royalties();
+
+        // perform assertions
+        assert_eq!(pool_royalty, 4 * TOKEN_SUPPLY.0);
+        let rewards = |contract: &Contract, account_id: AccountId| {
+            contract.view_claimable_rewards(account_id)["NEAR"].0
+        };
+        assert_eq!(rewards(&contract, OWNER_ACCOUNT.parse().unwrap()), 2 * TOKEN_SUPPLY.0);
+        assert_eq!(rewards(&contract, new_user), 2 * TOKEN_SUPPLY.0);
+        assert_eq!(rewards(&contract, user), 0);
+    }
+}
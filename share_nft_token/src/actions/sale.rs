@@ -0,0 +1,695 @@
+//! Sale actions module
+//!
+//! Allows admins to run a fixed-price primary offering of shares.
+//!
+//! The issuer escrows the sale allocation by calling ft_transfer_call
+//! on this contract with receiver_id being the contract itself and
+//! msg "fund_sale". Registered buyers pay either by calling
+//! ft_transfer_call on the reward token with msg "buy_shares" or by
+//! attaching NEAR to buy_shares, depending on the sale currency, and
+//! immediately receive their shares. Payments exceeding what the
+//! per-account maximum or the hard cap allow are refunded.
+//!
+//! Once the sale ends or sells out, anyone can finalize it, sending
+//! the proceeds to the treasury and unsold shares back to the issuer.
+//!
+//! A sale can also be created as a rights offering of new shares,
+//! which are minted as they are bought instead of being escrowed.
+//! Until rights_end, only accounts holding shares on creation can buy,
+//! each up to their pro-rata part of the hard cap. Balances on creation
+//! are snapshotted lazily: update_user_rps records the balance of an
+//! account the first time it changes during the rights phase.
+//! The remainder is then open to the public under the usual limits.
+
+use crate::compliance::TransferParty;
+use crate::events::{RightsOfferingCreated, SaleCreated, SaleFinalized, SharesPurchased};
+use crate::ext_interface::{ext_ft, ext_self, FT_TRANSFER_GAS, REWARD_WITHDRAW_CALLBACK_GAS};
+use crate::sale::{RightsPhase, SaleConfig, SaleCurrency};
+use crate::*;
+use near_sdk::is_promise_success;
+use near_sdk::json_types::U64;
+
+#[near_bindgen]
+impl Contract {
+    /// Creates a sale, only one sale can run at a time.
+    /// Returns the id of the sale.
+    #[payable]
+    pub fn create_sale(&mut self, config: SaleConfig) -> u64 {
+        assert_one_yocto();
+        self.assert_role(&Role::Admin);
+        self.assert_no_split();
+        self.assert_no_buyout();
+        assert!(self.sale.is_none(), "A sale is already running");
+        config.assert_valid();
+        let id = self.sales_count;
+        self.sales_count += 1;
+        SaleCreated {
+            sale_id: id,
+            config: &config,
+        }
+        .emit();
+        self.sale = Some(Sale {
+            id,
+            config,
+            allocation: U128(0),
+            sold: U128(0),
+            proceeds: U128(0),
+            rights: None,
+        });
+        id
+    }
+
+    /// Creates a sale of up to config.hard_cap new shares, which holders
+    /// on creation can buy pro-rata until rights_end before the public.
+    /// config.issuer is unused since shares are minted when bought.
+    /// Returns the id of the sale.
+    #[payable]
+    pub fn create_rights_offering(&mut self, config: SaleConfig, rights_end: U64) -> u64 {
+        assert_one_yocto();
+        self.assert_role(&Role::Admin);
+        self.assert_no_split();
+        self.assert_no_buyout();
+        assert!(self.sale.is_none(), "A sale is already running");
+        config.assert_valid();
+        assert!(
+            config.start.0 < rights_end.0 && rights_end.0 < config.end.0,
+            "rights_end must be between start and end"
+        );
+        let total_supply = self.ft_functionality.total_supply;
+        let mintable = self.minting_policy.mintable(total_supply, config.start.0);
+        assert!(
+            config.hard_cap.0 <= mintable,
+            "Only {} shares can be minted at start",
+            mintable
+        );
+        let escrowed = self
+            .ft_functionality
+            .accounts
+            .get(&env::current_account_id())
+            .unwrap_or(0);
+        let snapshot_supply = U128(total_supply - escrowed);
+        assert!(snapshot_supply.0 > 0, "No holders to grant rights to");
+
+        let id = self.sales_count;
+        self.sales_count += 1;
+        SaleCreated {
+            sale_id: id,
+            config: &config,
+        }
+        .emit();
+        RightsOfferingCreated {
+            sale_id: id,
+            rights_end: &rights_end,
+            snapshot_supply: &snapshot_supply,
+        }
+        .emit();
+        self.sale = Some(Sale {
+            id,
+            allocation: config.hard_cap,
+            config,
+            sold: U128(0),
+            proceeds: U128(0),
+            rights: Some(RightsPhase {
+                end: rights_end,
+                snapshot_supply,
+            }),
+        });
+        id
+    }
+
+    /// Buys shares of a sale paid in NEAR with the attached deposit,
+    /// the excess is refunded
+    #[payable]
+    pub fn buy_shares(&mut self) {
+        let buyer = env::predecessor_account_id();
+        let refund = self.internal_buy_shares(buyer.clone(), env::attached_deposit(), SaleCurrency::Near);
+        if refund > 0 {
+            Promise::new(buyer).transfer(refund);
+        }
+    }
+
+    /// Sends proceeds to the treasury and unsold shares back to the
+    /// issuer once the sale is over. Callable by anyone.
+    pub fn finalize_sale(&mut self) {
+        self.assert_not_paused(&PauseFeature::Transfers);
+        let sale = self.sale.take().expect("No sale is running");
+        assert!(
+            sale.is_over(env::block_timestamp()),
+            "Sale can only be finalized once ended or sold out"
+        );
+        // unsold shares of rights offerings were never minted
+        let unsold = sale.allocation.0 - sale.sold.0;
+        if unsold > 0 && sale.rights.is_none() {
+            self.internal_release_from_escrow(&sale.config.issuer, unsold, "unsold sale allocation");
+        }
+        self.internal_send_proceeds(sale.config.currency, &sale.config.treasury, sale.proceeds.0);
+        SaleFinalized {
+            sale_id: sale.id,
+            sold: &sale.sold,
+            proceeds: &sale.proceeds,
+        }
+        .emit();
+    }
+
+    /// Keeps proceeds the treasury could not receive in the reserve,
+    /// from where the owner can withdraw them
+    #[private]
+    pub fn resolve_proceeds_transfer(&mut self, amount: U128) {
+        if !is_promise_success() {
+            self.reserve_token = U128(self.reserve_token.0 + amount.0);
+        }
+    }
+
+    pub fn view_sale(&self) -> Option<Sale> {
+        self.sale.clone()
+    }
+
+    /// Shares bought by account_id in sale sale_id, which are only
+    /// kept for the latest sale account_id bought shares in
+    pub fn view_sale_purchase(&self, sale_id: u64, account_id: AccountId) -> U128 {
+        U128(purchased_in(&self.sale_purchases, sale_id, &account_id))
+    }
+
+    /// Shares account_id can still buy during the rights phase
+    /// of the running rights offering
+    pub fn view_rights_entitlement(&self, account_id: AccountId) -> U128 {
+        match &self.sale {
+            Some(sale) if sale.is_rights_phase(env::block_timestamp()) => {
+                let subscribed = purchased_in(&self.rights_subscriptions, sale.id, &account_id);
+                U128(self.internal_rights_entitlement(sale, &account_id) - subscribed)
+            }
+            _ => U128(0),
+        }
+    }
+}
+
+impl Contract {
+    /// Transfers offering proceeds to treasury
+    pub fn internal_send_proceeds(&mut self, currency: SaleCurrency, treasury: &AccountId, amount: u128) {
+        if amount == 0 {
+            return;
+        }
+        match currency {
+            SaleCurrency::Near => {
+                Promise::new(treasury.clone()).transfer(amount);
+            }
+            SaleCurrency::RewardToken => {
+                ext_ft::ext(self.reward_token.clone())
+                    .with_static_gas(FT_TRANSFER_GAS)
+                    .with_attached_deposit(1)
+                    .ft_transfer(treasury.clone(), U128(amount), None)
+                    .then(
+                        ext_self::ext(env::current_account_id())
+                            .with_static_gas(REWARD_WITHDRAW_CALLBACK_GAS)
+                            .resolve_proceeds_transfer(U128(amount)),
+                    );
+            }
+        }
+    }
+
+    /// Records the balance of account_id if the rights phase of a
+    /// rights offering is running and it was not recorded yet,
+    /// replacing its snapshot of any previous offering.
+    /// Must be called before every change of a balance.
+    pub fn internal_checkpoint_rights(&mut self, account_id: &AccountId) {
+        if let Some(sale) = &self.sale {
+            if sale.is_rights_phase(env::block_timestamp()) {
+                let recorded = self
+                    .rights_snapshots
+                    .get(account_id)
+                    .is_some_and(|(sale_id, _)| sale_id == sale.id);
+                if !recorded {
+                    let balance = self.ft_functionality.accounts.get(account_id).unwrap_or(0);
+                    self.rights_snapshots.insert(account_id, &(sale.id, U128(balance)));
+                }
+            }
+        }
+    }
+
+    /// Shares account_id is entitled to in the rights offering sale,
+    /// based on its balance when the offering was created
+    pub fn internal_rights_entitlement(&self, sale: &Sale, account_id: &AccountId) -> u128 {
+        if *account_id == env::current_account_id() {
+            return 0;
+        }
+        let snapshot_balance = self
+            .rights_snapshots
+            .get(account_id)
+            .filter(|(sale_id, _)| *sale_id == sale.id)
+            .map(|(_, balance)| balance.0)
+            .unwrap_or_else(|| self.ft_functionality.accounts.get(account_id).unwrap_or(0));
+        sale.entitlement(snapshot_balance)
+    }
+
+    /// Adds shares received from sender_id to the sale allocation
+    pub fn internal_fund_sale(&mut self, sender_id: AccountId, amount: U128) -> U128 {
+        let sale = self.sale.as_mut().expect("No sale is running");
+        assert!(
+            sale.rights.is_none(),
+            "Shares of rights offerings are minted, not funded"
+        );
+        assert!(
+            sender_id == sale.config.issuer,
+            "Only the issuer can fund the sale"
+        );
+        assert!(
+            env::block_timestamp() < sale.config.end.0,
+            "Sale has ended"
+        );
+        sale.allocation = U128(sale.allocation.0 + amount.0);
+        U128(0)
+    }
+
+    /// Delivers the shares payment can buy to buyer.
+    /// Returns the part of payment to be refunded.
+    pub fn internal_buy_shares(&mut self, buyer: AccountId, payment: u128, currency: SaleCurrency) -> u128 {
+        self.assert_not_paused(&PauseFeature::Transfers);
+        let mut sale = self.sale.clone().expect("No sale is running");
+        assert!(
+            sale.config.currency == currency,
+            "Sale is paid in {:?}",
+            sale.config.currency
+        );
+        let timestamp = env::block_timestamp();
+        assert!(sale.is_open(timestamp), "Sale is not open");
+        assert!(
+            self.accounts_rps.contains_key(&buyer),
+            "{} must be registered to buy shares, see storage_deposit",
+            buyer
+        );
+        self.assert_party_allowed(&buyer, TransferParty::Receiver);
+        // subscriptions of the rights phase are capped by the entitlement
+        // and do not count against the limits of the public phase
+        let rights_phase = sale.is_rights_phase(timestamp);
+        let (purchases, limit) = if rights_phase {
+            let entitlement = self.internal_rights_entitlement(&sale, &buyer);
+            (&mut self.rights_subscriptions, entitlement)
+        } else {
+            (&mut self.sale_purchases, sale.config.max_per_account.0)
+        };
+        let purchased = purchased_in(purchases, sale.id, &buyer);
+        let shares = [
+            payment / sale.config.price.0,
+            sale.available(),
+            limit.saturating_sub(purchased),
+        ]
+        .into_iter()
+        .min()
+        .unwrap();
+        assert!(shares > 0, "No shares can be bought with this payment");
+        assert!(
+            rights_phase || purchased + shares >= sale.config.min_per_account.0,
+            "Must buy at least {} shares",
+            sale.config.min_per_account.0
+        );
+        purchases.insert(&buyer, &(sale.id, U128(purchased + shares)));
+        let cost = shares * sale.config.price.0;
+        sale.sold = U128(sale.sold.0 + shares);
+        sale.proceeds = U128(sale.proceeds.0 + cost);
+        if sale.rights.is_some() {
+            self.internal_mint(&buyer, shares, Some("rights offering".to_string()));
+        } else {
+            self.internal_release_from_escrow(&buyer, shares, "primary sale");
+        }
+        SharesPurchased {
+            sale_id: sale.id,
+            account_id: &buyer,
+            amount: &U128(shares),
+            cost: &U128(cost),
+        }
+        .emit();
+        self.sale = Some(sale);
+        payment - cost
+    }
+}
+
+/// Shares bought by account_id in sale sale_id according to purchases,
+/// which only keeps the latest sale of each account
+fn purchased_in(
+    purchases: &LookupMap<AccountId, (u64, U128)>,
+    sale_id: u64,
+    account_id: &AccountId,
+) -> u128 {
+    purchases
+        .get(account_id)
+        .filter(|(id, _)| *id == sale_id)
+        .map_or(0, |(_, purchased)| purchased.0)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::tests::*;
+
+    /// Sale of up to 100 shares at price 10, between timestamps 10 and 20
+    fn sale_config(currency: SaleCurrency) -> SaleConfig {
+        SaleConfig {
+            issuer: OWNER_ACCOUNT.parse().unwrap(),
+            treasury: ADMIN_ACCOUNT.parse().unwrap(),
+            currency,
+            price: U128(10),
+            start: U64(10),
+            end: U64(20),
+            min_per_account: U128(2),
+            max_per_account: U128(50),
+            hard_cap: U128(100),
+        }
+    }
+
+    /// Creates a sale funded with allocation shares
+    fn setup_sale(contract: &mut Contract, currency: SaleCurrency, allocation: u128) {
+        let owner: AccountId = OWNER_ACCOUNT.parse().unwrap();
+        let escrow: AccountId = CONTRACT_ACCOUNT.parse().unwrap();
+        contract.sale = Some(Sale {
+            id: 0,
+            config: sale_config(currency),
+            allocation: U128(0),
+            sold: U128(0),
+            proceeds: U128(0),
+            rights: None,
+        });
+        // simulate ft_transfer_call of the allocation to the contract
+        contract.ft_functionality.internal_transfer(&owner, &escrow, allocation, None);
+        contract.internal_update_holder(&escrow);
+        contract.internal_fund_sale(owner, U128(allocation));
+    }
+
+    #[rstest]
+    /// Test create_sale method
+    /// ASSERT:
+    /// (1) Only admins can create sales
+    #[should_panic = "user.testnet does not have the admin role"]
+    #[case(USER_ACCOUNT, sale_config(SaleCurrency::Near))]
+    /// (2) Terms are validated
+    #[should_panic = "start must be before end"]
+    #[case(OWNER_ACCOUNT, SaleConfig { start: U64(30), ..sale_config(SaleCurrency::Near) })]
+    #[should_panic = "max_per_account must be positive and at least min_per_account"]
+    #[case(OWNER_ACCOUNT, SaleConfig { min_per_account: U128(51), ..sale_config(SaleCurrency::Near) })]
+    /// (3) Sale is created and logged
+    #[case(OWNER_ACCOUNT, sale_config(SaleCurrency::RewardToken))]
+    fn test_create_sale(#[case] caller: &str, #[case] config: SaleConfig) {
+        // setup
+        let context = get_context(
+            vec![],
+            1,
+            0,
+            caller.parse().unwrap(),
+            0,
+            Gas(300u64 * 10u64.pow(12)),
+        );
+        testing_env!(context);
+        let mut contract = init_contract(1);
+
+        // call tested method
+        let id = contract.create_sale(config);
+
+        // perform assertions
+        assert_eq!(id, 0);
+        assert_eq!(contract.sales_count, 1);
+        let sale = contract.view_sale().unwrap();
+        assert_eq!(sale.allocation, U128(0));
+        assert!(get_logs()[0].contains(r#""event":"sale_created""#));
+    }
+
+    #[rstest]
+    /// Test internal_buy_shares through ft_on_transfer and buy_shares
+    /// ASSERT:
+    /// (1) Currency must match the sale
+    #[should_panic = "Sale is paid in Near"]
+    #[case(SaleCurrency::Near, SaleCurrency::RewardToken, 15, 100, 100)]
+    /// (2) Sale must be open
+    #[should_panic = "Sale is not open"]
+    #[case(SaleCurrency::Near, SaleCurrency::Near, 5, 100, 100)]
+    /// (3) Per-account minimum applies
+    #[should_panic = "Must buy at least 2 shares"]
+    #[case(SaleCurrency::Near, SaleCurrency::Near, 15, 19, 100)]
+    /// (4) Shares are delivered, payments above the per-account
+    ///     maximum or the available allocation are refunded
+    #[case(SaleCurrency::Near, SaleCurrency::Near, 15, 105, 100)]
+    #[case(SaleCurrency::RewardToken, SaleCurrency::RewardToken, 15, 1000, 100)]
+    #[case(SaleCurrency::RewardToken, SaleCurrency::RewardToken, 15, 1000, 30)]
+    fn test_buy_shares(
+        #[case] sale_currency: SaleCurrency,
+        #[case] payment_currency: SaleCurrency,
+        #[case] timestamp: u64,
+        #[case] payment: u128,
+        #[case] allocation: u128,
+    ) {
+        // setup
+        let (predecessor, deposit) = match payment_currency {
+            SaleCurrency::Near => (USER_ACCOUNT, payment),
+            SaleCurrency::RewardToken => (REWARDS_TOKEN_ACCOUNT, 0),
+        };
+        let context = get_context(
+            vec![],
+            deposit,
+            10u128.pow(24),
+            predecessor.parse().unwrap(),
+            timestamp,
+            Gas(300u64 * 10u64.pow(12)),
+        );
+        testing_env!(context);
+        let mut contract = init_contract(1);
+        let user: AccountId = USER_ACCOUNT.parse().unwrap();
+        register_user(&mut contract, &user, 0, 0, 0);
+        setup_sale(&mut contract, sale_currency, allocation);
+
+        // call tested method
+        let refund = match payment_currency {
+            SaleCurrency::Near => {
+                contract.buy_shares();
+                get_created_receipts()
+                    .first()
+                    .map(|receipt| match receipt.actions[0].clone() {
+                        VmAction::Transfer { deposit } => deposit,
+                        _ => panic!(),
+                    })
+                    .unwrap_or(0)
+            }
+            SaleCurrency::RewardToken => {
+                contract
+                    .ft_on_transfer(user.clone(), U128(payment), "buy_shares".to_string())
+                    .0
+            }
+        };
+
+        // perform assertions
+        let bought = std::cmp::min(std::cmp::min(payment / 10, 50), allocation);
+        assert_eq!(refund, payment - bought * 10);
+        assert_eq!(contract.ft_balance_of(user.clone()).0, bought);
+        assert_eq!(contract.view_sale_purchase(0, user).0, bought);
+        let sale = contract.view_sale().unwrap();
+        assert_eq!(sale.sold.0, bought);
+        assert_eq!(sale.proceeds.0, bought * 10);
+        assert_eq!(contract.contract_rps_token, U128(0));
+        assert!(get_logs().last().unwrap().contains(r#""event":"shares_purchased""#));
+    }
+
+    #[rstest]
+    /// Test finalize_sale method
+    /// ASSERT:
+    /// (1) Sale must be over
+    #[should_panic = "Sale can only be finalized once ended or sold out"]
+    #[case(SaleCurrency::Near, 15, 50)]
+    /// (2) Unsold shares return to the issuer, proceeds go to the treasury
+    #[case(SaleCurrency::Near, 20, 50)]
+    #[case(SaleCurrency::RewardToken, 20, 50)]
+    /// (3) Sold out sales can be finalized before their end
+    #[case(SaleCurrency::RewardToken, 15, 20)]
+    fn test_finalize_sale(
+        #[case] currency: SaleCurrency,
+        #[case] timestamp: u64,
+        #[case] allocation: u128,
+    ) {
+        // setup
+        let context = get_context(
+            vec![],
+            0,
+            10u128.pow(24),
+            REWARDS_TOKEN_ACCOUNT.parse().unwrap(),
+            15,
+            Gas(300u64 * 10u64.pow(12)),
+        );
+        testing_env!(context.clone());
+        let mut contract = init_contract(1);
+        let user: AccountId = USER_ACCOUNT.parse().unwrap();
+        let owner: AccountId = OWNER_ACCOUNT.parse().unwrap();
+        register_user(&mut contract, &user, 0, 0, 0);
+        setup_sale(&mut contract, currency, allocation);
+        contract.internal_buy_shares(user, 200, currency);
+        testing_env!(VMContext {
+            block_timestamp: timestamp,
+            storage_usage: env::storage_usage(),
+            ..context
+        });
+
+        // call tested method
+        contract.finalize_sale();
+
+        // perform assertions
+        assert!(contract.view_sale().is_none());
+        assert_eq!(contract.ft_balance_of(owner).0, TOKEN_SUPPLY.0 - 20);
+        assert_eq!(contract.ft_balance_of(CONTRACT_ACCOUNT.parse().unwrap()).0, 0);
+        let receipts = get_created_receipts();
+        let treasury_receipt = receipts.last().unwrap();
+        match currency {
+            SaleCurrency::Near => {
+                assert_eq!(treasury_receipt.receiver_id, ADMIN_ACCOUNT.parse().unwrap());
+            }
+            SaleCurrency::RewardToken => {
+                assert_eq!(receipts[0].receiver_id, REWARDS_TOKEN_ACCOUNT.parse().unwrap());
+            }
+        }
+        assert!(get_logs().last().unwrap().contains(r#""event":"sale_finalized""#));
+    }
+
+    /// Registers user with as many shares as the owner, allows minting
+    /// and creates a rights offering at timestamp 0 whose rights phase
+    /// runs until timestamp 15
+    fn setup_rights_offering(contract: &mut Contract) {
+        let user: AccountId = USER_ACCOUNT.parse().unwrap();
+        let new_user: AccountId = NEW_USER_ACCOUNT.parse().unwrap();
+        register_user(contract, &user, TOKEN_SUPPLY.0, 0, 0);
+        register_user(contract, &new_user, 0, 0, 0);
+        contract.minting_policy = MintingPolicy::new(U128(3 * TOKEN_SUPPLY.0), vec![]);
+        contract.create_rights_offering(sale_config(SaleCurrency::RewardToken), U64(15));
+    }
+
+    #[rstest]
+    /// Test create_rights_offering method
+    /// ASSERT:
+    /// (1) Rights phase must end within the sale
+    #[should_panic = "rights_end must be between start and end"]
+    #[case(20, 3 * TOKEN_SUPPLY.0)]
+    /// (2) Offered shares must be mintable
+    #[should_panic = "Only 50 shares can be minted at start"]
+    #[case(15, 2 * TOKEN_SUPPLY.0 + 50)]
+    /// (3) Offering is created with the supply held on creation
+    #[case(15, 3 * TOKEN_SUPPLY.0)]
+    fn test_create_rights_offering(#[case] rights_end: u64, #[case] supply_cap: u128) {
+        // setup
+        let context = get_context(
+            vec![],
+            1,
+            0,
+            OWNER_ACCOUNT.parse().unwrap(),
+            0,
+            Gas(300u64 * 10u64.pow(12)),
+        );
+        testing_env!(context);
+        let mut contract = init_contract(1);
+        register_user(&mut contract, &USER_ACCOUNT.parse().unwrap(), TOKEN_SUPPLY.0, 0, 0);
+        contract.minting_policy = MintingPolicy::new(U128(supply_cap), vec![]);
+
+        // call tested method
+        contract.create_rights_offering(sale_config(SaleCurrency::Near), U64(rights_end));
+
+        // perform assertions
+        let sale = contract.view_sale().unwrap();
+        assert_eq!(sale.allocation, U128(100));
+        assert_eq!(sale.rights.unwrap().snapshot_supply, U128(2 * TOKEN_SUPPLY.0));
+        assert!(get_logs()[1].contains(r#""event":"rights_offering_created""#));
+    }
+
+    #[rstest]
+    /// Test purchases of a rights offering
+    /// ASSERT:
+    /// (1) Accounts without shares on creation have no rights
+    #[should_panic = "No shares can be bought with this payment"]
+    #[case(NEW_USER_ACCOUNT)]
+    /// (2) Holders on creation subscribe up to their entitlement even
+    ///     after transferring shares, the remainder then opens to the
+    ///     public and new shares are minted on purchase
+    #[case(USER_ACCOUNT)]
+    fn test_rights_offering(#[case] subscriber: &str) {
+        // setup
+        let context = get_context(
+            vec![],
+            1,
+            0,
+            OWNER_ACCOUNT.parse().unwrap(),
+            0,
+            Gas(300u64 * 10u64.pow(12)),
+        );
+        testing_env!(context.clone());
+        let mut contract = init_contract(1);
+        setup_rights_offering(&mut contract);
+        let user: AccountId = USER_ACCOUNT.parse().unwrap();
+        let new_user: AccountId = NEW_USER_ACCOUNT.parse().unwrap();
+        testing_env!(VMContext {
+            predecessor_account_id: user.clone(),
+            block_timestamp: 5,
+            storage_usage: env::storage_usage(),
+            ..context.clone()
+        });
+        contract.ft_transfer(new_user.clone(), U128(TOKEN_SUPPLY.0 / 2), None);
+
+        // call tested method
+        testing_env!(VMContext {
+            predecessor_account_id: REWARDS_TOKEN_ACCOUNT.parse().unwrap(),
+            block_timestamp: 12,
+            storage_usage: env::storage_usage(),
+            ..context.clone()
+        });
+        assert_eq!(contract.view_rights_entitlement(user.clone()), U128(50));
+        assert_eq!(contract.view_rights_entitlement(new_user.clone()), U128(0));
+        let subscription_refund =
+            contract.internal_buy_shares(subscriber.parse().unwrap(), 1000, SaleCurrency::RewardToken);
+        testing_env!(VMContext {
+            predecessor_account_id: REWARDS_TOKEN_ACCOUNT.parse().unwrap(),
+            block_timestamp: 16,
+            storage_usage: env::storage_usage(),
+            ..context.clone()
+        });
+        let public_refund =
+            contract.internal_buy_shares(new_user.clone(), 1000, SaleCurrency::RewardToken);
+        contract.finalize_sale();
+
+        // perform assertions
+        assert_eq!(subscription_refund, 500);
+        assert_eq!(public_refund, 500);
+        assert_eq!(contract.ft_balance_of(user).0, TOKEN_SUPPLY.0 / 2 + 50);
+        assert_eq!(contract.ft_balance_of(new_user).0, TOKEN_SUPPLY.0 / 2 + 50);
+        assert_eq!(contract.ft_total_supply().0, 2 * TOKEN_SUPPLY.0 + 100);
+        assert_eq!(contract.minting_policy.minted, U128(100));
+        assert!(contract.view_sale().is_none());
+    }
+
+    #[rstest]
+    /// Test entitlement of rights offerings
+    /// ASSERT:
+    /// (1) Entitlements are pro rata to the snapshot balance, rounded down
+    #[case(1, 3, 100, 33)]
+    /// (2) Amounts with 24 decimals do not overflow
+    #[case(10u128.pow(32), 10u128.pow(33), 10u128.pow(32), 10u128.pow(31))]
+    #[case(10u128.pow(33), 10u128.pow(33), u128::MAX, u128::MAX)]
+    fn test_entitlement(
+        #[case] snapshot_balance: u128,
+        #[case] snapshot_supply: u128,
+        #[case] hard_cap: u128,
+        #[case] entitlement: u128,
+    ) {
+        // setup
+        let sale = Sale {
+            id: 0,
+            config: SaleConfig {
+                hard_cap: U128(hard_cap),
+                ..sale_config(SaleCurrency::RewardToken)
+            },
+            allocation: U128(hard_cap),
+            sold: U128(0),
+            proceeds: U128(0),
+            rights: Some(RightsPhase {
+                end: U64(15),
+                snapshot_supply: U128(snapshot_supply),
+            }),
+        };
+
+        // call tested method
+        let result = sale.entitlement(snapshot_balance);
+
+        // perform assertions
+        assert_eq!(result, entitlement);
+    }
+}
@@ -0,0 +1,453 @@
+//! Split actions module
+//!
+//! Allows the owner to split shares, multiplying every balance by
+//! numerator / denominator, e.g. 2/1 doubles every balance and
+//! 1/10 is a reverse split merging every 10 shares into one.
+//!
+//! Rewards of every holder are settled before balances change, so
+//! no accrued reward depends on the old share unit and contract_rps
+//! keeps its value: rewards distributed afterwards are shared among
+//! the new units. Rescaling contract_rps and every account_rps
+//! instead could not be done exactly in integer arithmetic.
+//!
+//! Fractions of a share that cannot be represented after the split
+//! are cashed out at cash_out_price per new share, paid from the
+//! reserve into each holder's claimable rewards.
+//!
+//! Holders are rescaled in batches: split rescales the first batch
+//! and anyone can rescale the following ones through process_split.
+//! Transfers and deposits are halted until every holder is rescaled,
+//! while balances are in mixed share units. Amounts of the minting
+//! policy and the buyout reserve price are rescaled as soon as the
+//! split starts.
+//!
+//! Sales, auctions and buybacks are priced in the old share unit, so
+//! no split can happen while one is open or shares are still escrowed.
+
+use crate::balances::BalanceChange;
+use crate::events::{SharesSplit, SplitStarted};
+use crate::*;
+
+/// Holders rescaled by each call by default. Each holder costs about
+/// 1.5 TGas of host calls, see test_process_split.
+pub const SPLIT_BATCH_SIZE: u64 = 50;
+
+#[near_bindgen]
+impl Contract {
+    /// Starts rescaling every balance and the supply by numerator /
+    /// denominator and rescales the first SPLIT_BATCH_SIZE holders.
+    /// cash_out_price, in reward tokens per share after the split, is
+    /// required unless the ratio reduces to a whole number.
+    /// Every holder must be indexed, see index_holders.
+    #[payable]
+    pub fn split(&mut self, numerator: u32, denominator: u32, cash_out_price: Option<U128>) {
+        assert_one_yocto();
+        self.assert_role(&Role::Owner);
+        self.assert_not_paused(&PauseFeature::Transfers);
+        self.assert_no_buyout();
+        assert!(self.sale.is_none(), "Cannot split while a sale is running");
+        assert!(
+            self.auction.as_ref().is_none_or(Auction::is_settled),
+            "Cannot split while an auction is running"
+        );
+        assert!(self.buyback.is_none(), "Cannot split while a buyback is open");
+        // unclaimed auction shares stay escrowed after settlement
+        assert!(
+            self.ft_functionality
+                .accounts
+                .get(&env::current_account_id())
+                .unwrap_or(0)
+                == 0,
+            "Cannot split while shares are escrowed"
+        );
+        self.assert_holders_indexed();
+        assert!(
+            numerator > 0 && denominator > 0 && numerator != denominator,
+            "Split ratio must be positive and different from 1"
+        );
+        let divisor = gcd(numerator, denominator);
+        let (numerator, denominator) = (numerator / divisor, denominator / divisor);
+        assert!(
+            denominator == 1 || cash_out_price.is_some(),
+            "cash_out_price is required unless the ratio reduces to a whole number"
+        );
+
+        self.splits_count += 1;
+        let mut split = Split {
+            id: self.splits_count,
+            numerator,
+            denominator,
+            cash_out_price,
+            cash_out_budget: U128(0),
+            cash_out: U128(0),
+            remaining: self.holders.len(),
+        };
+        let budget = split
+            .max_cash_out()
+            .checked_mul(split.remaining as u128)
+            .expect("Cash out overflow");
+        assert!(
+            budget <= self.reserve_token.0,
+            "Reserve cannot cover cash outs of up to {} reward tokens",
+            budget
+        );
+        self.reserve_token = U128(self.reserve_token.0 - budget);
+        split.cash_out_budget = U128(budget);
+        self.minting_policy.rescale(numerator as u128, denominator as u128);
+        if let Some(policy) = self.buyout_policy.as_mut() {
+            policy.rescale(numerator as u128, denominator as u128);
+        }
+        SplitStarted {
+            split_id: split.id,
+            numerator,
+            denominator,
+            holders: split.remaining,
+        }
+        .emit();
+        self.split = Some(split);
+        self.internal_process_split(SPLIT_BATCH_SIZE);
+    }
+
+    /// Rescales up to limit holders, SPLIT_BATCH_SIZE by default, of the
+    /// split in progress and completes it once every holder is
+    /// rescaled. Callable by anyone. Returns holders left to rescale.
+    pub fn process_split(&mut self, limit: Option<u64>) -> u64 {
+        self.internal_process_split(limit.unwrap_or(SPLIT_BATCH_SIZE))
+    }
+
+    pub fn view_split(&self) -> Option<Split> {
+        self.split.clone()
+    }
+}
+
+impl Contract {
+    /// Panics while a split is in progress. Must be called by every
+    /// offering of shares priced in a share unit.
+    pub fn assert_no_split(&self) {
+        assert!(self.split.is_none(), "Not allowed while a split is in progress");
+    }
+
+    fn internal_process_split(&mut self, limit: u64) -> u64 {
+        let mut split = self.split.clone().expect("No split is in progress");
+        assert!(limit > 0, "limit must be greater than 0");
+        let start = split.remaining.saturating_sub(limit);
+        let batch: Vec<AccountId> = (start..split.remaining)
+            .map(|index| self.holders.as_vector().get(index).unwrap())
+            .collect();
+
+        let mut adjustments = Vec::new();
+        for holder in batch.iter() {
+            self.update_user_rps(holder);
+            let balance = self.ft_functionality.accounts.get(holder).unwrap_or(0);
+            let (new_balance, cash_out) = split.rescale(balance);
+            if cash_out > 0 {
+                let mut user_rps = self.accounts_rps.get(holder).unwrap();
+                user_rps.rewards_received_token = U128(user_rps.rewards_received_token.0 + cash_out);
+                user_rps.rewards_balance_token = U128(user_rps.rewards_balance_token.0 + cash_out);
+                self.accounts_rps.insert(holder, &user_rps);
+                split.cash_out = U128(split.cash_out.0 + cash_out);
+            }
+            if new_balance != balance {
+                adjustments.push((holder, balance, new_balance));
+            }
+        }
+        // holders removed by the batch are swapped with holders of
+        // higher indexes, which are all rescaled already
+        self.internal_change_balances(
+            &adjustments
+                .iter()
+                .map(|(holder, balance, new_balance)| {
+                    if new_balance > balance {
+                        BalanceChange::mint(holder, new_balance - balance, Some("split"))
+                    } else {
+                        BalanceChange::burn(holder, balance - new_balance, Some("split"))
+                    }
+                })
+                .collect::<Vec<_>>(),
+        );
+        split.remaining = start;
+        if split.remaining > 0 {
+            self.split = Some(split);
+            return start;
+        }
+
+        self.split = None;
+        self.reserve_token =
+            U128(self.reserve_token.0 + split.cash_out_budget.0 - split.cash_out.0);
+        SharesSplit {
+            split_id: split.id,
+            numerator: split.numerator,
+            denominator: split.denominator,
+            total_supply: &U128(self.ft_functionality.total_supply),
+            cash_out: &split.cash_out,
+        }
+        .emit();
+        0
+    }
+}
+
+fn gcd(a: u32, b: u32) -> u32 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::tests::*;
+    use crate::auction::AuctionConfig;
+    use crate::sale::{SaleConfig, SaleCurrency};
+    use near_sdk::json_types::U64;
+
+    #[rstest]
+    /// Test split method
+    /// ASSERT:
+    /// (1) Only the owner can split
+    #[should_panic = "admin.testnet does not have the owner role"]
+    #[case(ADMIN_ACCOUNT, 2, 1, None, 0)]
+    /// (2) Ratio must change balances
+    #[should_panic = "Split ratio must be positive and different from 1"]
+    #[case(OWNER_ACCOUNT, 3, 3, None, 0)]
+    /// (3) Ratios leaving fractions require a cash out price
+    #[should_panic = "cash_out_price is required unless the ratio reduces to a whole number"]
+    #[case(OWNER_ACCOUNT, 1, 3, None, 0)]
+    /// (4) Cash outs of every holder must be covered by the reserve
+    #[should_panic = "Reserve cannot cover cash outs of up to 18 reward tokens"]
+    #[case(OWNER_ACCOUNT, 1, 3, Some(U128(9)), 17)]
+    /// (5) Balances and supply are rescaled, accrued rewards are kept,
+    ///     fractions are cashed out and later rewards use new units
+    #[case(OWNER_ACCOUNT, 2, 1, None, 0)]
+    #[case(OWNER_ACCOUNT, 4, 2, None, 0)]
+    #[case(OWNER_ACCOUNT, 1, 3, Some(U128(9)), 18)]
+    fn test_split(
+        #[case] caller: &str,
+        #[case] numerator: u32,
+        #[case] denominator: u32,
+        #[case] cash_out_price: Option<U128>,
+        #[case] reserve: u128,
+    ) {
+        // setup
+        let context = get_context(
+            vec![],
+            1,
+            0,
+            caller.parse().unwrap(),
+            0,
+            Gas(300u64 * 10u64.pow(12)),
+        );
+        testing_env!(context);
+        let mut contract = init_contract(1);
+        crate::tests::grant_role(&mut contract, ADMIN_ACCOUNT, Role::Admin);
+        let owner: AccountId = OWNER_ACCOUNT.parse().unwrap();
+        let user: AccountId = USER_ACCOUNT.parse().unwrap();
+        let new_user: AccountId = NEW_USER_ACCOUNT.parse().unwrap();
+        // balances: owner 100000, user 100, new_user 2
+        register_user(&mut contract, &user, 100, 0, 0);
+        register_user(&mut contract, &new_user, 2, 0, 0);
+        contract.reserve_token = U128(reserve);
+        let total_supply = contract.ft_total_supply().0;
+        contract.distribute_rewards_token(total_supply);
+
+        // call tested method
+        contract.split(numerator, denominator, cash_out_price);
+
+        // perform assertions
+        let n = numerator as u128;
+        let d = denominator as u128;
+        assert_eq!(contract.ft_balance_of(owner.clone()).0, TOKEN_SUPPLY.0 * n / d);
+        assert_eq!(contract.ft_balance_of(user.clone()).0, 100 * n / d);
+        assert_eq!(contract.ft_balance_of(new_user.clone()).0, 2 * n / d);
+        let new_supply = (TOKEN_SUPPLY.0 * n / d) + (100 * n / d) + (2 * n / d);
+        assert_eq!(contract.ft_total_supply().0, new_supply);
+        assert_eq!(contract.view_minting_policy().supply_cap.0, TOKEN_SUPPLY.0 * n / d);
+        // unused cash out budget returns to the reserve
+        assert_eq!(contract.view_reserve().0, if d == 3 { 6 } else { 0 });
+        assert!(contract.view_split().is_none());
+
+        // fraction cash outs at price 9: 1/3 of a share for owner and user,
+        // 2/3 of a share for new_user
+        let rewards = |contract: &Contract, account: AccountId| {
+            contract.view_claimable_rewards(account)[REWARDS_TOKEN_ACCOUNT].0
+        };
+        let (owner_cash_out, user_cash_out, new_user_cash_out) =
+            if d == 3 { (3, 3, 6) } else { (0, 0, 0) };
+        assert_eq!(rewards(&contract, owner.clone()), TOKEN_SUPPLY.0 + owner_cash_out);
+        assert_eq!(rewards(&contract, user.clone()), 100 + user_cash_out);
+        assert_eq!(rewards(&contract, new_user.clone()), 2 + new_user_cash_out);
+
+        contract.distribute_rewards_token(new_supply);
+        assert_eq!(rewards(&contract, user.clone()), 100 + user_cash_out + 100 * n / d);
+        assert_eq!(contract.view_holders(None, None).len(), if d == 3 { 2 } else { 3 });
+
+        let logs = get_logs();
+        assert!(logs[0].contains(r#""event":"split_started""#));
+        assert!(logs.last().unwrap().contains(r#""event":"shares_split""#));
+        if d == 3 {
+            assert!(logs[1].contains(r#""event":"ft_burn""#));
+            assert!(logs[2].contains(r#""event":"nft_burn""#));
+        } else {
+            assert!(logs[0].contains(r#""numerator":2,"denominator":1"#));
+            assert!(logs[1].contains(r#""event":"ft_mint""#));
+        }
+    }
+
+    /// Open offerings of shares priced in the current share unit
+    enum OpenOffering {
+        Sale,
+        Auction,
+        Buyback,
+        EscrowedShares,
+        UnindexedHolders,
+    }
+
+    #[rstest]
+    /// Test split method while shares are being offered
+    /// ASSERT:
+    /// (1) Splits are refused while a sale, an auction or a buyback is
+    ///     open, shares are escrowed or holders are not indexed
+    #[should_panic = "Cannot split while a sale is running"]
+    #[case(OpenOffering::Sale)]
+    #[should_panic = "Cannot split while an auction is running"]
+    #[case(OpenOffering::Auction)]
+    #[should_panic = "Cannot split while a buyback is open"]
+    #[case(OpenOffering::Buyback)]
+    #[should_panic = "Cannot split while shares are escrowed"]
+    #[case(OpenOffering::EscrowedShares)]
+    #[should_panic = "Every holder must be indexed first, see index_holders"]
+    #[case(OpenOffering::UnindexedHolders)]
+    fn test_split_open_offering(#[case] offering: OpenOffering) {
+        // setup
+        let context = get_context(
+            vec![],
+            1,
+            0,
+            OWNER_ACCOUNT.parse().unwrap(),
+            0,
+            Gas(300u64 * 10u64.pow(12)),
+        );
+        testing_env!(context);
+        let mut contract = init_contract(1);
+        let owner: AccountId = OWNER_ACCOUNT.parse().unwrap();
+        match offering {
+            OpenOffering::Sale => {
+                contract.create_sale(SaleConfig {
+                    issuer: owner.clone(),
+                    treasury: owner,
+                    currency: SaleCurrency::Near,
+                    price: U128(10),
+                    start: U64(10),
+                    end: U64(20),
+                    min_per_account: U128(2),
+                    max_per_account: U128(50),
+                    hard_cap: U128(100),
+                });
+            }
+            OpenOffering::Auction => {
+                contract.create_auction(AuctionConfig {
+                    issuer: owner.clone(),
+                    treasury: owner,
+                    currency: SaleCurrency::Near,
+                    supply: U128(100),
+                    start_price: U128(20),
+                    floor_price: U128(10),
+                    start: U64(100),
+                    end: U64(200),
+                });
+            }
+            OpenOffering::Buyback => {
+                contract.reserve_token = U128(100);
+                contract.open_buyback(U128(10), U128(100));
+            }
+            OpenOffering::EscrowedShares => {
+                contract.internal_register_escrow();
+                contract.internal_transfer_shares(
+                    &owner,
+                    &CONTRACT_ACCOUNT.parse().unwrap(),
+                    10,
+                    None,
+                );
+            }
+            OpenOffering::UnindexedHolders => {
+                contract.unindexed_supply = U128(10);
+            }
+        }
+
+        // call tested method
+        contract.split(2, 1, None);
+    }
+
+    #[rstest]
+    /// Test process_split method
+    /// ASSERT:
+    /// (1) split rescales a first batch of holders within the gas of a call
+    /// (2) Transfers are halted until every holder is rescaled
+    #[should_panic = "Contract transfers are paused until the split completes"]
+    #[case(true)]
+    /// (3) Remaining holders are rescaled in batches by anyone, the split
+    ///     completes with the last batch
+    #[case(false)]
+    fn test_process_split(#[case] transfer_during_split: bool) {
+        // setup
+        let context = get_context(
+            vec![],
+            1,
+            0,
+            OWNER_ACCOUNT.parse().unwrap(),
+            0,
+            Gas(300u64 * 10u64.pow(12)),
+        );
+        testing_env!(context.clone());
+        let mut contract = init_contract(1);
+        let owner: AccountId = OWNER_ACCOUNT.parse().unwrap();
+        // the owner is a holder too
+        let holders: Vec<AccountId> = (0..2 * SPLIT_BATCH_SIZE)
+            .map(|index| format!("holder{}.testnet", index).parse().unwrap())
+            .collect();
+        for holder in holders.iter() {
+            register_user(&mut contract, holder, 3, 0, 0);
+        }
+        contract.reserve_token = U128(2 * SPLIT_BATCH_SIZE as u128 + 1);
+        let initial_gas = env::used_gas().0;
+
+        contract.split(1, 2, Some(U128(2)));
+
+        // host calls only, leaves as much again for wasm execution
+        assert!(env::used_gas().0 - initial_gas < 100u64 * 10u64.pow(12));
+        assert_eq!(contract.view_split().unwrap().remaining, SPLIT_BATCH_SIZE + 1);
+        assert!(contract.split.is_some());
+        if transfer_during_split {
+            contract.ft_transfer(holders[0].clone(), U128(1), None);
+        }
+
+        // call tested method
+        testing_env!(VMContext {
+            predecessor_account_id: USER_ACCOUNT.parse().unwrap(),
+            attached_deposit: 0,
+            storage_usage: env::storage_usage(),
+            ..context
+        });
+        assert_eq!(contract.process_split(Some(SPLIT_BATCH_SIZE)), 1);
+        assert_eq!(contract.process_split(None), 0);
+
+        // perform assertions
+        assert!(contract.view_split().is_none());
+        assert_eq!(contract.ft_balance_of(owner).0, TOKEN_SUPPLY.0 / 2);
+        for holder in holders {
+            assert_eq!(contract.ft_balance_of(holder.clone()).0, 1);
+            assert_eq!(
+                contract.view_claimable_rewards(holder)[REWARDS_TOKEN_ACCOUNT].0,
+                1
+            );
+        }
+        assert_eq!(
+            contract.ft_total_supply().0,
+            TOKEN_SUPPLY.0 / 2 + 2 * SPLIT_BATCH_SIZE as u128
+        );
+        assert_eq!(contract.view_reserve().0, 1);
+        assert!(get_logs().last().unwrap().contains(r#""event":"shares_split""#));
+    }
+}
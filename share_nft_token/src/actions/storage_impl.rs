@@ -24,9 +24,7 @@ impl StorageManagement for Contract {
     ) -> StorageBalance {
         log!(env::storage_usage().to_string());
         let amount = env::attached_deposit();
-        let account_id = account_id
-            .map(|a| a.into())
-            .unwrap_or_else(|| env::predecessor_account_id());
+        let account_id = account_id.unwrap_or_else(env::predecessor_account_id);
         let min_balance = self.storage_balance_bounds().min.0;
         let already_registered = self.accounts_rps.contains_key(&account_id);
         if amount < min_balance {
@@ -42,8 +40,7 @@ impl StorageManagement for Contract {
                 Promise::new(env::predecessor_account_id()).transfer(amount);
             }
         } else {
-            self.ft_functionality.internal_register_account(&account_id);
-            self.update_user_rps(&account_id);
+            self.internal_register_account(&account_id, min_balance);
             let refund = amount - min_balance;
             if refund > 0 {
                 Promise::new(env::predecessor_account_id()).transfer(refund);
@@ -78,14 +75,10 @@ impl StorageManagement for Contract {
         let force = force.unwrap_or(false);
         assert!(!force, "force option not available");
 
-        let rewards = self.accounts_rps.get(&account_id);
-
-        match rewards {
+        let rewards = match self.accounts_rps.get(&account_id) {
+            Some(rewards) => rewards,
             None => return false,
-            _ => (),
-        }
-
-        let rewards = rewards.unwrap();
+        };
 
         assert_eq!(
             rewards.rewards_balance_token,
@@ -99,9 +92,19 @@ impl StorageManagement for Contract {
             "Account still has rewards tokens"
         );
 
+        let deposit = self.internal_storage_deposit(&account_id);
         self.ft_functionality.accounts.remove(&account_id);
         self.accounts_rps.remove(&account_id);
-        Promise::new(account_id.clone()).transfer(self.storage_balance_bounds().min.0 + 1);
+        self.position_approvals.remove(&account_id);
+        self.sale_purchases.remove(&account_id);
+        self.rights_snapshots.remove(&account_id);
+        self.rights_subscriptions.remove(&account_id);
+        self.buyout_snapshots.remove(&account_id);
+        self.buyout_votes.remove(&account_id);
+        self.share_allowances.remove(&account_id);
+        self.account_attributes.remove(&account_id);
+        self.storage_deposits.remove(&account_id);
+        Promise::new(account_id.clone()).transfer(deposit + 1);
         true
     }
 
@@ -117,7 +120,7 @@ impl StorageManagement for Contract {
     fn storage_balance_of(&self, account_id: AccountId) -> Option<StorageBalance> {
         if self.accounts_rps.contains_key(&account_id) {
             Some(StorageBalance {
-                total: self.storage_balance_bounds().min,
+                total: U128(self.internal_storage_deposit(&account_id)),
                 available: 0.into(),
             })
         } else {
@@ -125,3 +128,166 @@ impl StorageManagement for Contract {
         }
     }
 }
+
+impl Contract {
+    /// Storage deposit paid on registration of account_id, legacy
+    /// registrations without a record paid for
+    /// LEGACY_STORAGE_BYTES_PER_USER bytes
+    pub fn internal_storage_deposit(&self, account_id: &AccountId) -> Balance {
+        self.storage_deposits.get(account_id).map_or_else(
+            || Balance::from(LEGACY_STORAGE_BYTES_PER_USER) * env::storage_byte_cost(),
+            |deposit| deposit.0,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::approvals::PositionApprovals;
+    use crate::compliance::AccountAttributes;
+    use crate::tests::*;
+    use crate::tiers::{HolderTier, MAX_TIER_NAME_LEN};
+
+    #[rstest]
+    /// Test STORAGE_BYTES_PER_USER
+    /// ASSERT:
+    /// (1) Registration covers every entry stored for a holder with
+    ///     the longest account id, and storage_unregister removes them
+    ///     along with the entries paid for by other methods
+    fn test_storage_bytes_per_user() {
+        // setup
+        let context = get_context(
+            vec![],
+            1,
+            10u128.pow(24),
+            OWNER_ACCOUNT.parse().unwrap(),
+            0,
+            Gas(300u64 * 10u64.pow(12)),
+        );
+        testing_env!(context.clone());
+        let mut contract = init_contract(1);
+        let tier_name = "t".repeat(MAX_TIER_NAME_LEN);
+        contract.tiers.set(&vec![HolderTier {
+            name: tier_name,
+            min_bps: 1,
+            title: "Holder".to_string(),
+            media: "ar://holder".to_string(),
+            media_hash: None,
+            reference: None,
+            reference_hash: None,
+        }]);
+        let account_id: AccountId = "a".repeat(64).parse().unwrap();
+        let initial_storage_usage = env::storage_usage();
+
+        // call tested method
+        contract.internal_register_account(&account_id, u128::MAX);
+        contract.internal_deposit_shares(&account_id, TOKEN_SUPPLY.0, None);
+        contract.sale_purchases.insert(&account_id, &(u64::MAX, U128(u128::MAX)));
+        contract.rights_snapshots.insert(&account_id, &(u64::MAX, U128(u128::MAX)));
+        contract.rights_subscriptions.insert(&account_id, &(u64::MAX, U128(u128::MAX)));
+        contract.buyout_snapshots.insert(&account_id, &(u64::MAX, U128(u128::MAX)));
+        contract.buyout_votes.insert(&account_id, &(u64::MAX, true));
+        // approvals are charged to the holder, their counter is kept
+        let approvals = PositionApprovals {
+            next_approval_id: u64::MAX,
+            ..Default::default()
+        };
+        contract.position_approvals.insert(&account_id, &approvals);
+
+        // perform assertions
+        let storage_used = env::storage_usage() - initial_storage_usage;
+        assert!(storage_used <= STORAGE_BYTES_PER_USER);
+
+        contract.internal_set_allowance(&account_id, &OWNER_ACCOUNT.parse().unwrap(), 1);
+        contract
+            .account_attributes
+            .insert(&account_id, &AccountAttributes::default());
+        contract.storage_deposits.insert(&account_id, &U128(0));
+        contract.internal_burn_shares(&account_id, TOKEN_SUPPLY.0, None);
+        testing_env!(VMContext {
+            predecessor_account_id: account_id,
+            storage_usage: env::storage_usage(),
+            ..context
+        });
+        contract.storage_unregister(None);
+        assert_eq!(env::storage_usage(), initial_storage_usage);
+    }
+
+    #[derive(Clone, Copy)]
+    enum Registration {
+        Legacy,
+        Allocation,
+        StorageDeposit,
+    }
+
+    #[rstest]
+    /// Test storage_unregister
+    /// ASSERT:
+    /// (1) Accounts registered before v1.1.0 are refunded the deposit
+    ///     of v1.0.0
+    #[case(Registration::Legacy)]
+    /// (2) Accounts registered by the contract are refunded nothing
+    #[case(Registration::Allocation)]
+    /// (3) Accounts are refunded the deposit they paid
+    #[case(Registration::StorageDeposit)]
+    fn test_storage_unregister(#[case] registration: Registration) {
+        // setup
+        let min_balance = Balance::from(STORAGE_BYTES_PER_USER) * env::storage_byte_cost();
+        let context = get_context(
+            vec![],
+            min_balance,
+            10u128.pow(24),
+            NEW_USER_ACCOUNT.parse().unwrap(),
+            0,
+            Gas(300u64 * 10u64.pow(12)),
+        );
+        testing_env!(context.clone());
+        let mut contract = init_contract(1);
+        let account_id: AccountId = NEW_USER_ACCOUNT.parse().unwrap();
+        let deposit = match registration {
+            Registration::Legacy => {
+                register_user(&mut contract, &account_id, 0, 0, 0);
+                Balance::from(LEGACY_STORAGE_BYTES_PER_USER) * env::storage_byte_cost()
+            }
+            Registration::Allocation => {
+                contract.internal_register_account(&account_id, 0);
+                0
+            }
+            Registration::StorageDeposit => {
+                contract.storage_deposit(None, None);
+                min_balance
+            }
+        };
+        assert_eq!(
+            contract.storage_balance_of(account_id.clone()).unwrap().total,
+            U128(deposit)
+        );
+        contract
+            .account_attributes
+            .insert(&account_id, &AccountAttributes::default());
+        contract.internal_set_allowance(&account_id, &USER_ACCOUNT.parse().unwrap(), 1);
+
+        // call tested method
+        testing_env!(VMContext {
+            attached_deposit: 1,
+            storage_usage: env::storage_usage(),
+            ..context
+        });
+        assert!(contract.storage_unregister(None));
+
+        // perform assertions
+        let receipts = get_created_receipts();
+        assert_eq!(receipts.len(), 1);
+        assert_eq!(receipts[0].receiver_id, account_id);
+        match receipts[0].actions[0].clone() {
+            VmAction::Transfer { deposit: refund } => assert_eq!(refund, deposit + 1),
+            _ => panic!(),
+        }
+        assert!(contract.storage_balance_of(account_id.clone()).is_none());
+        assert!(contract.account_attributes.get(&account_id).is_none());
+        assert!(contract.share_allowances.get(&account_id).is_none());
+        assert!(contract.storage_deposits.get(&account_id).is_none());
+    }
+}
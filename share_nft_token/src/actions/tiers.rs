@@ -0,0 +1,205 @@
+//! Tiers actions module
+//!
+//! Admins can rank holders in tiers by the part of the supply they own,
+//! e.g. Patron from 1% and Collector from 0.1%. The NFT of each holder
+//! displays the title, media and reference of its tier, and
+//! view_tier allows other applications to gate perks by tier.
+//!
+//! The tier of each holder is recorded whenever its balance changes,
+//! emitting an event when it moves across a threshold. Since tiers are
+//! relative to the supply, tiers of holders whose balance did not
+//! change are only recorded on their next balance change, view_tier
+//! always returns the current one.
+
+use crate::events::{HolderTierChanged, TiersUpdated};
+use crate::tiers::{assert_valid_tiers, HolderTier};
+use crate::*;
+
+#[near_bindgen]
+impl Contract {
+    /// Replaces the tiers, sorted by decreasing threshold. Caller pays
+    /// for their storage, the excess is refunded.
+    #[payable]
+    pub fn set_tiers(&mut self, tiers: Vec<HolderTier>) {
+        assert!(
+            env::attached_deposit() >= 1,
+            "Requires attached deposit of at least 1 yoctoNEAR"
+        );
+        self.assert_role(&Role::Admin);
+        assert_valid_tiers(&tiers);
+        let initial_storage_usage = env::storage_usage();
+        let updated_by = env::predecessor_account_id();
+        TiersUpdated {
+            updated_by: &updated_by,
+            tiers: &tiers,
+        }
+        .emit();
+        self.tiers.set(&tiers);
+        self.charge_storage_deposit(initial_storage_usage, &updated_by);
+    }
+
+    pub fn view_tiers(&self) -> Vec<HolderTier> {
+        self.tiers.get().unwrap_or_default()
+    }
+
+    /// Current tier of account_id, if it reaches any
+    pub fn view_tier(&self, account_id: AccountId) -> Option<HolderTier> {
+        self.internal_tier(&account_id)
+    }
+}
+
+impl Contract {
+    /// Highest tier reached by the balance of account_id
+    pub fn internal_tier(&self, account_id: &AccountId) -> Option<HolderTier> {
+        let balance = self.ft_functionality.accounts.get(account_id).unwrap_or(0);
+        let total_supply = self.ft_functionality.total_supply;
+        if balance == 0 || account_id == &env::current_account_id() {
+            return None;
+        }
+        self.tiers
+            .get()?
+            .into_iter()
+            .find(|tier| tier.is_reached(balance, total_supply))
+    }
+
+    /// Records the current tier of account_id, emitting an event
+    /// if it changed
+    pub fn internal_update_tier(&mut self, account_id: &AccountId) {
+        let tier = self.internal_tier(account_id).map(|tier| tier.name);
+        let previous_tier = self.holder_tiers.get(account_id);
+        if tier == previous_tier {
+            return;
+        }
+        HolderTierChanged {
+            account_id,
+            previous_tier: previous_tier.as_deref(),
+            tier: tier.as_deref(),
+        }
+        .emit();
+        match &tier {
+            Some(tier) => self.holder_tiers.insert(account_id, tier),
+            None => self.holder_tiers.remove(account_id),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::tests::*;
+
+    fn tier(name: &str, min_bps: u16) -> HolderTier {
+        HolderTier {
+            name: name.to_string(),
+            min_bps,
+            title: format!("{} of the artwork", name),
+            media: format!("ar://{}", name),
+            media_hash: None,
+            reference: None,
+            reference_hash: None,
+        }
+    }
+
+    #[rstest]
+    /// Test set_tiers method
+    /// ASSERT:
+    /// (1) Only admins can set tiers
+    #[should_panic = "user.testnet does not have the admin role"]
+    #[case(USER_ACCOUNT, vec![tier("Patron", 100)])]
+    /// (2) Thresholds must be strictly decreasing
+    #[should_panic = "Tiers must be sorted by strictly decreasing min_bps"]
+    #[case(OWNER_ACCOUNT, vec![tier("Collector", 10), tier("Patron", 100)])]
+    /// (3) Names must be unique
+    #[should_panic = "Tier Patron is configured twice"]
+    #[case(OWNER_ACCOUNT, vec![tier("Patron", 100), tier("Patron", 10)])]
+    /// (4) Tiers are stored and current tiers are computed from them
+    #[case(OWNER_ACCOUNT, vec![tier("Patron", 100), tier("Collector", 10)])]
+    fn test_set_tiers(#[case] caller: &str, #[case] tiers: Vec<HolderTier>) {
+        // setup
+        let context = get_context(
+            vec![],
+            10u128.pow(24),
+            10u128.pow(24),
+            caller.parse().unwrap(),
+            0,
+            Gas(300u64 * 10u64.pow(12)),
+        );
+        testing_env!(context);
+        let mut contract = init_contract(1);
+        register_user(&mut contract, &USER_ACCOUNT.parse().unwrap(), 10, 0, 0);
+
+        // call tested method
+        contract.set_tiers(tiers);
+
+        // perform assertions
+        assert_eq!(contract.view_tiers().len(), 2);
+        assert_eq!(
+            contract.view_tier(OWNER_ACCOUNT.parse().unwrap()).unwrap().name,
+            "Patron"
+        );
+        assert!(contract.view_tier(USER_ACCOUNT.parse().unwrap()).is_none());
+        assert!(get_logs()[0].contains(r#""event":"tiers_updated""#));
+    }
+
+    #[rstest]
+    /// Test tier changes on transfers
+    /// ASSERT:
+    /// (1) Holders crossing a threshold emit a tier change event
+    ///     and their NFT displays the metadata of the tier
+    #[case(1_000, Some("Collector"))]
+    #[case(2_000, Some("Patron"))]
+    /// (2) Holders below every threshold have no tier
+    #[case(5, None)]
+    fn test_tier_changes(#[case] amount: u128, #[case] expected_tier: Option<&str>) {
+        // setup
+        let context = get_context(
+            vec![],
+            1,
+            0,
+            OWNER_ACCOUNT.parse().unwrap(),
+            0,
+            Gas(300u64 * 10u64.pow(12)),
+        );
+        testing_env!(context);
+        let mut contract = init_contract(1);
+        let user: AccountId = USER_ACCOUNT.parse().unwrap();
+        register_user(&mut contract, &user, 10, 0, 0);
+        contract
+            .tiers
+            .set(&vec![tier("Patron", 200), tier("Collector", 10)]);
+
+        // call tested method
+        contract.ft_transfer(user.clone(), U128(amount), None);
+
+        // perform assertions
+        assert_eq!(
+            contract.view_tier(user.clone()).map(|tier| tier.name),
+            expected_tier.map(str::to_string)
+        );
+        assert_eq!(contract.holder_tiers.get(&user).as_deref(), expected_tier);
+        let logs = get_logs();
+        let tier_changes: Vec<&String> = logs
+            .iter()
+            .filter(|log| log.contains(r#""event":"holder_tier_changed""#))
+            .filter(|log| log.contains(&format!(r#""account_id":"{}""#, USER_ACCOUNT)))
+            .collect();
+        match expected_tier {
+            Some(expected_tier) => {
+                assert_eq!(tier_changes.len(), 1);
+                assert!(tier_changes[0].contains(&format!(r#""tier":"{}""#, expected_tier)));
+                let metadata = contract
+                    .nft_token(USER_ACCOUNT.to_string())
+                    .unwrap()
+                    .metadata
+                    .unwrap();
+                assert!(metadata
+                    .title
+                    .unwrap()
+                    .starts_with(&format!("{} of the artwork", expected_tier)));
+                assert_eq!(metadata.media, Some(format!("ar://{}", expected_tier)));
+            }
+            None => assert!(tier_changes.is_empty()),
+        }
+    }
+}
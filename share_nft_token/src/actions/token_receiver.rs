@@ -3,6 +3,7 @@
 //! Implements NEP-141 ft_on_transfer method to handle receival of
 //! tokens by the contract.
 
+use crate::sale::SaleCurrency;
 use crate::*;
 
 #[near_bindgen]
@@ -11,15 +12,18 @@ impl Contract {
     /// distribution among share owners in the proportion of their ownership
     #[payable]
     pub fn near_deposit_rewards(&mut self) {
-        let total_reward_near = self.reward_tokens_all_time_count_near.0 + env::attached_deposit();
-        self.reward_tokens_all_time_count_near = U128(total_reward_near);
-        self.contract_rps_near = U128(total_reward_near / self.ft_functionality.ft_total_supply().0);
+        self.assert_not_paused(&PauseFeature::Deposits);
+        self.distribute_rewards_near(env::attached_deposit());
     }
 
     /// Allows the transfer of the reward token to the contract and its immediate
-    /// distribution among share owners in the proportion of their ownership
-    #[allow(unused_variables)]
+    /// distribution among share owners in the proportion of their ownership.
+    /// Shares transferred to the contract itself are handled by
+    /// internal_on_shares_transfer.
     pub fn ft_on_transfer(&mut self, sender_id: AccountId, amount: U128, msg: String) -> U128 {
+        if env::predecessor_account_id() == env::current_account_id() {
+            return self.internal_on_shares_transfer(sender_id, amount, msg);
+        }
         assert_eq!(
             self.reward_token,
             env::predecessor_account_id(),
@@ -29,15 +33,48 @@ impl Contract {
 
         match msg.as_str() {
             "deposit_profits" => {
-                let total_reward_tokens = self.reward_tokens_all_time_count_token.0 + amount.0;
-                self.reward_tokens_all_time_count_token = U128(total_reward_tokens);
-                self.contract_rps_token =
-                    U128(total_reward_tokens / self.ft_functionality.ft_total_supply().0);
+                self.assert_not_paused(&PauseFeature::Deposits);
+                self.distribute_rewards_token(amount.0);
+                U128(0)
+            }
+            "buy_shares" => U128(self.internal_buy_shares(
+                sender_id,
+                amount.0,
+                SaleCurrency::RewardToken,
+            )),
+            "bid_auction" => U128(self.internal_bid_auction(
+                sender_id,
+                amount.0,
+                SaleCurrency::RewardToken,
+            )),
+            "offer_buyout" => U128(self.internal_offer_buyout(sender_id, amount.0)),
+            "deposit_royalties" => {
+                self.internal_deposit_royalties(&sender_id, SaleCurrency::RewardToken, amount.0);
+                U128(0)
+            }
+            "fund_reserve" => {
+                self.assert_not_paused(&PauseFeature::Deposits);
+                self.reserve_token = U128(self.reserve_token.0 + amount.0);
                 U128(0)
             }
             _ => panic!("Invalid msg param"),
         }
     }
+
+}
+
+impl Contract {
+    /// Handles shares transferred to the contract itself through
+    /// ft_transfer_call. Returns the amount of shares to be refunded.
+    fn internal_on_shares_transfer(&mut self, sender_id: AccountId, amount: U128, msg: String) -> U128 {
+        match msg.as_str() {
+            "redeem" => self.internal_redeem(sender_id, amount),
+            "fund_sale" => self.internal_fund_sale(sender_id, amount),
+            "fund_auction" => self.internal_fund_auction(sender_id, amount),
+            _ => panic!("Invalid msg param"),
+        }
+    }
+
 }
 
 #[cfg(test)]
@@ -0,0 +1,215 @@
+//! Upgrade actions module
+//!
+//! Allows the owner to deploy new code to the contract account.
+//! The new code's migrate method is called in the same batch,
+//! converting the stored state into the layout the new code expects.
+//! If migrate fails the whole batch is reverted, keeping the old code.
+
+use crate::ext_interface::MIGRATE_GAS;
+use crate::migration::{StateVersion, VersionedContract, CURRENT_STATE_VERSION};
+use crate::*;
+
+#[near_bindgen]
+impl Contract {
+    /// Deploys the wasm passed as raw input (not JSON) and
+    /// calls migrate on it.
+    pub fn upgrade(&self) -> Promise {
+        self.assert_role(&Role::Owner);
+        let code = env::input().expect("Missing contract code");
+        assert!(!code.is_empty(), "Missing contract code");
+        Promise::new(env::current_account_id())
+            .deploy_contract(code)
+            .function_call("migrate".to_string(), b"{}".to_vec(), 0, MIGRATE_GAS)
+    }
+
+    /// Converts the stored state into the current layout. Must be called
+    /// by the contract itself, either through upgrade or in the same
+    /// transaction as a deployment from a full access key.
+    /// owner_id is only used when migrating from v1.0.0.
+    #[private]
+    #[init(ignore_state)]
+    pub fn migrate(owner_id: Option<AccountId>) -> Self {
+        let mut contract = VersionedContract::read().into_current(owner_id);
+        contract.internal_register_escrow();
+        Contract::write_state_version();
+        contract
+    }
+
+    pub fn view_state_version(&self) -> StateVersion {
+        CURRENT_STATE_VERSION
+    }
+
+    pub fn view_contract_version(&self) -> String {
+        env!("CARGO_PKG_VERSION").to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::migration::{ContractV1_0_0, NftInstanceDataV1_0_0, STATE_VERSION_KEY};
+    use crate::tests::*;
+
+    #[rstest]
+    /// Test upgrade method
+    /// ASSERT:
+    /// (1) Only owner can upgrade
+    #[should_panic = "admin.testnet does not have the owner role"]
+    #[case(ADMIN_ACCOUNT, vec![0, 97, 115, 109])]
+    /// (2) Code must be passed as input
+    #[should_panic = "Missing contract code"]
+    #[case(OWNER_ACCOUNT, vec![])]
+    /// (3) Deploys code and calls migrate in a single receipt
+    #[case(OWNER_ACCOUNT, vec![0, 97, 115, 109])]
+    fn test_upgrade(#[case] caller: &str, #[case] code: Vec<u8>) {
+        // setup
+        let context = get_context(
+            code.clone(),
+            0,
+            0,
+            caller.parse().unwrap(),
+            0,
+            Gas(300u64 * 10u64.pow(12)),
+        );
+        testing_env!(context);
+        let mut contract = init_contract(1);
+        crate::tests::grant_role(&mut contract, ADMIN_ACCOUNT, Role::Admin);
+
+        // call tested method
+        contract.upgrade();
+
+        // perform assertions
+        let receipts = get_created_receipts();
+        assert_eq!(receipts.len(), 1);
+        assert_eq!(receipts[0].receiver_id, CONTRACT_ACCOUNT.parse().unwrap());
+        assert_eq!(receipts[0].actions.len(), 2);
+        if let VmAction::DeployContract { code: deployed } = receipts[0].actions[0].clone() {
+            assert_eq!(deployed, code);
+        } else {
+            panic!()
+        };
+        if let VmAction::FunctionCall { function_name, .. } = receipts[0].actions[1].clone() {
+            assert_eq!(function_name, "migrate");
+        } else {
+            panic!()
+        };
+    }
+
+    #[rstest]
+    /// Test migrate method from the v1.0.0 layout
+    /// ASSERT:
+    /// (1) owner_id is required
+    #[should_panic = "owner_id is required to migrate from v1.0.0"]
+    #[case(None)]
+    /// (2) Balances, rewards and metadata are kept, new fields
+    ///     get their defaults and the state version is recorded
+    #[case(Some(OWNER_ACCOUNT))]
+    fn test_migrate_v1_0_0(#[case] owner_id: Option<&str>) {
+        // setup
+        let context = get_context(
+            vec![],
+            0,
+            0,
+            CONTRACT_ACCOUNT.parse().unwrap(),
+            0,
+            Gas(300u64 * 10u64.pow(12)),
+        );
+        testing_env!(context);
+        let user: AccountId = USER_ACCOUNT.parse().unwrap();
+        let mut legacy = init_contract(1);
+        register_user(&mut legacy, &user, 100, 10, 20);
+        legacy.contract_rps_token = U128(5);
+        legacy.reward_tokens_all_time_count_token = U128(5 * (TOKEN_SUPPLY.0 + 100) + 3);
+        legacy.reward_tokens_all_time_count_near = U128(7);
+        let legacy = ContractV1_0_0 {
+            ft_functionality: legacy.ft_functionality,
+            reward_token: legacy.reward_token,
+            reward_tokens_all_time_count_token: legacy.reward_tokens_all_time_count_token,
+            reward_tokens_all_time_count_near: legacy.reward_tokens_all_time_count_near,
+            contract_rps_token: legacy.contract_rps_token,
+            contract_rps_near: legacy.contract_rps_near,
+            accounts_rps: legacy.accounts_rps,
+            token_metadata: legacy.token_metadata,
+            nft_contract_metadata: legacy.nft_contract_metadata,
+            nft_instance_metadata: LazyOption::new(
+                b"legacy_instance".to_vec(),
+                Some(&NftInstanceDataV1_0_0 {
+                    title: "legacy_title".to_string(),
+                    description: "legacy_description".to_string(),
+                    media: "legacy_media".to_string(),
+                    reference: "legacy_reference".to_string(),
+                }),
+            ),
+        };
+        env::state_write(&legacy);
+        assert!(env::storage_read(STATE_VERSION_KEY).is_none());
+
+        // call tested method
+        let mut contract = Contract::migrate(owner_id.map(|id| id.parse().unwrap()));
+
+        // perform assertions
+        assert_eq!(contract.ft_balance_of(user.clone()), U128(100));
+        assert_eq!(contract.ft_total_supply(), U128(TOKEN_SUPPLY.0 + 100));
+        assert_eq!(contract.contract_rps_token, U128(5));
+        assert_eq!(contract.reward_tokens_all_time_count_near, U128(7));
+        assert_eq!(contract.undistributed_rewards_token, U128(3));
+        assert_eq!(contract.undistributed_rewards_near, U128(7));
+        assert_eq!(contract.view_minting_policy().supply_cap, contract.ft_total_supply());
+        let rps = contract.accounts_rps.get(&user).unwrap();
+        assert_eq!(rps.rewards_balance_token, U128(10));
+        assert_eq!(rps.rewards_balance_near, U128(20));
+        assert_eq!(contract.ft_metadata().symbol, "token_symbol");
+        let instance = contract.nft_instance_metadata.get().unwrap();
+        assert_eq!(instance.title, "legacy_title");
+        assert_eq!(instance.reference, Some("legacy_reference".to_string()));
+        assert!(instance.reference_hash.is_none());
+        assert!(contract.view_metadata_history(None, None).is_empty());
+        assert_eq!(contract.view_owner(), OWNER_ACCOUNT.parse().unwrap());
+        assert!(!contract.view_pause_state().transfers);
+        assert_eq!(contract.view_unindexed_supply(), contract.ft_total_supply());
+        assert!(contract.view_holders(None, None).is_empty());
+        // holders are indexed explicitly or on their next balance change
+        contract.index_holders(vec![user.clone()]);
+        assert_eq!(contract.view_unindexed_supply(), TOKEN_SUPPLY);
+        contract.internal_transfer_shares(&OWNER_ACCOUNT.parse().unwrap(), &user, 10, None);
+        assert_eq!(contract.view_unindexed_supply(), U128(0));
+        assert_eq!(contract.view_holders(None, None).len(), 2);
+        assert_eq!(
+            StateVersion::try_from_slice(&env::storage_read(STATE_VERSION_KEY).unwrap()).unwrap(),
+            CURRENT_STATE_VERSION
+        );
+    }
+
+    #[rstest]
+    /// Test migrate method from the current layout
+    /// ASSERT:
+    /// (1) State is kept as is, owner_id is ignored
+    fn test_migrate_current() {
+        // setup
+        let context = get_context(
+            vec![],
+            0,
+            0,
+            CONTRACT_ACCOUNT.parse().unwrap(),
+            0,
+            Gas(300u64 * 10u64.pow(12)),
+        );
+        testing_env!(context);
+        let mut contract = init_contract(1);
+        contract.pause_flags.claims = true;
+        env::state_write(&contract);
+        Contract::write_state_version();
+
+        // call tested method
+        let contract = Contract::migrate(Some(USER_ACCOUNT.parse().unwrap()));
+
+        // perform assertions
+        assert_eq!(contract.view_owner(), OWNER_ACCOUNT.parse().unwrap());
+        assert!(contract.view_pause_state().claims);
+        assert_eq!(
+            contract.ft_balance_of(OWNER_ACCOUNT.parse().unwrap()),
+            TOKEN_SUPPLY
+        );
+    }
+}
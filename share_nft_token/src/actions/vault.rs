@@ -0,0 +1,282 @@
+//! Vault actions module
+//!
+//! Allows the shares to fractionalize a real NEP-171 token issued by
+//! another contract. The token declared by vault_nft at initialization
+//! is deposited by calling nft_transfer_call on its contract with
+//! receiver_id being this contract. Any other token is returned.
+//!
+//! While custodied, the token is shown in the extra field of share
+//! NFTs and can only leave the contract:
+//! - to the bidder of an accepted buyout, through claim_vault_nft
+//! - to an account redeeming every share, through redeem_vault
+//!
+//! If the transfer fails, the token stays custodied and its recipient
+//! can retry through claim_vault_nft.
+
+use crate::events::{VaultDeposited, VaultReleased};
+use crate::ext_interface::{ext_nft, ext_self, NFT_TRANSFER_GAS, VAULT_RELEASE_CALLBACK_GAS};
+use crate::*;
+use near_contract_standards::non_fungible_token::core::NonFungibleTokenReceiver;
+use near_sdk::is_promise_success;
+
+#[near_bindgen]
+impl NonFungibleTokenReceiver for Contract {
+    /// Takes custody of the underlying NFT. Returns true, so that the
+    /// token is returned, for any other token.
+    #[allow(unused_variables)]
+    fn nft_on_transfer(
+        &mut self,
+        sender_id: AccountId,
+        previous_owner_id: AccountId,
+        token_id: TokenId,
+        msg: String,
+    ) -> PromiseOrValue<bool> {
+        let nft_contract_id = env::predecessor_account_id();
+        let vault = match self.vault.as_mut() {
+            Some(vault)
+                if !vault.custodied
+                    && vault.released_to.is_none()
+                    && vault.nft.nft_contract_id == nft_contract_id
+                    && vault.nft.token_id == token_id =>
+            {
+                vault
+            }
+            _ => return PromiseOrValue::Value(true),
+        };
+        vault.custodied = true;
+        VaultDeposited {
+            nft: &vault.nft,
+            previous_owner_id: &previous_owner_id,
+        }
+        .emit();
+        PromiseOrValue::Value(false)
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Burns every share, which the caller must hold, and
+    /// transfers the underlying NFT to the caller
+    #[payable]
+    pub fn redeem_vault(&mut self) {
+        assert_one_yocto();
+        self.assert_not_paused(&PauseFeature::Transfers);
+        self.assert_no_buyout();
+        let vault = self.vault.as_ref().expect("Contract has no vault");
+        assert!(vault.custodied, "Vault does not hold the NFT");
+        let account_id = env::predecessor_account_id();
+        let amount = self.ft_functionality.accounts.get(&account_id).unwrap_or(0);
+        assert!(
+            amount == self.ft_functionality.total_supply,
+            "Redeeming the NFT requires holding every share"
+        );
+        self.internal_burn_shares(&account_id, amount, Some("vault redeemed"));
+        self.vault.as_mut().unwrap().redeemed_by = Some(account_id.clone());
+        self.internal_release_vault(account_id);
+    }
+
+    /// Transfers the underlying NFT to the caller, which must be the
+    /// bidder of an accepted buyout or the account that redeemed every
+    /// share after a failed transfer
+    #[payable]
+    pub fn claim_vault_nft(&mut self) {
+        assert_one_yocto();
+        let vault = self.vault.as_ref().expect("Contract has no vault");
+        assert!(vault.custodied, "Vault does not hold the NFT");
+        let account_id = env::predecessor_account_id();
+        let is_buyer = self
+            .buyout
+            .as_ref()
+            .is_some_and(|buyout| buyout.is_accepted() && buyout.bidder == account_id);
+        assert!(
+            is_buyer || vault.redeemed_by.as_ref() == Some(&account_id),
+            "{} cannot claim the NFT",
+            account_id
+        );
+        self.internal_release_vault(account_id);
+    }
+
+    /// Keeps the NFT in custody if its transfer to receiver_id failed
+    #[private]
+    pub fn resolve_vault_release(&mut self, receiver_id: AccountId) {
+        let vault = self.vault.as_mut().unwrap();
+        if is_promise_success() {
+            vault.released_to = Some(receiver_id.clone());
+            VaultReleased {
+                nft: &vault.nft,
+                receiver_id: &receiver_id,
+            }
+            .emit();
+        } else {
+            vault.custodied = true;
+        }
+    }
+
+    pub fn view_vault(&self) -> Option<Vault> {
+        self.vault.clone()
+    }
+}
+
+impl Contract {
+    /// Transfers the underlying NFT to receiver_id
+    fn internal_release_vault(&mut self, receiver_id: AccountId) {
+        let vault = self.vault.as_mut().unwrap();
+        vault.custodied = false;
+        ext_nft::ext(vault.nft.nft_contract_id.clone())
+            .with_static_gas(NFT_TRANSFER_GAS)
+            .with_attached_deposit(1)
+            .nft_transfer(
+                receiver_id.clone(),
+                vault.nft.token_id.clone(),
+                None,
+                Some("vault release".to_string()),
+            )
+            .then(
+                ext_self::ext(env::current_account_id())
+                    .with_static_gas(VAULT_RELEASE_CALLBACK_GAS)
+                    .resolve_vault_release(receiver_id),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::buyout::{Buyout, BuyoutStatus};
+    use crate::tests::*;
+    use crate::vault::VaultNft;
+    use near_sdk::json_types::U64;
+
+    const NFT_CONTRACT_ACCOUNT: &str = "nft.testnet";
+
+    fn vault_nft() -> VaultNft {
+        VaultNft {
+            nft_contract_id: NFT_CONTRACT_ACCOUNT.parse().unwrap(),
+            token_id: "artwork".to_string(),
+        }
+    }
+
+    /// Accepted buyout of every share made by user
+    fn accepted_buyout() -> Buyout {
+        Buyout {
+            id: 0,
+            bidder: USER_ACCOUNT.parse().unwrap(),
+            price: U128(1),
+            supply: TOKEN_SUPPLY,
+            escrow: TOKEN_SUPPLY,
+            voting_end: U64(0),
+            votes_for: U128(0),
+            votes_against: U128(0),
+            approval_bps: 1,
+            status: BuyoutStatus::Accepted,
+        }
+    }
+
+    #[rstest]
+    /// Test nft_on_transfer method
+    /// ASSERT:
+    /// (1) Tokens of other contracts are returned
+    #[case(USER_ACCOUNT, "artwork", false)]
+    /// (2) Other tokens of the vault contract are returned
+    #[case(NFT_CONTRACT_ACCOUNT, "other", false)]
+    /// (3) The underlying NFT is custodied and shown in share NFTs
+    #[case(NFT_CONTRACT_ACCOUNT, "artwork", true)]
+    fn test_nft_on_transfer(#[case] predecessor: &str, #[case] token_id: &str, #[case] custodied: bool) {
+        // setup
+        let context = get_context(
+            vec![],
+            0,
+            0,
+            predecessor.parse().unwrap(),
+            0,
+            Gas(300u64 * 10u64.pow(12)),
+        );
+        testing_env!(context);
+        let mut contract = init_contract(1);
+        contract.vault = Some(Vault::new(vault_nft()));
+
+        // call tested method
+        let result = contract.nft_on_transfer(
+            OWNER_ACCOUNT.parse().unwrap(),
+            OWNER_ACCOUNT.parse().unwrap(),
+            token_id.to_string(),
+            "".to_string(),
+        );
+
+        // perform assertions
+        match result {
+            PromiseOrValue::Value(returned) => assert_eq!(returned, !custodied),
+            _ => panic!("nft_on_transfer must return a value"),
+        }
+        assert_eq!(contract.view_vault().unwrap().custodied, custodied);
+        let extra = contract.nft_token(OWNER_ACCOUNT.to_string()).unwrap().metadata.unwrap().extra;
+        assert_eq!(extra.is_some(), custodied);
+        if custodied {
+            assert!(extra.unwrap().contains(r#""token_id":"artwork""#));
+            assert!(get_logs()[0].contains(r#""event":"vault_deposited""#));
+        }
+    }
+
+    #[rstest]
+    /// Test redeem_vault and claim_vault_nft methods
+    /// ASSERT:
+    /// (1) Redemption requires every share
+    #[should_panic = "Redeeming the NFT requires holding every share"]
+    #[case(USER_ACCOUNT, false, 1)]
+    /// (2) Only the bidder of an accepted buyout can claim the NFT
+    #[should_panic = "owner.testnet cannot claim the NFT"]
+    #[case(OWNER_ACCOUNT, true, 0)]
+    /// (3) Holder of every share burns them to receive the NFT
+    #[case(OWNER_ACCOUNT, false, 0)]
+    /// (4) Bidder of an accepted buyout receives the NFT
+    #[case(USER_ACCOUNT, true, 0)]
+    fn test_release_vault(#[case] caller: &str, #[case] bought_out: bool, #[case] user_balance: u128) {
+        // setup
+        let context = get_context(
+            vec![],
+            1,
+            10u128.pow(24),
+            caller.parse().unwrap(),
+            0,
+            Gas(300u64 * 10u64.pow(12)),
+        );
+        testing_env!(context.clone());
+        let mut contract = init_contract(1);
+        register_user(&mut contract, &USER_ACCOUNT.parse().unwrap(), user_balance, 0, 0);
+        let mut vault = Vault::new(vault_nft());
+        vault.custodied = true;
+        contract.vault = Some(vault);
+        if bought_out {
+            contract.buyout = Some(accepted_buyout());
+        }
+
+        // call tested method
+        if bought_out {
+            contract.claim_vault_nft();
+        } else {
+            contract.redeem_vault();
+        }
+        testing_env!(
+            VMContext {
+                predecessor_account_id: CONTRACT_ACCOUNT.parse().unwrap(),
+                storage_usage: env::storage_usage(),
+                ..context
+            },
+            near_sdk::VMConfig::test(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Successful(vec![])],
+        );
+        contract.resolve_vault_release(caller.parse().unwrap());
+
+        // perform assertions
+        let vault = contract.view_vault().unwrap();
+        assert!(!vault.custodied);
+        assert_eq!(vault.released_to, Some(caller.parse().unwrap()));
+        if !bought_out {
+            assert_eq!(contract.ft_total_supply(), U128(0));
+        }
+        assert!(get_logs()[0].contains(r#""event":"vault_released""#));
+    }
+}
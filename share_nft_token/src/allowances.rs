@@ -0,0 +1,28 @@
+use crate::*;
+use std::collections::HashMap;
+
+/// Shares of a holder that each spender can transfer
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct ShareAllowances {
+    /// Splits started when the allowances were granted. Allowances
+    /// are void once a later split changes the share unit.
+    pub splits_count: u64,
+    pub spenders: HashMap<AccountId, U128>,
+}
+
+impl ShareAllowances {
+    pub fn new(splits_count: u64) -> Self {
+        Self {
+            splits_count,
+            spenders: HashMap::new(),
+        }
+    }
+
+    /// Allowance of spender_id, 0 if granted before the latest split
+    pub fn allowance(&self, spender_id: &AccountId, splits_count: u64) -> u128 {
+        if self.splits_count != splits_count {
+            return 0;
+        }
+        self.spenders.get(spender_id).map_or(0, |amount| amount.0)
+    }
+}
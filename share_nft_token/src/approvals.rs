@@ -0,0 +1,30 @@
+use crate::*;
+use std::collections::HashMap;
+
+/// Accounts approved by a holder to transfer its position NFT
+#[derive(BorshDeserialize, BorshSerialize, Default)]
+pub struct PositionApprovals {
+    /// Approval id assigned to the next approved account. Kept when
+    /// approvals are cleared, so stale approval ids are never reused.
+    pub next_approval_id: u64,
+    pub approved_account_ids: HashMap<AccountId, u64>,
+}
+
+impl PositionApprovals {
+    /// Approves account_id, replacing any previous approval,
+    /// and returns the new approval id
+    pub fn approve(&mut self, account_id: &AccountId) -> u64 {
+        let approval_id = self.next_approval_id;
+        self.approved_account_ids.insert(account_id.clone(), approval_id);
+        self.next_approval_id += 1;
+        approval_id
+    }
+
+    /// Whether account_id is approved, with approval_id if given
+    pub fn is_approved(&self, account_id: &AccountId, approval_id: Option<u64>) -> bool {
+        match self.approved_account_ids.get(account_id) {
+            Some(id) => approval_id.is_none_or(|approval_id| approval_id == *id),
+            None => false,
+        }
+    }
+}
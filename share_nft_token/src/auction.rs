@@ -0,0 +1,109 @@
+use crate::*;
+use crate::sale::SaleCurrency;
+use near_sdk::json_types::U64;
+
+/// Terms of a descending price auction, set by an admin
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AuctionConfig {
+    /// Account funding the auctioned shares, receives unsold shares back
+    pub issuer: AccountId,
+    /// Account receiving the proceeds
+    pub treasury: AccountId,
+    pub currency: SaleCurrency,
+    /// Shares auctioned
+    pub supply: U128,
+    /// Price of one share at start, decreasing linearly down to
+    /// floor_price at end
+    pub start_price: U128,
+    pub floor_price: U128,
+    /// Block timestamps in nanoseconds
+    pub start: U64,
+    pub end: U64,
+}
+
+impl AuctionConfig {
+    pub fn assert_valid(&self) {
+        assert!(self.supply.0 > 0, "supply must be greater than 0");
+        assert!(self.floor_price.0 > 0, "floor_price must be greater than 0");
+        assert!(
+            self.start_price.0 >= self.floor_price.0,
+            "start_price cannot be lower than floor_price"
+        );
+        assert!(self.start.0 < self.end.0, "start must be before end");
+        assert!(
+            self.end.0 > env::block_timestamp(),
+            "end must be in the future"
+        );
+    }
+
+    /// Price of one share at timestamp
+    pub fn price_at(&self, timestamp: u64) -> u128 {
+        if timestamp <= self.start.0 {
+            return self.start_price.0;
+        }
+        if timestamp >= self.end.0 {
+            return self.floor_price.0;
+        }
+        let elapsed = (timestamp - self.start.0) as u128;
+        let duration = (self.end.0 - self.start.0) as u128;
+        self.start_price.0 - (self.start_price.0 - self.floor_price.0) * elapsed / duration
+    }
+}
+
+/// State of a descending price auction
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Auction {
+    pub id: u64,
+    pub config: AuctionConfig,
+    /// Whether the issuer escrowed the auctioned shares
+    pub funded: bool,
+    /// Sum of all bids, in config.currency
+    pub committed: U128,
+    /// Number of accounts that placed bids
+    pub bidders: u64,
+    /// Uniform price paid by every bidder, fixed on the first
+    /// settle_auction call
+    pub clearing_price: Option<U128>,
+    /// Number of bidders whose allocation has been computed
+    pub settled_bidders: u64,
+    pub sold: U128,
+    pub proceeds: U128,
+}
+
+impl Auction {
+    /// Bids cannot exceed the value of the supply at the current
+    /// price. Once they reach it, the auction has cleared.
+    pub fn has_cleared(&self, timestamp: u64) -> bool {
+        self.committed.0 >= self.config.supply.0 * self.config.price_at(timestamp)
+    }
+
+    /// Price at which every share sells, once known. Rounded up so
+    /// that bids never buy more than the supply.
+    pub fn compute_clearing_price(&self, timestamp: u64) -> Option<u128> {
+        if self.has_cleared(timestamp) {
+            let supply = self.config.supply.0;
+            Some(self.committed.0.div_ceil(supply))
+        } else if timestamp >= self.config.end.0 {
+            Some(self.config.floor_price.0)
+        } else {
+            None
+        }
+    }
+
+    pub fn is_settled(&self) -> bool {
+        self.clearing_price.is_some() && self.settled_bidders == self.bidders
+    }
+}
+
+/// Bid of an account in an auction, removed once claimed
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AuctionBid {
+    pub currency: SaleCurrency,
+    pub committed: U128,
+    /// Shares bought and funds refunded, computed on settlement
+    pub shares: U128,
+    pub refund: U128,
+}
@@ -0,0 +1,52 @@
+use crate::*;
+
+/// Movement of shares applied by internal_change_balances
+pub struct BalanceChange<'a> {
+    /// Account whose shares move, None mints them
+    pub sender_id: Option<&'a AccountId>,
+    /// Account receiving the shares, None burns them
+    pub receiver_id: Option<&'a AccountId>,
+    pub amount: u128,
+    pub memo: Option<&'a str>,
+}
+
+impl<'a> BalanceChange<'a> {
+    pub fn transfer(sender_id: &'a AccountId, receiver_id: &'a AccountId, amount: u128, memo: Option<&'a str>) -> Self {
+        Self {
+            sender_id: Some(sender_id),
+            receiver_id: Some(receiver_id),
+            amount,
+            memo,
+        }
+    }
+
+    pub fn mint(receiver_id: &'a AccountId, amount: u128, memo: Option<&'a str>) -> Self {
+        Self {
+            sender_id: None,
+            receiver_id: Some(receiver_id),
+            amount,
+            memo,
+        }
+    }
+
+    pub fn burn(sender_id: &'a AccountId, amount: u128, memo: Option<&'a str>) -> Self {
+        Self {
+            sender_id: Some(sender_id),
+            receiver_id: None,
+            amount,
+            memo,
+        }
+    }
+
+    pub fn assert_valid(&self) {
+        assert!(self.amount > 0, "The amount should be a positive number");
+        assert!(
+            self.sender_id.is_some() || self.receiver_id.is_some(),
+            "Balance change requires a sender or a receiver"
+        );
+        assert!(
+            self.sender_id.is_none() || self.sender_id != self.receiver_id,
+            "Sender and receiver should be different"
+        );
+    }
+}
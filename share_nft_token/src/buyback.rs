@@ -0,0 +1,19 @@
+use crate::*;
+
+/// Offer of the issuer to buy back shares at a fixed price,
+/// paid from a budget taken out of the reserve.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Buyback {
+    /// Reward tokens paid per share
+    pub price: U128,
+    /// Reward tokens left to pay for redemptions
+    pub pool: U128,
+}
+
+impl Buyback {
+    /// Shares out of amount that the pool can pay for
+    pub fn redeemable(&self, amount: u128) -> u128 {
+        std::cmp::min(amount, self.pool.0 / self.price.0)
+    }
+}
@@ -0,0 +1,85 @@
+use crate::*;
+use near_sdk::json_types::U64;
+
+/// Basis points of the supply representing all shares
+pub const FULL_APPROVAL_BPS: u16 = 10_000;
+
+/// Rules deciding whether offers to buy the whole artwork are accepted
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct BuyoutPolicy {
+    /// Offers at or above this price per share are accepted without a vote
+    pub reserve_price: Option<U128>,
+    /// Duration of the vote on other offers, in nanoseconds
+    pub voting_period: U64,
+    /// Part of the supply, in basis points, that must approve an offer
+    pub approval_bps: u16,
+}
+
+impl BuyoutPolicy {
+    pub fn assert_valid(&self) {
+        assert!(
+            self.voting_period.0 > 0,
+            "voting_period must be greater than 0"
+        );
+        assert!(
+            self.approval_bps > 0 && self.approval_bps <= FULL_APPROVAL_BPS,
+            "approval_bps must be between 1 and {}",
+            FULL_APPROVAL_BPS
+        );
+    }
+
+    /// Expresses the reserve price per share after a split, rounded up
+    /// so that the whole artwork is never valued lower than before
+    pub fn rescale(&mut self, numerator: u128, denominator: u128) {
+        if let Some(reserve) = self.reserve_price {
+            let scaled = reserve.0.checked_mul(denominator).expect("Reserve price overflow");
+            self.reserve_price = Some(U128(scaled.div_ceil(numerator)));
+        }
+    }
+}
+
+#[derive(
+    BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug,
+)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(rename_all = "snake_case")]
+pub enum BuyoutStatus {
+    /// Holders are voting on the offer
+    Voting,
+    /// Transfers are frozen and holders redeem their shares
+    Accepted,
+}
+
+/// Offer to buy every share at a fixed price in the reward token
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Buyout {
+    pub id: u64,
+    pub bidder: AccountId,
+    /// Reward tokens paid per share
+    pub price: U128,
+    /// Shares in existence when the offer was made
+    pub supply: U128,
+    /// Reward tokens escrowed and not yet paid to holders
+    pub escrow: U128,
+    /// Block timestamp in nanoseconds at which the vote ends
+    pub voting_end: U64,
+    /// Shares voting for and against the offer
+    pub votes_for: U128,
+    pub votes_against: U128,
+    /// Approval threshold of the policy when the offer was made
+    pub approval_bps: u16,
+    pub status: BuyoutStatus,
+}
+
+impl Buyout {
+    pub fn is_accepted(&self) -> bool {
+        self.status == BuyoutStatus::Accepted
+    }
+
+    /// Whether enough shares voted for the offer
+    pub fn is_approved(&self) -> bool {
+        self.votes_for.0 * FULL_APPROVAL_BPS as u128 >= self.supply.0 * self.approval_bps as u128
+    }
+}
@@ -0,0 +1,179 @@
+use crate::*;
+
+/// Contract wide rules restricting transfers of shares,
+/// set by compliance officers.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Default, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TransferRestrictions {
+    /// Only allowlisted accounts can send or receive shares
+    pub allowlist_only: bool,
+    /// Accounts in any of these jurisdictions can neither
+    /// send nor receive shares
+    pub blocked_jurisdictions: Vec<String>,
+}
+
+impl TransferRestrictions {
+    pub fn assert_valid(&self) {
+        self.blocked_jurisdictions
+            .iter()
+            .for_each(|jurisdiction| assert_valid_jurisdiction(jurisdiction));
+    }
+}
+
+/// Compliance attributes of an account, set by compliance officers
+#[derive(
+    BorshDeserialize, BorshSerialize, Serialize, Deserialize, Default, Clone, PartialEq, Eq, Debug,
+)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AccountAttributes {
+    /// Account passed the checks required to hold shares
+    pub allowlisted: bool,
+    /// Account can neither send nor receive shares
+    pub frozen: bool,
+    /// ISO 3166-1 alpha-2 code of the country of the account
+    pub jurisdiction: Option<String>,
+}
+
+impl AccountAttributes {
+    pub fn assert_valid(&self) {
+        if let Some(jurisdiction) = &self.jurisdiction {
+            assert_valid_jurisdiction(jurisdiction);
+        }
+    }
+}
+
+/// Jurisdictions are upper case ISO 3166-1 alpha-2 codes, e.g. "US"
+fn assert_valid_jurisdiction(jurisdiction: &str) {
+    assert!(
+        jurisdiction.len() == 2 && jurisdiction.chars().all(|c| c.is_ascii_uppercase()),
+        "Invalid jurisdiction {}, must be an ISO 3166-1 alpha-2 code",
+        jurisdiction
+    );
+}
+
+/// Side of a transfer on which an account stands
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TransferParty {
+    /// Account whose shares are debited
+    Sender,
+    /// Account credited with shares, by transfers, purchases,
+    /// auction claims or minting
+    Receiver,
+    /// Account moving shares of a holder out of its allowance
+    Spender,
+}
+
+/// Reason why a transfer of shares would fail, reported by
+/// detect_transfer_restriction before users sign.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(rename_all = "snake_case")]
+pub enum TransferRestriction {
+    /// Transfer is allowed
+    None,
+    TransfersPaused,
+    InvalidAmount,
+    InsufficientBalance,
+    ReceiverNotRegistered,
+    SenderFrozen,
+    ReceiverFrozen,
+    SpenderFrozen,
+    SenderNotAllowlisted,
+    ReceiverNotAllowlisted,
+    SpenderNotAllowlisted,
+    SenderJurisdictionBlocked,
+    ReceiverJurisdictionBlocked,
+    SpenderJurisdictionBlocked,
+}
+
+impl TransferRestriction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TransferRestriction::None => "none",
+            TransferRestriction::TransfersPaused => "transfers_paused",
+            TransferRestriction::InvalidAmount => "invalid_amount",
+            TransferRestriction::InsufficientBalance => "insufficient_balance",
+            TransferRestriction::ReceiverNotRegistered => "receiver_not_registered",
+            TransferRestriction::SenderFrozen => "sender_frozen",
+            TransferRestriction::ReceiverFrozen => "receiver_frozen",
+            TransferRestriction::SpenderFrozen => "spender_frozen",
+            TransferRestriction::SenderNotAllowlisted => "sender_not_allowlisted",
+            TransferRestriction::ReceiverNotAllowlisted => "receiver_not_allowlisted",
+            TransferRestriction::SpenderNotAllowlisted => "spender_not_allowlisted",
+            TransferRestriction::SenderJurisdictionBlocked => "sender_jurisdiction_blocked",
+            TransferRestriction::ReceiverJurisdictionBlocked => "receiver_jurisdiction_blocked",
+            TransferRestriction::SpenderJurisdictionBlocked => "spender_jurisdiction_blocked",
+        }
+    }
+}
+
+impl Contract {
+    /// Panics if compliance rules forbid sender_id from transferring
+    /// shares to receiver_id. Must be called by every entry point
+    /// through which holders move shares between accounts.
+    pub fn assert_transfer_allowed(&self, sender_id: &AccountId, receiver_id: &AccountId) {
+        assert_unrestricted(self.internal_compliance_restriction(sender_id, receiver_id));
+    }
+
+    /// Panics if compliance rules forbid account_id from taking part
+    /// in a transfer as party. Must be called with the receiver by every
+    /// other entry point crediting shares, such as purchases and minting.
+    pub fn assert_party_allowed(&self, account_id: &AccountId, party: TransferParty) {
+        assert_unrestricted(self.internal_party_restriction(account_id, party));
+    }
+
+    /// Checks the sender, then the receiver
+    pub fn internal_compliance_restriction(
+        &self,
+        sender_id: &AccountId,
+        receiver_id: &AccountId,
+    ) -> TransferRestriction {
+        match self.internal_party_restriction(sender_id, TransferParty::Sender) {
+            TransferRestriction::None => {
+                self.internal_party_restriction(receiver_id, TransferParty::Receiver)
+            }
+            restriction => restriction,
+        }
+    }
+
+    /// Checks whether account_id is frozen, allowlisted and in a blocked
+    /// jurisdiction, in this order. The contract's own account, which
+    /// escrows shares of sales and auctions, is exempt.
+    pub fn internal_party_restriction(&self, account_id: &AccountId, party: TransferParty) -> TransferRestriction {
+        if *account_id == env::current_account_id() {
+            return TransferRestriction::None;
+        }
+        let attributes = self.account_attributes.get(account_id).unwrap_or_default();
+        let restrictions = &self.transfer_restrictions;
+        let blocked = attributes
+            .jurisdiction
+            .as_ref()
+            .is_some_and(|jurisdiction| restrictions.blocked_jurisdictions.contains(jurisdiction));
+        match party {
+            TransferParty::Sender if attributes.frozen => TransferRestriction::SenderFrozen,
+            TransferParty::Receiver if attributes.frozen => TransferRestriction::ReceiverFrozen,
+            TransferParty::Spender if attributes.frozen => TransferRestriction::SpenderFrozen,
+            TransferParty::Sender if restrictions.allowlist_only && !attributes.allowlisted => {
+                TransferRestriction::SenderNotAllowlisted
+            }
+            TransferParty::Receiver if restrictions.allowlist_only && !attributes.allowlisted => {
+                TransferRestriction::ReceiverNotAllowlisted
+            }
+            TransferParty::Spender if restrictions.allowlist_only && !attributes.allowlisted => {
+                TransferRestriction::SpenderNotAllowlisted
+            }
+            TransferParty::Sender if blocked => TransferRestriction::SenderJurisdictionBlocked,
+            TransferParty::Receiver if blocked => TransferRestriction::ReceiverJurisdictionBlocked,
+            TransferParty::Spender if blocked => TransferRestriction::SpenderJurisdictionBlocked,
+            _ => TransferRestriction::None,
+        }
+    }
+}
+
+fn assert_unrestricted(restriction: TransferRestriction) {
+    assert!(
+        restriction == TransferRestriction::None,
+        "Transfer restricted: {}",
+        restriction.as_str()
+    );
+}
@@ -0,0 +1,623 @@
+//! Events specific to the artwork shares contract
+//!
+//! NEP-141 and NEP-171 events are emitted through near_contract_standards.
+//! Every other state change that indexers need to follow is logged here,
+//! using the same [NEP-297](https://nomicon.io/Standards/EventsFormat)
+//! format under the `ample_share` standard.
+
+use crate::*;
+use crate::auction::AuctionConfig;
+use crate::buyout::BuyoutPolicy;
+use crate::minting::MintAllowance;
+use crate::pause::PauseFeature;
+use crate::roles::Role;
+use crate::royalties::RoyaltyConfig;
+use crate::sale::{SaleConfig, SaleCurrency};
+use crate::tiers::HolderTier;
+use crate::vault::VaultNft;
+use near_sdk::json_types::U64;
+use near_sdk::serde_json;
+
+pub const EVENT_STANDARD: &str = "ample_share";
+pub const EVENT_STANDARD_VERSION: &str = "1.0.0";
+
+/// Data to log when an account receives a role.
+#[must_use]
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RoleGranted<'a> {
+    pub account_id: &'a AccountId,
+    pub role: &'a Role,
+    pub granted_by: &'a AccountId,
+}
+
+impl RoleGranted<'_> {
+    pub fn emit(self) {
+        emit_event(AmpleShareEventKind::RoleGranted(&[self]))
+    }
+}
+
+/// Data to log when a role is taken away from an account.
+#[must_use]
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RoleRevoked<'a> {
+    pub account_id: &'a AccountId,
+    pub role: &'a Role,
+    pub revoked_by: &'a AccountId,
+}
+
+impl RoleRevoked<'_> {
+    pub fn emit(self) {
+        emit_event(AmpleShareEventKind::RoleRevoked(&[self]))
+    }
+}
+
+/// Data to log when the contract owner changes.
+#[must_use]
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct OwnershipTransferred<'a> {
+    pub old_owner_id: &'a AccountId,
+    pub new_owner_id: &'a AccountId,
+}
+
+impl OwnershipTransferred<'_> {
+    pub fn emit(self) {
+        emit_event(AmpleShareEventKind::OwnershipTransferred(&[self]))
+    }
+}
+
+/// Data to log when a feature is paused or unpaused.
+#[must_use]
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PauseChanged<'a> {
+    pub feature: &'a PauseFeature,
+    pub account_id: &'a AccountId,
+}
+
+impl PauseChanged<'_> {
+    pub fn emit_paused(self) {
+        emit_event(AmpleShareEventKind::FeaturePaused(&[self]))
+    }
+
+    pub fn emit_unpaused(self) {
+        emit_event(AmpleShareEventKind::FeatureUnpaused(&[self]))
+    }
+}
+
+/// Data to log when token or NFT metadata is replaced.
+#[must_use]
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MetadataUpdated<'a> {
+    pub kind: &'a str,
+    pub updated_by: &'a AccountId,
+    pub history_index: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reference: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reference_hash: Option<&'a Base64VecU8>,
+}
+
+impl MetadataUpdated<'_> {
+    pub fn emit(self) {
+        emit_event(AmpleShareEventKind::MetadataUpdated(&[self]))
+    }
+}
+
+/// Data to log when the owner replaces the minting policy.
+#[must_use]
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MintingPolicyUpdated<'a> {
+    pub updated_by: &'a AccountId,
+    pub supply_cap: &'a U128,
+    pub schedule: &'a [MintAllowance],
+}
+
+impl MintingPolicyUpdated<'_> {
+    pub fn emit(self) {
+        emit_event(AmpleShareEventKind::MintingPolicyUpdated(&[self]))
+    }
+}
+
+/// Data to log when a split starts rescaling holders.
+#[must_use]
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SplitStarted {
+    pub split_id: u64,
+    pub numerator: u32,
+    pub denominator: u32,
+    /// Holders to rescale
+    pub holders: u64,
+}
+
+impl SplitStarted {
+    pub fn emit(self) {
+        emit_event(AmpleShareEventKind::SplitStarted(&[self]))
+    }
+}
+
+/// Data to log when every balance is rescaled by a split.
+#[must_use]
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SharesSplit<'a> {
+    pub split_id: u64,
+    pub numerator: u32,
+    pub denominator: u32,
+    pub total_supply: &'a U128,
+    /// Reward tokens paid out for fractional shares
+    pub cash_out: &'a U128,
+}
+
+impl SharesSplit<'_> {
+    pub fn emit(self) {
+        emit_event(AmpleShareEventKind::SharesSplit(&[self]))
+    }
+}
+
+/// Data to log when a buyback is opened.
+#[must_use]
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct BuybackOpened<'a> {
+    pub price: &'a U128,
+    pub budget: &'a U128,
+}
+
+impl BuybackOpened<'_> {
+    pub fn emit(self) {
+        emit_event(AmpleShareEventKind::BuybackOpened(&[self]))
+    }
+}
+
+/// Data to log when a buyback is closed.
+#[must_use]
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct BuybackClosed<'a> {
+    /// Unused budget returned to the reserve
+    pub returned: &'a U128,
+}
+
+impl BuybackClosed<'_> {
+    pub fn emit(self) {
+        emit_event(AmpleShareEventKind::BuybackClosed(&[self]))
+    }
+}
+
+/// Data to log when a holder sells shares back to the issuer.
+#[must_use]
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SharesRedeemed<'a> {
+    pub account_id: &'a AccountId,
+    pub amount: &'a U128,
+    pub payout: &'a U128,
+}
+
+impl SharesRedeemed<'_> {
+    pub fn emit(self) {
+        emit_event(AmpleShareEventKind::SharesRedeemed(&[self]))
+    }
+}
+
+/// Data to log when a sale is created.
+#[must_use]
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SaleCreated<'a> {
+    pub sale_id: u64,
+    pub config: &'a SaleConfig,
+}
+
+impl SaleCreated<'_> {
+    pub fn emit(self) {
+        emit_event(AmpleShareEventKind::SaleCreated(&[self]))
+    }
+}
+
+/// Data to log when a sale is created as a rights offering.
+#[must_use]
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RightsOfferingCreated<'a> {
+    pub sale_id: u64,
+    pub rights_end: &'a U64,
+    pub snapshot_supply: &'a U128,
+}
+
+impl RightsOfferingCreated<'_> {
+    pub fn emit(self) {
+        emit_event(AmpleShareEventKind::RightsOfferingCreated(&[self]))
+    }
+}
+
+/// Data to log when an account buys shares in a sale.
+#[must_use]
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SharesPurchased<'a> {
+    pub sale_id: u64,
+    pub account_id: &'a AccountId,
+    pub amount: &'a U128,
+    pub cost: &'a U128,
+}
+
+impl SharesPurchased<'_> {
+    pub fn emit(self) {
+        emit_event(AmpleShareEventKind::SharesPurchased(&[self]))
+    }
+}
+
+/// Data to log when a sale is closed and settled.
+#[must_use]
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SaleFinalized<'a> {
+    pub sale_id: u64,
+    pub sold: &'a U128,
+    pub proceeds: &'a U128,
+}
+
+impl SaleFinalized<'_> {
+    pub fn emit(self) {
+        emit_event(AmpleShareEventKind::SaleFinalized(&[self]))
+    }
+}
+
+/// Data to log when an auction is created.
+#[must_use]
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AuctionCreated<'a> {
+    pub auction_id: u64,
+    pub config: &'a AuctionConfig,
+}
+
+impl AuctionCreated<'_> {
+    pub fn emit(self) {
+        emit_event(AmpleShareEventKind::AuctionCreated(&[self]))
+    }
+}
+
+/// Data to log when an account bids in an auction.
+#[must_use]
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AuctionBidPlaced<'a> {
+    pub auction_id: u64,
+    pub account_id: &'a AccountId,
+    pub amount: &'a U128,
+    pub price: &'a U128,
+}
+
+impl AuctionBidPlaced<'_> {
+    pub fn emit(self) {
+        emit_event(AmpleShareEventKind::AuctionBidPlaced(&[self]))
+    }
+}
+
+/// Data to log once every bid of an auction is settled.
+#[must_use]
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AuctionSettled<'a> {
+    pub auction_id: u64,
+    pub clearing_price: &'a U128,
+    pub sold: &'a U128,
+    pub proceeds: &'a U128,
+}
+
+impl AuctionSettled<'_> {
+    pub fn emit(self) {
+        emit_event(AmpleShareEventKind::AuctionSettled(&[self]))
+    }
+}
+
+/// Data to log when the buyout policy is replaced.
+#[must_use]
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct BuyoutPolicyUpdated<'a> {
+    pub updated_by: &'a AccountId,
+    pub policy: Option<&'a BuyoutPolicy>,
+}
+
+impl BuyoutPolicyUpdated<'_> {
+    pub fn emit(self) {
+        emit_event(AmpleShareEventKind::BuyoutPolicyUpdated(&[self]))
+    }
+}
+
+/// Data to log when an offer to buy the whole artwork is made.
+#[must_use]
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct BuyoutOffered<'a> {
+    pub buyout_id: u64,
+    pub bidder: &'a AccountId,
+    pub price: &'a U128,
+    pub escrow: &'a U128,
+    pub voting_end: &'a U64,
+}
+
+impl BuyoutOffered<'_> {
+    pub fn emit(self) {
+        emit_event(AmpleShareEventKind::BuyoutOffered(&[self]))
+    }
+}
+
+/// Data to log when a holder votes on a buyout offer.
+#[must_use]
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct BuyoutVoted<'a> {
+    pub buyout_id: u64,
+    pub account_id: &'a AccountId,
+    pub approve: bool,
+    pub weight: &'a U128,
+}
+
+impl BuyoutVoted<'_> {
+    pub fn emit(self) {
+        emit_event(AmpleShareEventKind::BuyoutVoted(&[self]))
+    }
+}
+
+/// Data to log when a buyout offer is accepted and transfers freeze.
+#[must_use]
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct BuyoutAccepted {
+    pub buyout_id: u64,
+}
+
+impl BuyoutAccepted {
+    pub fn emit(self) {
+        emit_event(AmpleShareEventKind::BuyoutAccepted(&[self]))
+    }
+}
+
+/// Data to log when a buyout offer is rejected and its escrow refunded.
+#[must_use]
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct BuyoutRejected<'a> {
+    pub buyout_id: u64,
+    pub refund: &'a U128,
+}
+
+impl BuyoutRejected<'_> {
+    pub fn emit(self) {
+        emit_event(AmpleShareEventKind::BuyoutRejected(&[self]))
+    }
+}
+
+/// Data to log when the contract takes custody of the underlying NFT.
+#[must_use]
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct VaultDeposited<'a> {
+    pub nft: &'a VaultNft,
+    pub previous_owner_id: &'a AccountId,
+}
+
+impl VaultDeposited<'_> {
+    pub fn emit(self) {
+        emit_event(AmpleShareEventKind::VaultDeposited(&[self]))
+    }
+}
+
+/// Data to log when the underlying NFT leaves the contract.
+#[must_use]
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct VaultReleased<'a> {
+    pub nft: &'a VaultNft,
+    pub receiver_id: &'a AccountId,
+}
+
+impl VaultReleased<'_> {
+    pub fn emit(self) {
+        emit_event(AmpleShareEventKind::VaultReleased(&[self]))
+    }
+}
+
+/// Data to log when the royalties on position sales change.
+#[must_use]
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RoyaltiesUpdated<'a> {
+    pub updated_by: &'a AccountId,
+    pub royalties: Option<&'a RoyaltyConfig>,
+}
+
+impl RoyaltiesUpdated<'_> {
+    pub fn emit(self) {
+        emit_event(AmpleShareEventKind::RoyaltiesUpdated(&[self]))
+    }
+}
+
+/// Data to log when a position is sold with a royalty owed
+/// to the dividend pool. Nothing is credited until the royalty
+/// is deposited.
+#[must_use]
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RoyaltyOwed<'a> {
+    pub token_id: &'a str,
+    pub balance: &'a U128,
+    pub amount: &'a U128,
+}
+
+impl RoyaltyOwed<'_> {
+    pub fn emit(self) {
+        emit_event(AmpleShareEventKind::RoyaltyOwed(&[self]))
+    }
+}
+
+/// Data to log when royalties are deposited to the dividend pool
+/// and distributed among holders.
+#[must_use]
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RoyaltiesDeposited<'a> {
+    pub depositor: &'a AccountId,
+    pub currency: &'a SaleCurrency,
+    pub amount: &'a U128,
+}
+
+impl RoyaltiesDeposited<'_> {
+    pub fn emit(self) {
+        emit_event(AmpleShareEventKind::RoyaltiesDeposited(&[self]))
+    }
+}
+
+/// Data to log when the tiers of holders change.
+#[must_use]
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TiersUpdated<'a> {
+    pub updated_by: &'a AccountId,
+    pub tiers: &'a [HolderTier],
+}
+
+impl TiersUpdated<'_> {
+    pub fn emit(self) {
+        emit_event(AmpleShareEventKind::TiersUpdated(&[self]))
+    }
+}
+
+/// Data to log when a balance change moves a holder across
+/// the threshold of a tier.
+#[must_use]
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct HolderTierChanged<'a> {
+    pub account_id: &'a AccountId,
+    pub previous_tier: Option<&'a str>,
+    pub tier: Option<&'a str>,
+}
+
+impl HolderTierChanged<'_> {
+    pub fn emit(self) {
+        emit_event(AmpleShareEventKind::HolderTierChanged(&[self]))
+    }
+}
+
+/// Data to log when a holder changes the shares a spender
+/// can transfer on its behalf.
+#[must_use]
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ShareAllowanceUpdated<'a> {
+    pub owner_id: &'a AccountId,
+    pub spender_id: &'a AccountId,
+    pub amount: &'a U128,
+}
+
+impl ShareAllowanceUpdated<'_> {
+    pub fn emit(self) {
+        emit_event(AmpleShareEventKind::ShareAllowanceUpdated(&[self]))
+    }
+}
+
+/// Data to log when compliance officers change the rules
+/// restricting transfers.
+#[must_use]
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TransferRestrictionsUpdated<'a> {
+    pub restrictions: &'a TransferRestrictions,
+    pub updated_by: &'a AccountId,
+}
+
+impl TransferRestrictionsUpdated<'_> {
+    pub fn emit(self) {
+        emit_event(AmpleShareEventKind::TransferRestrictionsUpdated(&[self]))
+    }
+}
+
+/// Data to log when compliance officers change the attributes
+/// of an account.
+#[must_use]
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AccountAttributesUpdated<'a> {
+    pub account_id: &'a AccountId,
+    pub attributes: &'a AccountAttributes,
+    pub updated_by: &'a AccountId,
+}
+
+impl AccountAttributesUpdated<'_> {
+    pub fn emit(self) {
+        emit_event(AmpleShareEventKind::AccountAttributesUpdated(&[self]))
+    }
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+struct AmpleShareEvent<'a> {
+    standard: &'static str,
+    version: &'static str,
+    #[serde(flatten)]
+    event_kind: AmpleShareEventKind<'a>,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(tag = "event", content = "data")]
+#[serde(rename_all = "snake_case")]
+enum AmpleShareEventKind<'a> {
+    RoleGranted(&'a [RoleGranted<'a>]),
+    RoleRevoked(&'a [RoleRevoked<'a>]),
+    OwnershipTransferred(&'a [OwnershipTransferred<'a>]),
+    FeaturePaused(&'a [PauseChanged<'a>]),
+    FeatureUnpaused(&'a [PauseChanged<'a>]),
+    MetadataUpdated(&'a [MetadataUpdated<'a>]),
+    MintingPolicyUpdated(&'a [MintingPolicyUpdated<'a>]),
+    SplitStarted(&'a [SplitStarted]),
+    SharesSplit(&'a [SharesSplit<'a>]),
+    BuybackOpened(&'a [BuybackOpened<'a>]),
+    BuybackClosed(&'a [BuybackClosed<'a>]),
+    SharesRedeemed(&'a [SharesRedeemed<'a>]),
+    SaleCreated(&'a [SaleCreated<'a>]),
+    RightsOfferingCreated(&'a [RightsOfferingCreated<'a>]),
+    SharesPurchased(&'a [SharesPurchased<'a>]),
+    SaleFinalized(&'a [SaleFinalized<'a>]),
+    AuctionCreated(&'a [AuctionCreated<'a>]),
+    AuctionBidPlaced(&'a [AuctionBidPlaced<'a>]),
+    AuctionSettled(&'a [AuctionSettled<'a>]),
+    BuyoutPolicyUpdated(&'a [BuyoutPolicyUpdated<'a>]),
+    BuyoutOffered(&'a [BuyoutOffered<'a>]),
+    BuyoutVoted(&'a [BuyoutVoted<'a>]),
+    BuyoutAccepted(&'a [BuyoutAccepted]),
+    BuyoutRejected(&'a [BuyoutRejected<'a>]),
+    VaultDeposited(&'a [VaultDeposited<'a>]),
+    VaultReleased(&'a [VaultReleased<'a>]),
+    RoyaltiesUpdated(&'a [RoyaltiesUpdated<'a>]),
+    RoyaltyOwed(&'a [RoyaltyOwed<'a>]),
+    RoyaltiesDeposited(&'a [RoyaltiesDeposited<'a>]),
+    TiersUpdated(&'a [TiersUpdated<'a>]),
+    HolderTierChanged(&'a [HolderTierChanged<'a>]),
+    ShareAllowanceUpdated(&'a [ShareAllowanceUpdated<'a>]),
+    TransferRestrictionsUpdated(&'a [TransferRestrictionsUpdated<'a>]),
+    AccountAttributesUpdated(&'a [AccountAttributesUpdated<'a>]),
+}
+
+fn emit_event(event_kind: AmpleShareEventKind) {
+    let event = AmpleShareEvent {
+        standard: EVENT_STANDARD,
+        version: EVENT_STANDARD_VERSION,
+        event_kind,
+    };
+    env::log_str(&format!(
+        "EVENT_JSON:{}",
+        serde_json::to_string(&event).unwrap_or_else(|_| env::abort())
+    ));
+}
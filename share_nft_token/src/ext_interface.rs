@@ -9,13 +9,80 @@ pub const REWARD_WITHDRAW_CALLBACK_GAS: Gas = Gas(50_000_000_000_000);
 
 /// Interface to call cross contract method on
 /// NEP-141 adherent contracts.
+#[allow(dead_code)]
 #[ext_contract(ext_ft)]
 pub trait FungibleToken {
     fn ft_transfer(receiver_id: AccountId, amount: U128, memo: Option<String>);
 }
 
+/// Gas reserved by ft_transfer_call for its own execution
+/// and the ft_resolve_transfer callback
+pub const FT_TRANSFER_CALL_GAS: Gas = Gas(40_000_000_000_000);
+/// Gas amount necessary to call ft_resolve_transfer on self
+pub const FT_RESOLVE_TRANSFER_GAS: Gas = Gas(15_000_000_000_000);
+
+/// Gas amount necessary to call nft_transfer on the contract
+/// holding the underlying NFT
+pub const NFT_TRANSFER_GAS: Gas = Gas(50_000_000_000_000);
+/// Gas amount necessary to call resolve_vault_release on self
+pub const VAULT_RELEASE_CALLBACK_GAS: Gas = Gas(20_000_000_000_000);
+
+/// Interface to call cross contract method on
+/// NEP-171 adherent contracts.
+#[allow(dead_code)]
+#[ext_contract(ext_nft)]
+pub trait NonFungibleToken {
+    fn nft_transfer(
+        receiver_id: AccountId,
+        token_id: TokenId,
+        approval_id: Option<u64>,
+        memo: Option<String>,
+    );
+}
+
+/// Gas reserved by nft_transfer_call for its own execution
+/// and the resolve_position_transfer callback
+pub const NFT_TRANSFER_CALL_GAS: Gas = Gas(45_000_000_000_000);
+/// Gas amount necessary to call resolve_position_transfer on self
+pub const POSITION_RESOLVE_GAS: Gas = Gas(20_000_000_000_000);
+
+/// Interface to call nft_on_transfer on receivers of positions
+#[allow(dead_code)]
+#[ext_contract(ext_nft_receiver)]
+pub trait NonFungibleTokenReceiver {
+    fn nft_on_transfer(
+        sender_id: AccountId,
+        previous_owner_id: AccountId,
+        token_id: TokenId,
+        msg: String,
+    ) -> bool;
+}
+
+/// Gas amount necessary to call nft_on_approve on approved accounts
+pub const NFT_ON_APPROVE_GAS: Gas = Gas(30_000_000_000_000);
+
+/// Interface to call nft_on_approve on accounts approved
+/// to transfer positions
+#[allow(dead_code)]
+#[ext_contract(ext_nft_approval_receiver)]
+pub trait NonFungibleTokenApprovalReceiver {
+    fn nft_on_approve(token_id: TokenId, owner_id: AccountId, approval_id: u64, msg: String);
+}
+
 /// Interface to call callbacks on the contract itself
+#[allow(dead_code)]
 #[ext_contract(ext_self)]
 pub trait RewardsCallback {
     fn resolve_reward_transfer(receiver_id: AccountId, amount: U128);
-}
\ No newline at end of file
+    fn resolve_reserve_withdraw(amount: U128);
+    fn resolve_proceeds_transfer(amount: U128);
+    fn resolve_vault_release(receiver_id: AccountId);
+    fn resolve_position_transfer(
+        previous_owner_id: AccountId,
+        receiver_id: AccountId,
+        amount: U128,
+    ) -> bool;
+}
+/// Gas attached to the migrate call that follows
+/// the deployment of new code on upgrade
+pub const MIGRATE_GAS: Gas = Gas(100_000_000_000_000);
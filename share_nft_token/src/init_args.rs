@@ -0,0 +1,182 @@
+use crate::metadata::assert_valid_hash;
+use crate::minting::{MintAllowance, MintingPolicy};
+use crate::vault::VaultNft;
+use crate::*;
+use std::collections::HashSet;
+use near_contract_standards::fungible_token::metadata::FT_METADATA_SPEC;
+use near_contract_standards::non_fungible_token::metadata::NFT_METADATA_SPEC;
+
+/// Arguments of the contract initialization. Versioned through the
+/// `version` field so that deployment scripts written for an older
+/// version keep working when new arguments are introduced.
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(tag = "version")]
+pub enum InitArgs {
+    #[serde(rename = "1")]
+    V1(InitArgsV1),
+}
+
+impl InitArgs {
+    /// Converts arguments of any version into the latest one
+    pub fn into_latest(self) -> InitArgsV1 {
+        match self {
+            InitArgs::V1(args) => args,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct InitArgsV1 {
+    /// Receives the entire supply and becomes the contract owner
+    pub owner_id: AccountId,
+    pub total_supply: U128,
+    /// NEP-141 token in which dividends are paid
+    pub reward_token: AccountId,
+    pub token_name: String,
+    pub token_symbol: String,
+    /// Must be a data URL
+    #[serde(default)]
+    pub token_icon: Option<String>,
+    #[serde(default)]
+    pub token_reference: Option<String>,
+    /// sha256 of the document in token_reference
+    #[serde(default)]
+    pub token_reference_hash: Option<Base64VecU8>,
+    /// Defaults to 0, each token being one indivisible share
+    #[serde(default)]
+    pub decimals: Option<u8>,
+    pub nft_instance_name: String,
+    pub nft_instance_description: String,
+    pub nft_instance_media_url: String,
+    /// sha256 of the file in nft_instance_media_url
+    #[serde(default)]
+    pub nft_instance_media_hash: Option<Base64VecU8>,
+    /// (account, amount) pairs receiving the supply at initialization,
+    /// must sum to total_supply. Defaults to the entire supply to owner_id.
+    #[serde(default)]
+    pub initial_allocation: Option<Vec<(AccountId, U128)>>,
+    /// Maximum supply reachable through ft_mint. Defaults to
+    /// total_supply, which disables minting until raised by the owner.
+    #[serde(default)]
+    pub supply_cap: Option<U128>,
+    /// Allowances restricting when new shares can be minted
+    #[serde(default)]
+    pub minting_schedule: Option<Vec<MintAllowance>>,
+    /// NFT of another contract the shares represent, to be deposited
+    /// through nft_transfer_call after initialization
+    #[serde(default)]
+    pub vault_nft: Option<VaultNft>,
+}
+
+impl InitArgsV1 {
+    /// Panics if any argument would leave the contract unusable
+    pub fn assert_valid(&self) {
+        assert!(self.total_supply.0 > 0, "total_supply must be greater than 0");
+        assert!(
+            self.reward_token != env::current_account_id(),
+            "reward_token cannot be the share contract itself"
+        );
+        assert!(!self.token_name.is_empty(), "token_name cannot be empty");
+        assert!(!self.token_symbol.is_empty(), "token_symbol cannot be empty");
+        if let Some(icon) = &self.token_icon {
+            assert!(icon.starts_with("data:"), "token_icon must be a data URL");
+        }
+        assert!(
+            self.token_reference.is_some() == self.token_reference_hash.is_some(),
+            "token_reference and token_reference_hash must be provided together"
+        );
+        if let Some(reference_hash) = &self.token_reference_hash {
+            assert_valid_hash(reference_hash, "token_reference_hash");
+        }
+        assert!(
+            !self.nft_instance_name.is_empty(),
+            "nft_instance_name cannot be empty"
+        );
+        assert!(
+            !self.nft_instance_media_url.is_empty(),
+            "nft_instance_media_url cannot be empty"
+        );
+        if let Some(media_hash) = &self.nft_instance_media_hash {
+            assert_valid_hash(media_hash, "nft_instance_media_hash");
+        }
+        if let Some(allocation) = &self.initial_allocation {
+            assert!(!allocation.is_empty(), "initial_allocation cannot be empty");
+            let mut accounts = HashSet::new();
+            let allocated = allocation.iter().fold(0u128, |allocated, (account_id, amount)| {
+                assert!(amount.0 > 0, "Allocation to {} must be greater than 0", account_id);
+                assert!(
+                    accounts.insert(account_id),
+                    "{} appears more than once in initial_allocation",
+                    account_id
+                );
+                allocated
+                    .checked_add(amount.0)
+                    .expect("initial_allocation overflow")
+            });
+            assert_eq!(
+                allocated, self.total_supply.0,
+                "initial_allocation must sum to total_supply"
+            );
+        }
+        if let Some(nft) = &self.vault_nft {
+            assert!(
+                nft.nft_contract_id != env::current_account_id(),
+                "vault_nft cannot be issued by the share contract itself"
+            );
+        }
+        self.minting_policy().assert_valid(self.total_supply.0);
+        self.token_metadata().assert_valid();
+        self.nft_contract_metadata().assert_valid();
+    }
+
+    pub fn minting_policy(&self) -> MintingPolicy {
+        MintingPolicy::new(
+            self.supply_cap.unwrap_or(self.total_supply),
+            self.minting_schedule.clone().unwrap_or_default(),
+        )
+    }
+
+    /// Accounts and amounts receiving shares at initialization
+    pub fn allocation(&self) -> Vec<(AccountId, U128)> {
+        self.initial_allocation
+            .clone()
+            .unwrap_or_else(|| vec![(self.owner_id.clone(), self.total_supply)])
+    }
+
+    pub fn token_metadata(&self) -> FungibleTokenMetadata {
+        FungibleTokenMetadata {
+            spec: FT_METADATA_SPEC.to_string(),
+            name: format!("{} Token", self.token_name),
+            symbol: self.token_symbol.clone(),
+            icon: self.token_icon.clone(),
+            reference: self.token_reference.clone(),
+            reference_hash: self.token_reference_hash.clone(),
+            decimals: self.decimals.unwrap_or(0),
+        }
+    }
+
+    pub fn nft_contract_metadata(&self) -> NFTContractMetadata {
+        NFTContractMetadata {
+            spec: NFT_METADATA_SPEC.to_string(),
+            name: format!("{} NFT", self.token_name),
+            symbol: self.token_symbol.clone(),
+            icon: self.token_icon.clone(),
+            base_uri: None,
+            reference: self.token_reference.clone(),
+            reference_hash: self.token_reference_hash.clone(),
+        }
+    }
+
+    pub fn nft_instance_metadata(&self) -> NftInstanceData {
+        NftInstanceData {
+            title: self.nft_instance_name.clone(),
+            description: self.nft_instance_description.clone(),
+            media: self.nft_instance_media_url.clone(),
+            media_hash: self.nft_instance_media_hash.clone(),
+            reference: self.token_reference.clone(),
+            reference_hash: self.token_reference_hash.clone(),
+        }
+    }
+}
@@ -19,16 +19,15 @@
 
 use near_contract_standards::fungible_token::core::FungibleTokenCore;
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
-use near_sdk::collections::{LazyOption, LookupMap};
-use near_sdk::json_types::U128;
+use near_sdk::collections::{LazyOption, LookupMap, UnorderedSet, Vector};
+use near_sdk::json_types::{Base64VecU8, U128};
 #[allow(unused_imports)]
 use near_sdk::serde::{self, Deserialize, Serialize};
 use near_sdk::{
     env, log, near_bindgen, utils::assert_one_yocto, AccountId, Balance, BorshStorageKey, Gas,
-    PanicOnDefault, Promise, PromiseOrValue,
+    PanicOnDefault, Promise, PromiseOrValue, StorageUsage,
 };
 
-use near_contract_standards;
 use near_contract_standards::fungible_token::events::{FtBurn, FtMint};
 use near_contract_standards::fungible_token::metadata::{
     FungibleTokenMetadata, FungibleTokenMetadataProvider,
@@ -45,17 +44,60 @@ use near_contract_standards::non_fungible_token::metadata::{
 use near_contract_standards::non_fungible_token::{Token, TokenId};
 
 mod actions;
+mod allowances;
+mod approvals;
+mod auction;
+mod balances;
+mod buyback;
+mod buyout;
+mod compliance;
+mod events;
 mod ext_interface;
+mod init_args;
+mod metadata;
+mod migration;
+mod minting;
+mod pause;
+mod roles;
+mod royalties;
 mod rps_manager;
-
+mod sale;
+mod split;
+mod svg;
+mod tiers;
+mod vault;
+
+use allowances::ShareAllowances;
+use approvals::PositionApprovals;
+use auction::{Auction, AuctionBid};
+use balances::BalanceChange;
+use buyback::Buyback;
+use buyout::{Buyout, BuyoutPolicy};
+use compliance::{AccountAttributes, TransferRestrictions};
+use init_args::InitArgs;
+use metadata::MetadataChange;
+use minting::MintingPolicy;
+use pause::{PauseFeature, PauseFlags};
+use roles::Role;
+use royalties::RoyaltyConfig;
 use rps_manager::RpsManager;
-
-/// Exact byte size of data stored for each user that registers
-/// in the contract. Contract takes 154 bytes for information plus
-/// 2 bytes for each UTF8 char in account ID (LookupMaps in
-/// ft_functionality and accounts_rps), which amounts to
-/// 282 bytes. Consider 300 to give a 10% wiggle room.
-const STORAGE_BYTES_PER_USER: u64 = 400;
+use split::Split;
+use sale::Sale;
+use svg::SvgMediaConfig;
+use tiers::HolderTier;
+use vault::Vault;
+
+/// Byte size of data stored for each user that registers in the
+/// contract: its balance, rewards and storage deposit, and once it
+/// holds shares its entry in holders, its tier, approval counter,
+/// latest purchase, rights snapshot and subscription, and buyout
+/// snapshot and vote. Amounts to 1979 bytes for the longest account
+/// ID, as measured by test_storage_bytes_per_user, rounded up to 2100.
+const STORAGE_BYTES_PER_USER: u64 = 2100;
+
+/// STORAGE_BYTES_PER_USER of v1.0.0, paid by accounts registered
+/// before their storage deposit was recorded
+const LEGACY_STORAGE_BYTES_PER_USER: u64 = 400;
 
 #[near_bindgen]
 #[derive(PanicOnDefault, BorshDeserialize, BorshSerialize)]
@@ -74,6 +116,11 @@ pub struct Contract {
     /// distribution algorithm](http://batog.info/papers/scalable-reward-distribution.pdf)
     pub contract_rps_token: U128,
     pub contract_rps_near: U128,
+    /// Rewards received but not yet reflected in contract_rps,
+    /// since they could not be evenly divided among the supply.
+    /// Carried over to the next distribution.
+    pub undistributed_rewards_token: U128,
+    pub undistributed_rewards_near: U128,
     /// Revenue per share claimed by each individual account
     /// up to its latest interaction. part of the [scalable reward
     /// distribution algorithm](http://batog.info/papers/scalable-reward-distribution.pdf)
@@ -85,6 +132,88 @@ pub struct Contract {
     /// Metadata for the artwork's NFT, includes name,
     /// image and other relevant data.
     pub nft_instance_metadata: LazyOption<NftInstanceData>,
+    /// Account that holds every administrative role. Can only
+    /// be changed through transfer_ownership.
+    pub owner_id: AccountId,
+    /// Accounts explicitly granted each administrative role
+    pub role_members: LookupMap<Role, UnorderedSet<AccountId>>,
+    /// Features currently halted by a pauser
+    pub pause_flags: PauseFlags,
+    /// Audit log of every metadata update since initialization
+    pub metadata_history: Vector<MetadataChange>,
+    /// Supply cap and schedule restricting ft_mint
+    pub minting_policy: MintingPolicy,
+    /// Accounts currently holding shares, except the contract's own
+    pub holders: UnorderedSet<AccountId>,
+    /// Shares of holders not yet in holders, as left by the v1.0.0
+    /// migration until they are indexed
+    pub unindexed_supply: U128,
+    /// Reward tokens deposited by the issuer to fund corporate
+    /// actions, kept apart from dividends
+    pub reserve_token: U128,
+    /// Split currently rescaling holders, if any
+    pub split: Option<Split>,
+    /// Number of splits ever started
+    pub splits_count: u64,
+    /// Open offer to buy back shares, if any
+    pub buyback: Option<Buyback>,
+    /// Primary offering currently running, if any
+    pub sale: Option<Sale>,
+    /// Number of sales ever created, used as id of the next one
+    pub sales_count: u64,
+    /// Id of the latest sale in which each account bought shares,
+    /// with the shares it bought
+    pub sale_purchases: LookupMap<AccountId, (u64, U128)>,
+    /// Descending price auction currently running, if any
+    pub auction: Option<Auction>,
+    /// Number of auctions ever created, used as id of the next one
+    pub auctions_count: u64,
+    /// Bid of each account in each auction
+    pub auction_bids: LookupMap<(u64, AccountId), AuctionBid>,
+    /// Bidders of each auction, by order of their first bid
+    pub auction_bidders: LookupMap<(u64, u64), AccountId>,
+    /// Id of the latest rights offering during which the balance of
+    /// each account changed, with its balance on creation of the
+    /// offering, recorded when it first changes during the rights phase
+    pub rights_snapshots: LookupMap<AccountId, (u64, U128)>,
+    /// Id of the latest rights offering to which each account
+    /// subscribed, with the shares it subscribed
+    pub rights_subscriptions: LookupMap<AccountId, (u64, U128)>,
+    /// Rules for accepting buyouts, buyouts are disabled when unset
+    pub buyout_policy: Option<BuyoutPolicy>,
+    /// Pending or accepted offer to buy the whole artwork, if any
+    pub buyout: Option<Buyout>,
+    /// Number of buyout offers ever made, used as id of the next one
+    pub buyouts_count: u64,
+    /// Id of the latest buyout offer during which the balance of each
+    /// account changed, with its balance when the offer was made,
+    /// recorded when it first changes during the vote
+    pub buyout_snapshots: LookupMap<AccountId, (u64, U128)>,
+    /// Id of the latest buyout offer each account voted on, with its vote
+    pub buyout_votes: LookupMap<AccountId, (u64, bool)>,
+    /// Underlying NFT of another contract, when the shares fractionalize one
+    pub vault: Option<Vault>,
+    /// Accounts approved by each holder to transfer its position NFT
+    pub position_approvals: LookupMap<AccountId, PositionApprovals>,
+    /// Royalties paid on sales of positions, none when unset
+    pub royalties: Option<RoyaltyConfig>,
+    /// Settings of the SVG rendered on-chain as media of each holder's
+    /// NFT, the media of nft_instance_metadata is used when unset
+    pub svg_media: LazyOption<SvgMediaConfig>,
+    /// Tiers of holders, sorted by decreasing threshold
+    pub tiers: LazyOption<Vec<HolderTier>>,
+    /// Name of the tier of each holder on its latest balance change
+    pub holder_tiers: LookupMap<AccountId, String>,
+    /// Storage deposit paid on registration of each account, refunded
+    /// by storage_unregister. Missing for accounts registered before
+    /// v1.1.0, which paid for LEGACY_STORAGE_BYTES_PER_USER bytes.
+    pub storage_deposits: LookupMap<AccountId, U128>,
+    /// Shares of each holder that each spender can transfer
+    pub share_allowances: LookupMap<AccountId, ShareAllowances>,
+    /// Rules restricting transfers of shares between holders
+    pub transfer_restrictions: TransferRestrictions,
+    /// Compliance attributes of each account, default when missing
+    pub account_attributes: LookupMap<AccountId, AccountAttributes>,
 }
 
 /// NFT data to display for owners of shares
@@ -94,7 +223,13 @@ pub struct NftInstanceData {
     pub title: String,
     pub description: String,
     pub media: String,
-    pub reference: String,
+    /// sha256 of the file in media
+    #[serde(default)]
+    pub media_hash: Option<Base64VecU8>,
+    pub reference: Option<String>,
+    /// sha256 of the document in reference
+    #[serde(default)]
+    pub reference_hash: Option<Base64VecU8>,
 }
 
 #[derive(BorshDeserialize, BorshSerialize, BorshStorageKey)]
@@ -104,88 +239,110 @@ pub enum StorageKey {
     FungibleTokenMetadata,
     NonFungibleTokenMetadata,
     InstanceNonFungibleTokenMetadata,
+    RoleMembersMap,
+    RoleMembers { role: Role },
+    MetadataHistory,
+    Holders,
+    SalePurchases,
+    AuctionBids,
+    AuctionBidders,
+    RightsSnapshots,
+    RightsSubscriptions,
+    BuyoutSnapshots,
+    BuyoutVotes,
+    PositionApprovals,
+    SvgMedia,
+    Tiers,
+    HolderTiers,
+    StorageDeposits,
+    ShareAllowances,
+    AccountAttributes,
 }
 
 #[allow(dead_code)]
 #[near_bindgen]
 impl Contract {
-    /// Initializes the contract and sends entire initial balance
-    /// to owner.
+    /// Initializes the contract and distributes the entire supply
+    /// according to the initial allocation, which defaults to all of
+    /// it to owner. owner_id also becomes the contract owner, holding
+    /// every administrative role.
     #[init]
-    pub fn new(
-        owner_id: AccountId,
-        total_supply: U128,
-        reward_token: AccountId,
-        token_name: String,
-        token_symbol: String,
-        token_icon: Option<String>,
-        token_reference: String,
-        nft_instance_name: String,
-        nft_instance_description: String,
-        nft_instance_media_url: String,
-    ) -> Self {
+    pub fn new(args: InitArgs) -> Self {
         assert!(!env::state_exists(), "Already initialized");
-        let token_metadata = FungibleTokenMetadata {
-            spec: "ft-1.0.0".to_string(),
-            name: format!("{} Token", token_name.clone()),
-            symbol: token_symbol.clone(),
-            icon: token_icon.clone(),
-            reference: Some(token_reference.clone()),
-            reference_hash: None,
-            decimals: 0,
-        };
-        let nft_contract_metadata = NFTContractMetadata {
-            spec: "nft-1.0.0".to_string(),
-            name: format!("{} NFT", token_name.clone()),
-            symbol: token_symbol,
-            icon: token_icon,
-            base_uri: None,
-            reference: Some(token_reference.clone()),
-            reference_hash: None,
-        };
-        let nft_instance_metadata = NftInstanceData {
-            title: nft_instance_name,
-            description: nft_instance_description,
-            media: nft_instance_media_url,
-            reference: token_reference,
-        };
+        let args = args.into_latest();
+        args.assert_valid();
         let mut this = Self {
             ft_functionality: FungibleToken::new(StorageKey::FungibleToken),
-            reward_token,
+            reward_token: args.reward_token.clone(),
             reward_tokens_all_time_count_token: U128(0),
             reward_tokens_all_time_count_near: U128(0),
             contract_rps_token: U128(0),
             contract_rps_near: U128(0),
+            undistributed_rewards_token: U128(0),
+            undistributed_rewards_near: U128(0),
             accounts_rps: LookupMap::new(StorageKey::AccontRps),
             token_metadata: LazyOption::new(
                 StorageKey::FungibleTokenMetadata,
-                Some(&token_metadata),
+                Some(&args.token_metadata()),
             ),
             nft_contract_metadata: LazyOption::new(
                 StorageKey::NonFungibleTokenMetadata,
-                Some(&nft_contract_metadata),
+                Some(&args.nft_contract_metadata()),
             ),
             nft_instance_metadata: LazyOption::new(
                 StorageKey::InstanceNonFungibleTokenMetadata,
-                Some(&nft_instance_metadata),
+                Some(&args.nft_instance_metadata()),
             ),
+            owner_id: args.owner_id.clone(),
+            role_members: LookupMap::new(StorageKey::RoleMembersMap),
+            pause_flags: PauseFlags::default(),
+            metadata_history: Vector::new(StorageKey::MetadataHistory),
+            minting_policy: args.minting_policy(),
+            holders: UnorderedSet::new(StorageKey::Holders),
+            unindexed_supply: U128(0),
+            reserve_token: U128(0),
+            split: None,
+            splits_count: 0,
+            buyback: None,
+            sale: None,
+            sales_count: 0,
+            sale_purchases: LookupMap::new(StorageKey::SalePurchases),
+            auction: None,
+            auctions_count: 0,
+            auction_bids: LookupMap::new(StorageKey::AuctionBids),
+            auction_bidders: LookupMap::new(StorageKey::AuctionBidders),
+            rights_snapshots: LookupMap::new(StorageKey::RightsSnapshots),
+            rights_subscriptions: LookupMap::new(StorageKey::RightsSubscriptions),
+            buyout_policy: None,
+            buyout: None,
+            buyouts_count: 0,
+            buyout_snapshots: LookupMap::new(StorageKey::BuyoutSnapshots),
+            buyout_votes: LookupMap::new(StorageKey::BuyoutVotes),
+            vault: args.vault_nft.clone().map(Vault::new),
+            position_approvals: LookupMap::new(StorageKey::PositionApprovals),
+            royalties: None,
+            svg_media: LazyOption::new(StorageKey::SvgMedia, None),
+            tiers: LazyOption::new(StorageKey::Tiers, None),
+            holder_tiers: LookupMap::new(StorageKey::HolderTiers),
+            storage_deposits: LookupMap::new(StorageKey::StorageDeposits),
+            share_allowances: LookupMap::new(StorageKey::ShareAllowances),
+            transfer_restrictions: TransferRestrictions::default(),
+            account_attributes: LookupMap::new(StorageKey::AccountAttributes),
         };
-        this.update_user_rps(&owner_id);
-        this.ft_functionality.internal_register_account(&owner_id);
-        this.ft_functionality
-            .internal_deposit(&owner_id, total_supply.into());
-        FtMint {
-            owner_id: &owner_id,
-            amount: &total_supply,
-            memo: Some("Single Mint event on creation"),
+        Contract::write_state_version();
+        this.internal_register_escrow();
+        let allocation = args.allocation();
+        for (account_id, _) in allocation.iter() {
+            this.internal_register_account(account_id, 0);
         }
-        .emit();
-        NftMint {
-            owner_id: &owner_id,
-            token_ids: &["0"],
-            memo: Some("received first shares"),
-        }
-        .emit();
+        this.internal_change_balances(
+            &allocation
+                .iter()
+                .map(|(account_id, amount)| {
+                    BalanceChange::mint(account_id, amount.0, Some("Initial allocation on creation"))
+                })
+                .collect::<Vec<_>>(),
+        );
 
         this
     }
@@ -198,6 +355,9 @@ impl Contract {
     /// if user has rewards to receive, credit them to user's
     /// RpsManager and update's account_rps to contract_rps' value
     pub fn update_user_rps(&mut self, account_id: &AccountId) {
+        self.internal_index_holder(account_id);
+        self.internal_checkpoint_rights(account_id);
+        self.internal_checkpoint_buyout(account_id);
         let mut user_rps = self.accounts_rps.get(account_id).unwrap_or(RpsManager::new(
             self.contract_rps_token.0,
             self.contract_rps_near.0,
@@ -238,50 +398,171 @@ impl Contract {
         user_rps.rewards_balance_token = U128(user_rps.rewards_balance_token.0 + amount);
         self.accounts_rps.insert(account_id, &user_rps);
     }
+}
+
+impl Contract {
+    /// Distributes reward tokens among current holders by increasing
+    /// contract_rps_token. Since the increase is relative to the current
+    /// supply, rewards already accrued are never affected by later
+    /// changes of supply.
+    pub fn distribute_rewards_token(&mut self, amount: u128) {
+        self.reward_tokens_all_time_count_token =
+            U128(self.reward_tokens_all_time_count_token.0 + amount);
+        let (rps_increase, undistributed) = self.compute_rps_increase(
+            self.undistributed_rewards_token.0 + amount,
+        );
+        self.contract_rps_token = U128(self.contract_rps_token.0 + rps_increase);
+        self.undistributed_rewards_token = U128(undistributed);
+    }
+
+    /// Distributes NEAR among current holders by increasing
+    /// contract_rps_near, see distribute_rewards_token.
+    pub fn distribute_rewards_near(&mut self, amount: u128) {
+        self.reward_tokens_all_time_count_near =
+            U128(self.reward_tokens_all_time_count_near.0 + amount);
+        let (rps_increase, undistributed) = self.compute_rps_increase(
+            self.undistributed_rewards_near.0 + amount,
+        );
+        self.contract_rps_near = U128(self.contract_rps_near.0 + rps_increase);
+        self.undistributed_rewards_near = U128(undistributed);
+    }
 
-    /// This method must be called every time a user transfers shares.
-    /// If user has no more shares, emits an event representing the burn
-    /// of their NFT
-    pub fn emit_sender_nft_events(&self, account_id: &AccountId) {
-        if self.ft_functionality.ft_balance_of(account_id.clone()) == U128(0) {
-            NftBurn {
-                owner_id: account_id,
-                token_ids: &["0"],
-                authorized_id: None,
-                memo: Some("transferred all shares"),
-            }
-            .emit()
+    /// Splits amount into the rps increase for the current supply
+    /// and the remainder that cannot be distributed yet
+    fn compute_rps_increase(&self, amount: u128) -> (u128, u128) {
+        let total_supply = self.ft_functionality.ft_total_supply().0;
+        if total_supply == 0 {
+            return (0, amount);
         }
+        (amount / total_supply, amount % total_supply)
     }
 
-    /// This method must be called every time a user receives shares.
-    /// If user previously had no shares, emits an event representing
-    /// the mint of the NFT representation of shares
-    pub fn emit_receiver_nft_events(&self, account_id: &AccountId) {
-        if self.ft_functionality.ft_balance_of(account_id.clone()) == U128(0) {
-            NftMint {
-                owner_id: account_id,
-                token_ids: &["0"],
-                memo: Some("received first shares"),
-            }
-            .emit()
+    /// Must be called every time the balance of account_id changes,
+    /// keeps it in holders only while it holds shares and records
+    /// its tier. Shares escrowed by the contract have no holder.
+    pub fn internal_update_holder(&mut self, account_id: &AccountId) {
+        if *account_id == env::current_account_id() {
+            return;
         }
+        if self.ft_functionality.accounts.get(account_id).unwrap_or(0) > 0 {
+            self.holders.insert(account_id);
+        } else {
+            self.holders.remove(account_id);
+        }
+        self.internal_update_tier(account_id);
     }
-}
 
-impl Contract {
-    fn on_tokens_burned(&mut self, account_id: AccountId, amount: u128) {
-        FtBurn {
-            owner_id: &account_id,
-            amount: &U128(amount),
-            memo: None,
+    /// Adds account_id to holders if its shares date back to v1.0.0
+    /// and were never indexed. Must be called before its balance
+    /// changes, so that its shares are counted out of unindexed_supply.
+    pub fn internal_index_holder(&mut self, account_id: &AccountId) {
+        if self.unindexed_supply.0 == 0
+            || *account_id == env::current_account_id()
+            || self.holders.contains(account_id)
+        {
+            return;
+        }
+        let balance = self.ft_functionality.accounts.get(account_id).unwrap_or(0);
+        if balance > 0 {
+            self.holders.insert(account_id);
+            self.unindexed_supply = U128(self.unindexed_supply.0 - balance);
+        }
+    }
+
+    /// Panics until every holder left by the v1.0.0 migration is indexed
+    pub fn assert_holders_indexed(&self) {
+        assert_eq!(
+            self.unindexed_supply.0, 0,
+            "Every holder must be indexed first, see index_holders"
+        );
+    }
+
+    /// Registers the contract's own account, which holds shares
+    /// transferred to the contract until they are burned
+    pub fn internal_register_escrow(&mut self) {
+        let escrow = env::current_account_id();
+        if !self.accounts_rps.contains_key(&escrow) {
+            self.internal_register_account(&escrow, 0);
+        }
+    }
+
+    /// Panics if receiver_id is the contract's own account. Shares only
+    /// reach the contract through ft_transfer_call with a msg it handles,
+    /// so its balance only holds shares escrowed by sales and auctions.
+    /// Must be called by every other path transferring shares.
+    pub fn assert_not_escrow(&self, receiver_id: &AccountId) {
+        assert!(
+            *receiver_id != env::current_account_id(),
+            "Shares can only be sent to the contract through ft_transfer_call"
+        );
+    }
+
+    /// Rewards accrued by shares while held by the contract belong to
+    /// no one, hands them back to the next distribution
+    pub fn internal_redistribute_escrow_rewards(&mut self) {
+        let escrow = env::current_account_id();
+        self.update_user_rps(&escrow);
+        let mut escrow_rps = self.accounts_rps.get(&escrow).unwrap();
+        let (rewards_token, rewards_near) = escrow_rps.withdraw_rewards();
+        self.accounts_rps.insert(&escrow, &escrow_rps);
+        self.undistributed_rewards_token = U128(self.undistributed_rewards_token.0 + rewards_token.0);
+        self.undistributed_rewards_near = U128(self.undistributed_rewards_near.0 + rewards_near.0);
+    }
+
+    /// Transfers shares held by the contract to receiver_id,
+    /// settling the rewards of both
+    pub fn internal_release_from_escrow(&mut self, receiver_id: &AccountId, amount: u128, memo: &str) {
+        let escrow = env::current_account_id();
+        self.internal_redistribute_escrow_rewards();
+        self.internal_transfer_shares(&escrow, receiver_id, amount, Some(memo.to_string()));
+    }
+
+    /// Registers account_id in the contract, storage must be
+    /// paid for by the caller. deposit is what storage_unregister
+    /// refunds, 0 for registrations paid for by the contract.
+    pub fn internal_register_account(&mut self, account_id: &AccountId, deposit: Balance) {
+        self.ft_functionality.internal_register_account(account_id);
+        self.storage_deposits.insert(account_id, &U128(deposit));
+        self.update_user_rps(account_id);
+    }
+
+    /// Charges payer for the storage used since initial_storage_usage
+    /// out of the attached deposit and refunds the remainder.
+    pub fn charge_storage_deposit(&self, initial_storage_usage: StorageUsage, payer: &AccountId) {
+        let storage_used = env::storage_usage().saturating_sub(initial_storage_usage);
+        self.charge_storage_bytes(storage_used, payer);
+    }
+
+    /// Charges payer for storage_used bytes out of the attached
+    /// deposit and refunds the remainder. Used for growth of the
+    /// contract's own state, which is only written after the call.
+    pub fn charge_storage_bytes(&self, storage_used: StorageUsage, payer: &AccountId) {
+        let storage_cost = Balance::from(storage_used) * env::storage_byte_cost();
+        let attached_deposit = env::attached_deposit();
+        assert!(
+            attached_deposit >= storage_cost,
+            "Must attach {} yoctoNEAR to cover storage",
+            storage_cost
+        );
+        let refund = attached_deposit - storage_cost;
+        if refund > 0 {
+            Promise::new(payer.clone()).transfer(refund);
+        }
+    }
+
+    /// Refunds owner for the storage released since initial_storage_usage
+    pub fn refund_storage_release(&self, initial_storage_usage: StorageUsage, owner: &AccountId) {
+        let storage_released = initial_storage_usage.saturating_sub(env::storage_usage());
+        if storage_released > 0 {
+            Promise::new(owner.clone())
+                .transfer(Balance::from(storage_released) * env::storage_byte_cost());
         }
-        .emit();
     }
 }
 
 #[cfg(test)]
 mod tests {
+    #![allow(unused_imports)]
     pub use near_sdk::collections::LazyOption;
     pub use near_sdk::mock::VmAction;
     pub use near_sdk::serde_json::{self, json};
@@ -306,6 +587,14 @@ mod tests {
     pub const OWNER_ACCOUNT: &str = "owner.testnet";
     /// Mocked regular user account id
     pub const USER_ACCOUNT: &str = "user.testnet";
+    /// Mocked account not registered in the contract
+    pub const NEW_USER_ACCOUNT: &str = "new_user.testnet";
+    /// Mocked account holding the admin role
+    pub const ADMIN_ACCOUNT: &str = "admin.testnet";
+    /// Mocked account holding the pauser role
+    pub const PAUSER_ACCOUNT: &str = "pauser.testnet";
+    /// Mocked account holding the metadata_manager role
+    pub const METADATA_MANAGER_ACCOUNT: &str = "metadata.testnet";
     /// Total token supply to use in tests
     pub const TOKEN_SUPPLY: U128 = U128(100000);
 
@@ -346,6 +635,23 @@ mod tests {
         let hash3 = env::keccak256(&hash2[..]);
         let hash4 = env::keccak256(&hash3[..]);
         let hash5 = env::keccak256(&hash4[..]);
+        let hash6 = env::keccak256(&hash5[..]);
+        let hash7 = env::keccak256(&hash6[..]);
+        let hash8 = env::keccak256(&hash7[..]);
+        let hash9 = env::keccak256(&hash8[..]);
+        let hash10 = env::keccak256(&hash9[..]);
+        let hash11 = env::keccak256(&hash10[..]);
+        let hash12 = env::keccak256(&hash11[..]);
+        let hash13 = env::keccak256(&hash12[..]);
+        let hash14 = env::keccak256(&hash13[..]);
+        let hash15 = env::keccak256(&hash14[..]);
+        let hash16 = env::keccak256(&hash15[..]);
+        let hash17 = env::keccak256(&hash16[..]);
+        let hash18 = env::keccak256(&hash17[..]);
+        let hash19 = env::keccak256(&hash18[..]);
+        let hash20 = env::keccak256(&hash19[..]);
+        let hash21 = env::keccak256(&hash20[..]);
+        let hash22 = env::keccak256(&hash21[..]);
         let token_metadata = FungibleTokenMetadata {
             spec: "ft-1.0.0".to_string(),
             name: format!("{}-token", "token_name"),
@@ -368,7 +674,9 @@ mod tests {
             title: "nft_instance_name".to_string(),
             description: "nft_instance_description".to_string(),
             media: "nft_instance_media_url".to_string(),
-            reference: "token_reference".to_string(),
+            media_hash: None,
+            reference: Some("token_reference".to_string()),
+            reference_hash: None,
         };
         let mut this = Contract {
             ft_functionality: FungibleToken::new(hash1),
@@ -377,16 +685,55 @@ mod tests {
             reward_tokens_all_time_count_near: U128(0),
             contract_rps_token: U128(0),
             contract_rps_near: U128(0),
+            undistributed_rewards_token: U128(0),
+            undistributed_rewards_near: U128(0),
             accounts_rps: LookupMap::new(hash2),
             token_metadata: LazyOption::new(hash3, Some(&token_metadata)),
             nft_contract_metadata: LazyOption::new(hash4, Some(&nft_contract_metadata)),
             nft_instance_metadata: LazyOption::new(hash5, Some(&nft_instance_metadata)),
+            owner_id: OWNER_ACCOUNT.parse().unwrap(),
+            role_members: LookupMap::new(hash6),
+            pause_flags: PauseFlags::default(),
+            metadata_history: Vector::new(hash7),
+            minting_policy: MintingPolicy::new(TOKEN_SUPPLY, vec![]),
+            holders: UnorderedSet::new(hash8),
+            unindexed_supply: U128(0),
+            reserve_token: U128(0),
+            split: None,
+            splits_count: 0,
+            buyback: None,
+            sale: None,
+            sales_count: 0,
+            sale_purchases: LookupMap::new(hash9),
+            auction: None,
+            auctions_count: 0,
+            auction_bids: LookupMap::new(hash10),
+            auction_bidders: LookupMap::new(hash11),
+            rights_snapshots: LookupMap::new(hash12),
+            rights_subscriptions: LookupMap::new(hash13),
+            buyout_policy: None,
+            buyout: None,
+            buyouts_count: 0,
+            buyout_snapshots: LookupMap::new(hash14),
+            buyout_votes: LookupMap::new(hash15),
+            vault: None,
+            position_approvals: LookupMap::new(hash16),
+            royalties: None,
+            svg_media: LazyOption::new(hash17, None),
+            tiers: LazyOption::new(hash18, None),
+            holder_tiers: LookupMap::new(hash19),
+            storage_deposits: LookupMap::new(hash20),
+            share_allowances: LookupMap::new(hash21),
+            transfer_restrictions: TransferRestrictions::default(),
+            account_attributes: LookupMap::new(hash22),
         };
+        this.internal_register_escrow();
         this.update_user_rps(&OWNER_ACCOUNT.parse().unwrap());
         this.ft_functionality
             .internal_register_account(&OWNER_ACCOUNT.parse().unwrap());
         this.ft_functionality
             .internal_deposit(&OWNER_ACCOUNT.parse().unwrap(), TOKEN_SUPPLY.into());
+        this.internal_update_holder(&OWNER_ACCOUNT.parse().unwrap());
         this
     }
 
@@ -402,14 +749,70 @@ mod tests {
         contract
             .ft_functionality
             .internal_deposit(user, token_balance);
+        contract.internal_update_holder(user);
         let mut internal_rps = contract.accounts_rps.get(user).unwrap();
         internal_rps.rewards_balance_token = U128(rewards_balance_token);
         internal_rps.rewards_balance_near = U128(rewards_balance_near);
         contract.accounts_rps.insert(user, &internal_rps);
     }
 
+    pub fn grant_role(contract: &mut Contract, account: &str, role: Role) {
+        let mut members = contract
+            .role_members
+            .get(&role)
+            .unwrap_or_else(|| UnorderedSet::new(StorageKey::RoleMembers { role }));
+        members.insert(&account.parse().unwrap());
+        contract.role_members.insert(&role, &members);
+    }
+
+    /// Builds init args in their JSON form, with fields in
+    /// overrides replacing the defaults
+    pub fn init_args(overrides: serde_json::Value) -> InitArgs {
+        let mut args = json!({
+            "version": "1",
+            "owner_id": OWNER_ACCOUNT,
+            "total_supply": TOKEN_SUPPLY,
+            "reward_token": REWARDS_TOKEN_ACCOUNT,
+            "token_name": "NAME_HERE",
+            "token_symbol": "SYMBOL_HERE",
+            "token_icon": "data:image/svg+xml,ICON_HERE",
+            "token_reference": "REFERENCE_HERE",
+            "token_reference_hash": "AQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQE=",
+            "nft_instance_name": "NFT_NAME_HERE",
+            "nft_instance_description": "NFT_DESC_HERE",
+            "nft_instance_media_url": "NFT_MEDIA_HERE",
+        });
+        for (key, value) in overrides.as_object().unwrap() {
+            args[key] = value.clone();
+        }
+        serde_json::from_value(args).unwrap()
+    }
+
     #[rstest]
-    fn test_new() {
+    /// Test new method
+    /// ASSERT:
+    /// (1) Supply must be positive
+    #[should_panic = "total_supply must be greater than 0"]
+    #[case(json!({"total_supply": "0"}))]
+    /// (2) Reward token cannot be the contract itself
+    #[should_panic = "reward_token cannot be the share contract itself"]
+    #[case(json!({"reward_token": CONTRACT_ACCOUNT}))]
+    /// (3) Icon must be a data URL
+    #[should_panic = "token_icon must be a data URL"]
+    #[case(json!({"token_icon": "https://icon.svg"}))]
+    /// (4) Reference and its hash go together
+    #[should_panic = "token_reference and token_reference_hash must be provided together"]
+    #[case(json!({"token_reference_hash": null}))]
+    /// (5) Hashes must be sha256 digests
+    #[should_panic = "nft_instance_media_hash has to be 32 bytes"]
+    #[case(json!({"nft_instance_media_hash": "AQEB"}))]
+    /// (6) Text fields cannot be empty
+    #[should_panic = "token_symbol cannot be empty"]
+    #[case(json!({"token_symbol": ""}))]
+    /// (7) Entire supply goes to owner, optional fields are applied
+    #[case(json!({}))]
+    #[case(json!({"decimals": 8, "token_icon": null, "token_reference": null, "token_reference_hash": null}))]
+    fn test_new(#[case] overrides: serde_json::Value) {
         let context = get_context(
             vec![],
             0,
@@ -419,19 +822,9 @@ mod tests {
             Gas(300u64 * 10u64.pow(12)),
         );
         testing_env!(context);
+        let decimals = overrides["decimals"].as_u64().unwrap_or(0) as u8;
 
-        let contract = Contract::new(
-            OWNER_ACCOUNT.parse().unwrap(),
-            TOKEN_SUPPLY,
-            REWARDS_TOKEN_ACCOUNT.parse().unwrap(),
-            "NAME_HERE".to_string(),
-            "SYMBOL_HERE".to_string(),
-            Some("ICON_HERE".to_string()),
-            "REFERENCE_HERE".to_string(),
-            "NFT_NAME_HERE".to_string(),
-            "NFT_DESC_HERE".to_string(),
-            "NFT_MEDIA_HERE".to_string(),
-        );
+        let contract = Contract::new(init_args(overrides));
 
         assert_eq!(
             TOKEN_SUPPLY,
@@ -439,5 +832,59 @@ mod tests {
                 .ft_functionality
                 .ft_balance_of(OWNER_ACCOUNT.parse().unwrap())
         );
+        assert_eq!(contract.view_owner(), OWNER_ACCOUNT.parse().unwrap());
+        let token_metadata = contract.ft_metadata();
+        assert_eq!(token_metadata.decimals, decimals);
+        assert_eq!(
+            token_metadata.reference_hash,
+            contract.nft_instance_metadata.get().unwrap().reference_hash
+        );
+    }
+
+    #[rstest]
+    /// Test new method with an initial allocation
+    /// ASSERT:
+    /// (1) Allocations must sum to total supply
+    #[should_panic = "initial_allocation must sum to total_supply"]
+    #[case(json!([[USER_ACCOUNT, "100"], [ADMIN_ACCOUNT, "100"]]))]
+    /// (2) Accounts cannot be repeated
+    #[should_panic = "user.testnet appears more than once in initial_allocation"]
+    #[case(json!([[USER_ACCOUNT, "50000"], [USER_ACCOUNT, "50000"]]))]
+    /// (3) Allocations must be positive
+    #[should_panic = "Allocation to admin.testnet must be greater than 0"]
+    #[case(json!([[USER_ACCOUNT, "100000"], [ADMIN_ACCOUNT, "0"]]))]
+    /// (4) Every account is registered and credited, owner does
+    ///     not need to be part of the allocation, a single event of
+    ///     each kind is emitted
+    #[case(json!([[USER_ACCOUNT, "60000"], [ADMIN_ACCOUNT, "40000"]]))]
+    fn test_new_initial_allocation(#[case] allocation: serde_json::Value) {
+        let context = get_context(
+            vec![],
+            0,
+            0,
+            OWNER_ACCOUNT.parse().unwrap(),
+            0,
+            Gas(300u64 * 10u64.pow(12)),
+        );
+        testing_env!(context);
+
+        let contract = Contract::new(init_args(json!({ "initial_allocation": allocation })));
+
+        assert_eq!(contract.ft_balance_of(USER_ACCOUNT.parse().unwrap()), U128(60000));
+        assert_eq!(contract.ft_balance_of(ADMIN_ACCOUNT.parse().unwrap()), U128(40000));
+        assert_eq!(contract.ft_balance_of(OWNER_ACCOUNT.parse().unwrap()), U128(0));
+        assert!(contract
+            .accounts_rps
+            .contains_key(&USER_ACCOUNT.parse().unwrap()));
+        assert!(contract
+            .accounts_rps
+            .contains_key(&ADMIN_ACCOUNT.parse().unwrap()));
+        assert_eq!(contract.view_owner(), OWNER_ACCOUNT.parse().unwrap());
+        let logs = get_logs();
+        assert_eq!(logs.len(), 2);
+        assert!(logs[0].contains(r#""event":"ft_mint""#));
+        assert!(logs[0].contains(USER_ACCOUNT) && logs[0].contains(ADMIN_ACCOUNT));
+        assert!(logs[1].contains(r#""event":"nft_mint""#));
+        assert!(logs[1].contains(USER_ACCOUNT) && logs[1].contains(ADMIN_ACCOUNT));
     }
 }
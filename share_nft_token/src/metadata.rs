@@ -0,0 +1,71 @@
+use crate::*;
+use near_sdk::json_types::{Base64VecU8, U64};
+
+/// Value of one of the contract's metadata objects after an update
+#[derive(BorshDeserialize, BorshSerialize, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(tag = "kind", content = "metadata")]
+#[serde(rename_all = "snake_case")]
+pub enum MetadataSnapshot {
+    Token(FungibleTokenMetadata),
+    NftContract(NFTContractMetadata),
+    NftInstance(NftInstanceData),
+    SvgMedia(Option<SvgMediaConfig>),
+}
+
+impl MetadataSnapshot {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            MetadataSnapshot::Token(_) => "token",
+            MetadataSnapshot::NftContract(_) => "nft_contract",
+            MetadataSnapshot::NftInstance(_) => "nft_instance",
+            MetadataSnapshot::SvgMedia(_) => "svg_media",
+        }
+    }
+
+    pub fn reference(&self) -> Option<&str> {
+        match self {
+            MetadataSnapshot::Token(metadata) => metadata.reference.as_deref(),
+            MetadataSnapshot::NftContract(metadata) => metadata.reference.as_deref(),
+            MetadataSnapshot::NftInstance(metadata) => metadata.reference.as_deref(),
+            MetadataSnapshot::SvgMedia(_) => None,
+        }
+    }
+
+    pub fn reference_hash(&self) -> Option<&Base64VecU8> {
+        match self {
+            MetadataSnapshot::Token(metadata) => metadata.reference_hash.as_ref(),
+            MetadataSnapshot::NftContract(metadata) => metadata.reference_hash.as_ref(),
+            MetadataSnapshot::NftInstance(metadata) => metadata.reference_hash.as_ref(),
+            MetadataSnapshot::SvgMedia(_) => None,
+        }
+    }
+}
+
+/// Entry of the metadata audit history
+#[derive(BorshDeserialize, BorshSerialize, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MetadataChange {
+    pub updated_by: AccountId,
+    /// Block timestamp of the update in nanoseconds
+    pub timestamp: U64,
+    #[serde(flatten)]
+    pub metadata: MetadataSnapshot,
+}
+
+/// Panics unless hash is a 32 bytes sha256 digest
+pub fn assert_valid_hash(hash: &Base64VecU8, field: &str) {
+    assert_eq!(hash.0.len(), 32, "{} has to be 32 bytes", field);
+}
+
+/// Panics unless reference comes with a valid reference_hash.
+/// Since the referenced document lives off-chain, its hash is what
+/// allows clients to verify that it was not tampered with.
+pub fn assert_verifiable_reference(reference: Option<&str>, reference_hash: Option<&Base64VecU8>) {
+    if reference.is_some() {
+        let reference_hash = reference_hash.expect("reference_hash is required with reference");
+        assert_valid_hash(reference_hash, "reference_hash");
+    } else {
+        assert!(reference_hash.is_none(), "reference_hash requires reference");
+    }
+}
@@ -0,0 +1,183 @@
+//! State versioning and migration
+//!
+//! The contract state is a flat Borsh layout, so every deployed
+//! version must be able to read the layout of all versions before it.
+//! The layout version is stored under its own storage key, which is
+//! missing on contracts initialized by v1.0.0.
+//!
+//! Layouts are versioned per release rather than per field: every
+//! field added to [`Contract`] between two releases ships in the same
+//! version. v1.1.0 is still unreleased and covers every field added
+//! since v1.0.0, so its fields only need a default in the v1.0.0
+//! conversion.
+//!
+//! Once a release is deployed, the next change to [`Contract`] must
+//! keep its layout here as a frozen struct, add a variant to
+//! [`StateVersion`] and [`VersionedContract`] and implement its
+//! conversion into the current layout.
+
+use crate::minting::MintingPolicy;
+use crate::*;
+
+/// Storage key holding the current [`StateVersion`]
+pub const STATE_VERSION_KEY: &[u8] = b"STATE_VERSION";
+
+/// Every state layout the contract has been deployed with
+#[derive(
+    BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug,
+)]
+#[serde(crate = "near_sdk::serde")]
+pub enum StateVersion {
+    V1_0_0,
+    V1_1_0,
+}
+
+/// Layout version written by this code
+pub const CURRENT_STATE_VERSION: StateVersion = StateVersion::V1_1_0;
+
+/// Contract state as laid out by v1.0.0
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct ContractV1_0_0 {
+    pub ft_functionality: FungibleToken,
+    pub reward_token: AccountId,
+    pub reward_tokens_all_time_count_token: U128,
+    pub reward_tokens_all_time_count_near: U128,
+    pub contract_rps_token: U128,
+    pub contract_rps_near: U128,
+    pub accounts_rps: LookupMap<AccountId, RpsManager>,
+    pub token_metadata: LazyOption<FungibleTokenMetadata>,
+    pub nft_contract_metadata: LazyOption<NFTContractMetadata>,
+    pub nft_instance_metadata: LazyOption<NftInstanceDataV1_0_0>,
+}
+
+/// NFT instance data as laid out by v1.0.0
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct NftInstanceDataV1_0_0 {
+    pub title: String,
+    pub description: String,
+    pub media: String,
+    pub reference: String,
+}
+
+/// Contract state in any of its versions
+#[allow(clippy::large_enum_variant)]
+pub enum VersionedContract {
+    V1_0_0(ContractV1_0_0),
+    V1_1_0(Contract),
+}
+
+impl VersionedContract {
+    /// Reads the state layout indicated by STATE_VERSION_KEY
+    pub fn read() -> Self {
+        let version = env::storage_read(STATE_VERSION_KEY)
+            .map(|raw| {
+                StateVersion::try_from_slice(&raw).expect("Cannot deserialize state version")
+            })
+            .unwrap_or(StateVersion::V1_0_0);
+        match version {
+            StateVersion::V1_0_0 => VersionedContract::V1_0_0(
+                env::state_read().expect("Cannot deserialize v1.0.0 state"),
+            ),
+            StateVersion::V1_1_0 => VersionedContract::V1_1_0(
+                env::state_read().expect("Cannot deserialize v1.1.0 state"),
+            ),
+        }
+    }
+
+    /// Converts any previous layout into the current one.
+    /// owner_id is required when migrating from v1.0.0, which had
+    /// no notion of owner, and ignored otherwise.
+    pub fn into_current(self, owner_id: Option<AccountId>) -> Contract {
+        match self {
+            VersionedContract::V1_0_0(state) => {
+                let total_supply = state.ft_functionality.total_supply;
+                Contract {
+                    ft_functionality: state.ft_functionality,
+                    reward_token: state.reward_token,
+                    reward_tokens_all_time_count_token: state.reward_tokens_all_time_count_token,
+                    reward_tokens_all_time_count_near: state.reward_tokens_all_time_count_near,
+                    contract_rps_token: state.contract_rps_token,
+                    contract_rps_near: state.contract_rps_near,
+                    // v1.0.0 computed contract_rps from all time counts
+                    undistributed_rewards_token: U128(
+                        state
+                            .reward_tokens_all_time_count_token
+                            .0
+                            .saturating_sub(state.contract_rps_token.0 * total_supply),
+                    ),
+                    undistributed_rewards_near: U128(
+                        state
+                            .reward_tokens_all_time_count_near
+                            .0
+                            .saturating_sub(state.contract_rps_near.0 * total_supply),
+                    ),
+                    accounts_rps: state.accounts_rps,
+                    token_metadata: state.token_metadata,
+                    nft_contract_metadata: state.nft_contract_metadata,
+                    nft_instance_metadata: {
+                        let data = state.nft_instance_metadata.get().unwrap();
+                        LazyOption::new(
+                            StorageKey::InstanceNonFungibleTokenMetadata,
+                            Some(&NftInstanceData {
+                                title: data.title,
+                                description: data.description,
+                                media: data.media,
+                                media_hash: None,
+                                reference: Some(data.reference),
+                                reference_hash: None,
+                            }),
+                        )
+                    },
+                    owner_id: owner_id.expect("owner_id is required to migrate from v1.0.0"),
+                    role_members: LookupMap::new(StorageKey::RoleMembersMap),
+                    pause_flags: PauseFlags::default(),
+                    metadata_history: Vector::new(StorageKey::MetadataHistory),
+                    minting_policy: MintingPolicy::new(U128(total_supply), vec![]),
+                    // v1.0.0 could not enumerate holders, they are indexed
+                    // on their next balance change or through index_holders
+                    holders: UnorderedSet::new(StorageKey::Holders),
+                    unindexed_supply: U128(total_supply),
+                    reserve_token: U128(0),
+                    split: None,
+                    splits_count: 0,
+                    buyback: None,
+                    sale: None,
+                    sales_count: 0,
+                    sale_purchases: LookupMap::new(StorageKey::SalePurchases),
+                    auction: None,
+                    auctions_count: 0,
+                    auction_bids: LookupMap::new(StorageKey::AuctionBids),
+                    auction_bidders: LookupMap::new(StorageKey::AuctionBidders),
+                    rights_snapshots: LookupMap::new(StorageKey::RightsSnapshots),
+                    rights_subscriptions: LookupMap::new(StorageKey::RightsSubscriptions),
+                    buyout_policy: None,
+                    buyout: None,
+                    buyouts_count: 0,
+                    buyout_snapshots: LookupMap::new(StorageKey::BuyoutSnapshots),
+                    buyout_votes: LookupMap::new(StorageKey::BuyoutVotes),
+                    vault: None,
+                    position_approvals: LookupMap::new(StorageKey::PositionApprovals),
+                    royalties: None,
+                    svg_media: LazyOption::new(StorageKey::SvgMedia, None),
+                    tiers: LazyOption::new(StorageKey::Tiers, None),
+                    holder_tiers: LookupMap::new(StorageKey::HolderTiers),
+                    storage_deposits: LookupMap::new(StorageKey::StorageDeposits),
+                    share_allowances: LookupMap::new(StorageKey::ShareAllowances),
+                    transfer_restrictions: Default::default(),
+                    account_attributes: LookupMap::new(StorageKey::AccountAttributes),
+                }
+            }
+            VersionedContract::V1_1_0(state) => state,
+        }
+    }
+}
+
+impl Contract {
+    /// Records that the stored state follows the current layout
+    pub fn write_state_version() {
+        env::storage_write(
+            STATE_VERSION_KEY,
+            &CURRENT_STATE_VERSION.try_to_vec().unwrap(),
+        );
+    }
+}
@@ -0,0 +1,76 @@
+use crate::*;
+use near_sdk::json_types::U64;
+
+/// Amount of new shares that becomes mintable from a given time on
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MintAllowance {
+    /// Block timestamp in nanoseconds
+    pub available_from: U64,
+    pub amount: U128,
+}
+
+/// Limits on the issuance of new shares after initialization.
+///
+/// The supply can never exceed supply_cap. When schedule is not
+/// empty, the shares minted since initialization can never exceed
+/// the sum of the allowances already available.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MintingPolicy {
+    pub supply_cap: U128,
+    pub schedule: Vec<MintAllowance>,
+    /// Shares minted since initialization
+    pub minted: U128,
+}
+
+impl MintingPolicy {
+    pub fn new(supply_cap: U128, schedule: Vec<MintAllowance>) -> Self {
+        Self {
+            supply_cap,
+            schedule,
+            minted: U128(0),
+        }
+    }
+
+    /// Panics if the policy cannot apply to the current supply
+    pub fn assert_valid(&self, total_supply: u128) {
+        assert!(
+            self.supply_cap.0 >= total_supply,
+            "supply_cap cannot be lower than the current supply"
+        );
+        for allowance in self.schedule.iter() {
+            assert!(
+                allowance.amount.0 > 0,
+                "Minting schedule amounts must be greater than 0"
+            );
+        }
+    }
+
+    /// Expresses every amount in shares after a split
+    pub fn rescale(&mut self, numerator: u128, denominator: u128) {
+        let rescale = |amount: U128| U128(amount.0 * numerator / denominator);
+        self.supply_cap = rescale(self.supply_cap);
+        self.minted = rescale(self.minted);
+        for allowance in self.schedule.iter_mut() {
+            allowance.amount = rescale(allowance.amount);
+        }
+        self.schedule.retain(|allowance| allowance.amount.0 > 0);
+    }
+
+    /// Shares that can be minted at timestamp, given total_supply
+    pub fn mintable(&self, total_supply: u128, timestamp: u64) -> u128 {
+        let below_cap = self.supply_cap.0.saturating_sub(total_supply);
+        if self.schedule.is_empty() {
+            return below_cap;
+        }
+        let released = self
+            .schedule
+            .iter()
+            .filter(|allowance| allowance.available_from.0 <= timestamp)
+            .fold(0u128, |released, allowance| {
+                released.saturating_add(allowance.amount.0)
+            });
+        std::cmp::min(below_cap, released.saturating_sub(self.minted.0))
+    }
+}
@@ -0,0 +1,82 @@
+use crate::*;
+
+/// Features of the contract that can be halted independently
+/// by a pauser in case a bug is discovered.
+#[derive(
+    BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug,
+)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(rename_all = "snake_case")]
+pub enum PauseFeature {
+    /// Every movement of shares: transfers, minting and burning
+    Transfers,
+    /// Deposit of rewards in NEAR or in the reward token
+    Deposits,
+    /// Withdrawal of rewards through claim_rewards
+    Claims,
+}
+
+impl PauseFeature {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PauseFeature::Transfers => "transfers",
+            PauseFeature::Deposits => "deposits",
+            PauseFeature::Claims => "claims",
+        }
+    }
+}
+
+/// Current pause state of each feature
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Default, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PauseFlags {
+    pub transfers: bool,
+    pub deposits: bool,
+    pub claims: bool,
+}
+
+impl PauseFlags {
+    pub fn is_paused(&self, feature: &PauseFeature) -> bool {
+        match feature {
+            PauseFeature::Transfers => self.transfers,
+            PauseFeature::Deposits => self.deposits,
+            PauseFeature::Claims => self.claims,
+        }
+    }
+
+    pub fn set(&mut self, feature: &PauseFeature, paused: bool) {
+        match feature {
+            PauseFeature::Transfers => self.transfers = paused,
+            PauseFeature::Deposits => self.deposits = paused,
+            PauseFeature::Claims => self.claims = paused,
+        }
+    }
+}
+
+impl Contract {
+    /// Panics if feature is currently paused. Must be called at the
+    /// start of every entry point belonging to a pausable feature.
+    /// Transfers are also frozen for good once a buyout is accepted.
+    /// Transfers and deposits are also halted while a split is in
+    /// progress, since balances are then in mixed share units.
+    pub fn assert_not_paused(&self, feature: &PauseFeature) {
+        assert!(
+            !self.pause_flags.is_paused(feature),
+            "Contract {} are paused",
+            feature.as_str()
+        );
+        if *feature != PauseFeature::Claims {
+            assert!(
+                self.split.is_none(),
+                "Contract {} are paused until the split completes",
+                feature.as_str()
+            );
+        }
+        if *feature == PauseFeature::Transfers {
+            assert!(
+                self.buyout.as_ref().is_none_or(|buyout| !buyout.is_accepted()),
+                "Shares are frozen by an accepted buyout"
+            );
+        }
+    }
+}
@@ -0,0 +1,73 @@
+use crate::*;
+
+/// Administrative roles recognized by the contract.
+///
+/// There is always exactly one owner, who implicitly holds every
+/// other role. Admins implicitly hold every role except owner.
+/// The remaining roles are meant to be delegated to accounts that
+/// only need to perform a narrow set of actions.
+#[derive(
+    BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug,
+)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    Owner,
+    Admin,
+    Pauser,
+    MetadataManager,
+    ComplianceOfficer,
+    Minter,
+}
+
+impl Role {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Owner => "owner",
+            Role::Admin => "admin",
+            Role::Pauser => "pauser",
+            Role::MetadataManager => "metadata_manager",
+            Role::ComplianceOfficer => "compliance_officer",
+            Role::Minter => "minter",
+        }
+    }
+}
+
+/// Role checks used by every administrative method
+impl Contract {
+    /// Returns true if account_id holds role, either directly or
+    /// implicitly through the owner or admin roles.
+    pub fn has_role(&self, account_id: &AccountId, role: &Role) -> bool {
+        if *account_id == self.owner_id {
+            return true;
+        }
+        match role {
+            Role::Owner => false,
+            Role::Admin => self.is_role_member(account_id, &Role::Admin),
+            _ => {
+                self.is_role_member(account_id, &Role::Admin)
+                    || self.is_role_member(account_id, role)
+            }
+        }
+    }
+
+    /// Panics unless the predecessor holds role. Must be called at the
+    /// start of every method restricted to privileged accounts.
+    pub fn assert_role(&self, role: &Role) {
+        let caller = env::predecessor_account_id();
+        assert!(
+            self.has_role(&caller, role),
+            "{} does not have the {} role",
+            caller,
+            role.as_str()
+        );
+    }
+
+    /// Only checks explicit membership, without role implication
+    pub fn is_role_member(&self, account_id: &AccountId, role: &Role) -> bool {
+        self.role_members
+            .get(role)
+            .map(|members| members.contains(account_id))
+            .unwrap_or(false)
+    }
+}
@@ -0,0 +1,63 @@
+use crate::*;
+use std::collections::HashMap;
+
+/// Basis points of the price of a position representing the whole price
+pub const FULL_PRICE_BPS: u16 = 10_000;
+/// Royalties cannot take more than half of the price of a position
+pub const MAX_ROYALTY_BPS: u16 = 5_000;
+/// Accounts that can receive royalties besides the dividend pool
+pub const MAX_ROYALTY_ACCOUNTS: usize = 10;
+
+/// Royalties paid on every sale of a position through a marketplace
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RoyaltyConfig {
+    /// Part of the price, in basis points, paid to each account,
+    /// such as the artist
+    pub accounts: HashMap<AccountId, u16>,
+    /// Part of the price, in basis points, distributed as dividends
+    /// among every holder
+    pub dividend_pool_bps: u16,
+    /// Account collecting the dividend pool part of payouts, which it
+    /// deposits through deposit_royalties. Marketplaces pay payouts
+    /// by plain transfers, which the contract itself could not tell
+    /// apart from its other funds.
+    pub dividend_pool_account_id: AccountId,
+}
+
+impl RoyaltyConfig {
+    pub fn assert_valid(&self) {
+        assert!(
+            self.accounts.len() <= MAX_ROYALTY_ACCOUNTS,
+            "Royalties can be paid to at most {} accounts",
+            MAX_ROYALTY_ACCOUNTS
+        );
+        assert!(
+            self.accounts.values().all(|bps| *bps > 0),
+            "Royalty of each account must be greater than 0"
+        );
+        assert!(
+            self.dividend_pool_account_id != env::current_account_id(),
+            "Dividend pool royalties cannot be paid to the contract itself"
+        );
+        let total_bps: u32 = self.accounts.values().map(|bps| *bps as u32).sum::<u32>()
+            + self.dividend_pool_bps as u32;
+        assert!(
+            total_bps <= MAX_ROYALTY_BPS as u32,
+            "Royalties cannot exceed {} basis points",
+            MAX_ROYALTY_BPS
+        );
+    }
+
+    /// Part of balance owed to a royalty of bps basis points
+    pub fn royalty(balance: u128, bps: u16) -> u128 {
+        balance * bps as u128 / FULL_PRICE_BPS as u128
+    }
+}
+
+/// Amounts owed to each account on sale of a position (NEP-199)
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Payout {
+    pub payout: HashMap<AccountId, U128>,
+}
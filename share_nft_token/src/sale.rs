@@ -0,0 +1,125 @@
+use crate::*;
+use near_sdk::json_types::U64;
+
+/// Unsigned 256 bit integer holding products of two amounts
+mod wide {
+    #![allow(clippy::all)]
+    uint::construct_uint! {
+        pub struct U256(4);
+    }
+}
+use wide::U256;
+
+/// Currency in which shares of a sale are paid for
+#[derive(
+    BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug,
+)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(rename_all = "snake_case")]
+pub enum SaleCurrency {
+    RewardToken,
+    Near,
+}
+
+/// Terms of a fixed-price sale, set by an admin
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SaleConfig {
+    /// Account funding the sale allocation, receives unsold shares back
+    pub issuer: AccountId,
+    /// Account receiving the proceeds
+    pub treasury: AccountId,
+    pub currency: SaleCurrency,
+    /// Price of one share in currency
+    pub price: U128,
+    /// Block timestamps in nanoseconds
+    pub start: U64,
+    pub end: U64,
+    /// Shares each account must buy at least, across its purchases
+    pub min_per_account: U128,
+    /// Shares each account can buy at most, across its purchases
+    pub max_per_account: U128,
+    /// Shares that can be sold at most
+    pub hard_cap: U128,
+}
+
+impl SaleConfig {
+    pub fn assert_valid(&self) {
+        assert!(self.price.0 > 0, "price must be greater than 0");
+        assert!(self.start.0 < self.end.0, "start must be before end");
+        assert!(
+            self.end.0 > env::block_timestamp(),
+            "end must be in the future"
+        );
+        assert!(self.hard_cap.0 > 0, "hard_cap must be greater than 0");
+        assert!(
+            self.min_per_account.0 <= self.max_per_account.0 && self.max_per_account.0 > 0,
+            "max_per_account must be positive and at least min_per_account"
+        );
+    }
+}
+
+/// Pre-emptive phase of a rights offering, during which only holders
+/// at creation can buy, each up to their pro-rata part of the hard cap
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RightsPhase {
+    /// Block timestamp in nanoseconds after which the remainder
+    /// is open to the public
+    pub end: U64,
+    /// Shares held by accounts other than the contract on creation
+    pub snapshot_supply: U128,
+}
+
+/// State of a fixed-price sale
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Sale {
+    pub id: u64,
+    pub config: SaleConfig,
+    /// Shares escrowed by the issuer for the sale,
+    /// or to be minted for rights offerings
+    pub allocation: U128,
+    pub sold: U128,
+    /// Payments received, in config.currency
+    pub proceeds: U128,
+    /// Set for rights offerings, whose shares are minted when bought
+    /// instead of escrowed by the issuer
+    pub rights: Option<RightsPhase>,
+}
+
+impl Sale {
+    /// Shares still available to buyers
+    pub fn available(&self) -> u128 {
+        std::cmp::min(self.config.hard_cap.0, self.allocation.0) - self.sold.0
+    }
+
+    pub fn is_open(&self, timestamp: u64) -> bool {
+        timestamp >= self.config.start.0 && timestamp < self.config.end.0
+    }
+
+    /// Whether only holders at creation can currently buy
+    pub fn is_rights_phase(&self, timestamp: u64) -> bool {
+        self.rights
+            .as_ref()
+            .is_some_and(|rights| timestamp < rights.end.0)
+    }
+
+    /// Shares an account holding snapshot_balance on creation
+    /// of a rights offering is entitled to
+    pub fn entitlement(&self, snapshot_balance: u128) -> u128 {
+        match &self.rights {
+            // widened, amounts of tokens with 24 decimals overflow u128
+            Some(rights) => (U256::from(snapshot_balance) * U256::from(self.config.hard_cap.0)
+                / U256::from(rights.snapshot_supply.0))
+            .as_u128(),
+            None => 0,
+        }
+    }
+
+    /// Sale can be finalized once ended or sold out
+    pub fn is_over(&self, timestamp: u64) -> bool {
+        timestamp >= self.config.end.0
+            || (self.allocation.0 > 0 && self.available() == 0)
+    }
+}
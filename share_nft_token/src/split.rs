@@ -0,0 +1,47 @@
+use crate::*;
+
+/// Split in progress, rescaling holders in batches through process_split.
+/// Holders are processed from the end of holders down, so that holders
+/// removed by the split only move holders already processed.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Split {
+    pub id: u64,
+    /// Ratio reduced to lowest terms
+    pub numerator: u32,
+    pub denominator: u32,
+    /// Reward tokens paid per share after the split for fractions of
+    /// a share, required unless denominator is 1
+    pub cash_out_price: Option<U128>,
+    /// Reward tokens taken out of the reserve to cover the cash out
+    /// of every holder, the unused part is returned on completion
+    pub cash_out_budget: U128,
+    /// Reward tokens cashed out so far
+    pub cash_out: U128,
+    /// Holders still to rescale, those at the first indexes of holders
+    pub remaining: u64,
+}
+
+impl Split {
+    /// Largest cash out of a single holder, for a fraction of
+    /// denominator - 1 out of denominator
+    pub fn max_cash_out(&self) -> u128 {
+        self.cash_out_price.map_or(0, |price| {
+            price.0 * (self.denominator as u128 - 1) / self.denominator as u128
+        })
+    }
+
+    /// Balance after the split and the cash out of the fraction of a
+    /// share that cannot be represented
+    pub fn rescale(&self, balance: u128) -> (u128, u128) {
+        let scaled = balance
+            .checked_mul(self.numerator as u128)
+            .expect("Balance overflow");
+        let denominator = self.denominator as u128;
+        let fraction = scaled % denominator;
+        let cash_out = self
+            .cash_out_price
+            .map_or(0, |price| fraction * price.0 / denominator);
+        (scaled / denominator, cash_out)
+    }
+}
@@ -0,0 +1,83 @@
+use crate::*;
+use near_sdk::base64;
+
+/// Settings of the on-chain SVG rendered as media of each holder's NFT
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SvgMediaConfig {
+    /// URL of the artwork thumbnail embedded in the SVG,
+    /// defaults to the media of the holder NFTs
+    pub thumbnail: Option<String>,
+    /// Color of the frame, text and badge, as a CSS color name
+    /// or hex code
+    pub color: String,
+}
+
+impl SvgMediaConfig {
+    pub fn assert_valid(&self) {
+        assert!(
+            !self.color.is_empty()
+                && self.color.len() <= 32
+                && self.color.chars().all(|c| c.is_ascii_alphanumeric() || c == '#'),
+            "color must be a CSS color name or hex code"
+        );
+    }
+}
+
+/// Data of a holder's position displayed in its SVG
+pub struct PositionSvg<'a> {
+    pub title: &'a str,
+    pub thumbnail: &'a str,
+    pub color: &'a str,
+    pub balance: u128,
+    pub total_supply: u128,
+    /// Label of the holder's tier, if any
+    pub badge: Option<&'a str>,
+}
+
+impl PositionSvg<'_> {
+    /// Part of the supply held, with two decimals
+    pub fn percentage(&self) -> String {
+        let bps = self.balance * 10_000 / self.total_supply;
+        format!("{}.{:02}%", bps / 100, bps % 100)
+    }
+
+    pub fn render(&self) -> String {
+        let badge = match self.badge {
+            Some(badge) => format!(
+                r#"<rect x="312" y="408" width="168" height="48" rx="24" fill="{color}"/><text x="396" y="440" text-anchor="middle" font-family="sans-serif" font-size="22" fill="white">{badge}</text>"#,
+                color = self.color,
+                badge = escape_xml(badge),
+            ),
+            None => String::new(),
+        };
+        format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="512" height="512" viewBox="0 0 512 512"><rect x="4" y="4" width="504" height="504" fill="white" stroke="{color}" stroke-width="8"/><image href="{thumbnail}" x="32" y="32" width="448" height="352" preserveAspectRatio="xMidYMid slice"/><text x="32" y="428" font-family="sans-serif" font-size="26" fill="{color}">{balance} shares</text><text x="32" y="468" font-family="sans-serif" font-size="20" fill="{color}">{percentage} of {title}</text>{badge}</svg>"#,
+            color = self.color,
+            thumbnail = escape_xml(self.thumbnail),
+            balance = self.balance,
+            percentage = self.percentage(),
+            title = escape_xml(self.title),
+            badge = badge,
+        )
+    }
+
+    /// SVG as a data URL, along with the sha256 of the SVG
+    pub fn data_url(&self) -> (String, Base64VecU8) {
+        let svg = self.render();
+        let media_hash = Base64VecU8(env::sha256(svg.as_bytes()));
+        (
+            format!("data:image/svg+xml;base64,{}", base64::encode(svg)),
+            media_hash,
+        )
+    }
+}
+
+/// Escapes text to be embedded in SVG markup
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
@@ -0,0 +1,72 @@
+use crate::metadata::{assert_valid_hash, assert_verifiable_reference};
+use crate::*;
+
+/// Basis points of the supply representing all shares
+pub const FULL_SUPPLY_BPS: u16 = 10_000;
+/// Tiers that can be configured at most
+pub const MAX_TIERS: usize = 10;
+/// Characters a tier name can have at most
+pub const MAX_TIER_NAME_LEN: usize = 32;
+
+/// Rank of holders owning at least a part of the supply, displayed
+/// in their NFT and used to gate perks
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct HolderTier {
+    /// Unique name of the tier, shown as badge of the SVG media
+    pub name: String,
+    /// Part of the supply, in basis points, holders must own at least
+    pub min_bps: u16,
+    /// Title of the NFT of holders in the tier
+    pub title: String,
+    pub media: String,
+    /// sha256 of the file in media
+    #[serde(default)]
+    pub media_hash: Option<Base64VecU8>,
+    pub reference: Option<String>,
+    /// sha256 of the document in reference
+    #[serde(default)]
+    pub reference_hash: Option<Base64VecU8>,
+}
+
+impl HolderTier {
+    /// Whether balance out of total_supply reaches the tier
+    pub fn is_reached(&self, balance: u128, total_supply: u128) -> bool {
+        balance * FULL_SUPPLY_BPS as u128 >= self.min_bps as u128 * total_supply
+    }
+}
+
+/// Panics unless tiers have valid metadata, unique names and
+/// strictly decreasing thresholds
+pub fn assert_valid_tiers(tiers: &[HolderTier]) {
+    assert!(
+        tiers.len() <= MAX_TIERS,
+        "At most {} tiers can be configured",
+        MAX_TIERS
+    );
+    for (index, tier) in tiers.iter().enumerate() {
+        assert!(
+            !tier.name.is_empty() && tier.name.len() <= MAX_TIER_NAME_LEN,
+            "Tier name must have between 1 and {} characters",
+            MAX_TIER_NAME_LEN
+        );
+        assert!(
+            tier.min_bps > 0 && tier.min_bps <= FULL_SUPPLY_BPS,
+            "min_bps must be between 1 and {}",
+            FULL_SUPPLY_BPS
+        );
+        assert!(
+            index == 0 || tier.min_bps < tiers[index - 1].min_bps,
+            "Tiers must be sorted by strictly decreasing min_bps"
+        );
+        assert!(
+            tiers[..index].iter().all(|other| other.name != tier.name),
+            "Tier {} is configured twice",
+            tier.name
+        );
+        assert_verifiable_reference(tier.reference.as_deref(), tier.reference_hash.as_ref());
+        if let Some(media_hash) = &tier.media_hash {
+            assert_valid_hash(media_hash, "media_hash");
+        }
+    }
+}
@@ -0,0 +1,40 @@
+use crate::*;
+
+/// NEP-171 token of another contract represented by the shares
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct VaultNft {
+    pub nft_contract_id: AccountId,
+    pub token_id: TokenId,
+}
+
+/// Custody of the underlying NFT by the share contract
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Vault {
+    pub nft: VaultNft,
+    /// Whether the contract currently holds the NFT
+    pub custodied: bool,
+    /// Account that redeemed every share and can claim the NFT
+    pub redeemed_by: Option<AccountId>,
+    /// Account the NFT was transferred to, once released
+    pub released_to: Option<AccountId>,
+}
+
+impl Vault {
+    pub fn new(nft: VaultNft) -> Self {
+        Self {
+            nft,
+            custodied: false,
+            redeemed_by: None,
+            released_to: None,
+        }
+    }
+
+    /// NEP-177 extra field of share NFTs while the NFT is custodied
+    pub fn token_extra(&self) -> Option<String> {
+        self.custodied.then(|| {
+            near_sdk::serde_json::json!({ "vault": self.nft }).to_string()
+        })
+    }
+}
@@ -38,16 +38,18 @@ mod tests {
     owner
       .call(&worker, share_token.id(), "new")
       .args_json(json!({
+        "args": {
+          "version": "1",
           "owner_id": owner.id(),
           "total_supply": share_token_supply.to_string(),
           "reward_token": ft_token.id(),
           "token_name": "name".to_string(),
           "token_symbol": "name".to_string(),
-          "token_icon": "name".to_string(),
-          "token_reference": "name".to_string(),
+          "token_icon": "data:image/svg+xml,name".to_string(),
           "nft_instance_name": "name".to_string(),
           "nft_instance_description": "name".to_string(),
           "nft_instance_media_url": "name".to_string(),
+        }
       }))?
       .transact()
       .await?;
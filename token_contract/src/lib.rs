@@ -16,7 +16,6 @@ NOTES:
     keys on its account.
 */
 
-use near_contract_standards;
 use near_contract_standards::fungible_token::metadata::{
   FungibleTokenMetadata, FungibleTokenMetadataProvider,
 };
@@ -38,9 +37,6 @@ pub struct Contract {
 
 #[near_bindgen]
 impl Contract {
-  /// Initializes the contract with the given total supply owned by the given `owner_id` with
-  /// default metadata (for example purposes only).
-
   /// Initializes the contract with the given total supply owned by the given `owner_id` with
   /// the given fungible token metadata.
   #[init]